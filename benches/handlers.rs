@@ -0,0 +1,50 @@
+//! Bytes allocated while handling a request whose handler returns a large `String`
+//!
+//! Run with `cargo bench --bench handlers`. The handler's own allocation of the body is counted
+//! as well, so anything past the body size is what torus added on the way to the response
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use torus_http::prelude::*;
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const BODY_SIZE: usize = 1024 * 1024;
+const REQUESTS: usize = 100;
+
+fn main() {
+    let server = HttpServer::new().get("/", |_| "x".repeat(BODY_SIZE));
+    let request = HttpRequest::from_str("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .expect("failed parsing request");
+
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    for _ in 0..REQUESTS {
+        black_box(server.handle(request.clone()));
+    }
+    let per_request = (ALLOCATED.load(Ordering::Relaxed) - allocated) / REQUESTS;
+
+    println!(
+        "{BODY_SIZE} byte body: {per_request} bytes allocated per request, {} beyond the body",
+        per_request.saturating_sub(BODY_SIZE)
+    );
+}
@@ -0,0 +1,58 @@
+//! Requests per second for a tiny response over a single keep-alive connection
+//!
+//! Run with `cargo bench --bench keep_alive`
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    thread,
+    time::Instant,
+};
+
+use torus_http::prelude::*;
+
+const REQUESTS: u32 = 20_000;
+
+fn main() {
+    let server = HttpServer::new()
+        .get("/", |_| "hi")
+        .bind(("127.0.0.1", 0))
+        .expect("failed binding");
+    let port = server.port();
+    thread::spawn(move || server.listen().expect("failed listening"));
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("failed connecting");
+    let mut buf = [0; 1024];
+    let start = Instant::now();
+    for _ in 0..REQUESTS {
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .expect("failed writing request");
+        read_response(&mut stream, &mut buf);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{REQUESTS} requests in {elapsed:?} ({:.0} requests/sec)",
+        f64::from(REQUESTS) / elapsed.as_secs_f64()
+    );
+}
+
+/// Reads exactly one response, relying on the server always sending `Content-Length`
+fn read_response(stream: &mut TcpStream, buf: &mut [u8]) {
+    let mut len = 0;
+    loop {
+        len += stream.read(&mut buf[len..]).expect("failed reading response");
+        let Some(head_end) = buf[..len].windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let head = std::str::from_utf8(&buf[..head_end]).expect("non utf8 response head");
+        let content_length: usize = head
+            .lines()
+            .find_map(|line| line.strip_prefix("Content-Length: "))
+            .and_then(|v| v.parse().ok())
+            .expect("response without Content-Length");
+        if len >= head_end + 4 + content_length {
+            return;
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Heap allocations made by the route lookup alone, against a larger route table
+//!
+//! Run with `cargo bench --bench routing`. Requests are built up front and go through
+//! `HttpServer::handle`, a global middleware marks where the lookup starts and a route
+//! middleware where it ended, so only what happens in between is counted
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use torus_http::prelude::*;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+/// Allocation count when the last global middleware let the request through
+static LOOKUP_START: AtomicUsize = AtomicUsize::new(0);
+/// Allocations made between there and the route middleware, summed over every request
+static LOOKUP_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const ROUTES: usize = 100;
+const REQUESTS: usize = 10_000;
+
+fn main() {
+    let mut server = HttpServer::new().add_middleware(|req: HttpRequest| {
+        LOOKUP_START.store(ALLOCATIONS.load(Ordering::Relaxed), Ordering::Relaxed);
+        req
+    });
+    let found = |req: HttpRequest| {
+        let allocations =
+            ALLOCATIONS.load(Ordering::Relaxed) - LOOKUP_START.load(Ordering::Relaxed);
+        LOOKUP_ALLOCATIONS.fetch_add(allocations, Ordering::Relaxed);
+        req
+    };
+    for i in 0..ROUTES {
+        for method in [
+            HttpMethod::Get,
+            HttpMethod::Post,
+            HttpMethod::other("PURGE"),
+        ] {
+            server = server.route_with_middleware(format!("/route/{i}"), method, [found], |_| "hi");
+        }
+        server = server.route_with_middleware(
+            format!("/pattern/{i}/:id"),
+            HttpMethod::Get,
+            [found],
+            |_| "hi",
+        );
+    }
+
+    bench(&server, "static GET", |i| format!("GET /route/{i}"));
+    bench(&server, "static PURGE", |i| format!("PURGE /route/{i}"));
+    // the pattern's captured params are the only allocations routing has to make
+    bench(&server, "pattern GET", |i| format!("GET /pattern/{i}/7"));
+}
+
+fn bench(server: &HttpServer, name: &str, request_line: impl Fn(usize) -> String) {
+    let requests: Vec<HttpRequest> = (0..ROUTES)
+        .map(|i| {
+            HttpRequest::from_str(&format!(
+                "{} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+                request_line(i)
+            ))
+            .expect("failed parsing request")
+        })
+        .collect();
+
+    LOOKUP_ALLOCATIONS.store(0, Ordering::Relaxed);
+    for request in requests.iter().cycle().take(REQUESTS) {
+        black_box(server.handle(request.clone()));
+    }
+    #[allow(clippy::cast_precision_loss)] // far below 2^52
+    let per_request = LOOKUP_ALLOCATIONS.load(Ordering::Relaxed) as f64 / REQUESTS as f64;
+    println!("{name}: {per_request:.1} allocations per lookup");
+}
@@ -0,0 +1,38 @@
+//! Serializing responses with `into_bytes` against `write_to` into a reused buffer
+//!
+//! Run with `cargo bench --bench serialize`. `into_bytes` consumes the response, so its numbers
+//! include cloning it, which is what callers holding on to a response pay as well
+use std::{hint::black_box, time::Instant};
+
+use torus_http::prelude::*;
+
+const ROUNDS: usize = 1_000;
+
+fn main() {
+    for size in [1024, 1024 * 1024] {
+        let response = HttpResponse::new()
+            .set_body(vec![b'x'; size])
+            .insert_header("Content-Type", "text/plain");
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            black_box(black_box(response.clone()).into_bytes());
+        }
+        let into_bytes = start.elapsed();
+
+        let mut buf = Vec::new();
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            buf.clear();
+            black_box(&response)
+                .write_to(&mut buf)
+                .expect("writing into a Vec can't fail");
+            black_box(&buf);
+        }
+        let write_to = start.elapsed();
+
+        println!(
+            "{size} byte body, {ROUNDS} rounds: into_bytes {into_bytes:?}, write_to {write_to:?}"
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! Derive and route macros for `torus-http`, use them through its `derive` feature
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, Fields, ItemFn, LitInt, LitStr, Path, PathArguments, Token, Type,
+    parse::Parser, parse_macro_input, punctuated::Punctuated,
+};
+
+/// Implements `torus_http::query::QueryParams` for a struct with named fields
+///
+/// Every field is read from the query parameter of the same name, or the one given with
+/// `#[query(rename = "...")]`. Values are parsed with `FromStr`, `Option` fields may be absent.
+///
+/// # Example:
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use torus_http::{prelude::*, query::QueryExtractError};
+/// use torus_http_macros::QueryParams;
+///
+/// #[derive(QueryParams)]
+/// struct Search {
+///     q: String,
+///     page: u32,
+///     #[query(rename = "sort_by")]
+///     order: Option<String>,
+/// }
+///
+/// let req = HttpRequest::from_str("GET /search?q=torus&page=2&sort_by=date HTTP/1.1\r\n\r\n").unwrap();
+/// let search: Search = req.query_params().unwrap();
+/// assert_eq!((search.q.as_str(), search.page), ("torus", 2));
+/// assert_eq!(search.order.as_deref(), Some("date"));
+///
+/// // optional parameters can be left out
+/// let req = HttpRequest::from_str("GET /search?q=torus&page=1 HTTP/1.1\r\n\r\n").unwrap();
+/// assert_eq!(req.query_params::<Search>().unwrap().order, None);
+///
+/// // required ones can't
+/// let req = HttpRequest::from_str("GET /search?page=1 HTTP/1.1\r\n\r\n").unwrap();
+/// assert_eq!(
+///     req.query_params::<Search>().err(),
+///     Some(QueryExtractError::Missing { name: "q".into() })
+/// );
+///
+/// let req = HttpRequest::from_str("GET /search?q=torus&page=two HTTP/1.1\r\n\r\n").unwrap();
+/// assert_eq!(
+///     req.query_params::<Search>().err(),
+///     Some(QueryExtractError::Invalid { name: "page".into(), value: "two".into() })
+/// );
+/// ```
+#[proc_macro_derive(QueryParams, attributes(query))]
+pub fn derive_query_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_query_params(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_query_params(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "QueryParams can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "QueryParams needs a struct with named fields",
+        ));
+    };
+
+    let mut inits = Vec::new();
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named fields have names");
+        let mut name = ident.to_string();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("query"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `rename = \"...\"`"))
+                }
+            })?;
+        }
+        let extract = if is_option(&field.ty) {
+            quote!(::torus_http::query::optional(params, #name)?)
+        } else {
+            quote!(::torus_http::query::required(params, #name)?)
+        };
+        inits.push(quote!(#ident: #extract));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::torus_http::query::QueryParams for #ident #ty_generics #where_clause {
+            fn extract(
+                params: &::std::collections::HashMap<::std::string::String, ::std::string::String>,
+            ) -> ::std::result::Result<Self, ::torus_http::query::QueryExtractError> {
+                ::std::result::Result::Ok(Self { #(#inits),* })
+            }
+        }
+    })
+}
+
+/// Whether a field is an `Option<T>`, judging by the name since macros can't see types
+fn is_option(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.path.segments.last().is_some_and(|segment| {
+        segment.ident == "Option" && matches!(segment.arguments, PathArguments::AngleBracketed(_))
+    })
+}
+
+/// Implements `torus_http::response::Response` by serializing the value as json, needs a
+/// `serde::Serialize` implementation
+///
+/// Responses go out as `200 OK` with `Content-Type: application/json`, change either with
+/// `#[response(status = 201, content_type = "...")]`. Status codes torus doesn't know are
+/// rejected at compile time.
+///
+/// # Example:
+///
+/// ```rust
+/// use serde::Serialize;
+/// use torus_http::{prelude::*, status::SuccessResponse};
+/// use torus_http_macros::Response;
+///
+/// #[derive(Serialize, Response)]
+/// #[response(status = 201)]
+/// struct User {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// let user = User { id: 7, name: "ferris".into() };
+/// assert_eq!(user.status_code(), SuccessResponse::Created.into());
+/// let res = user.into_response();
+/// assert_eq!(res.status, SuccessResponse::Created.into());
+/// assert_eq!(res.headers["Content-Type"], "application/json");
+/// assert_eq!(res.body_str(), Some(r#"{"id":7,"name":"ferris"}"#));
+///
+/// #[derive(Serialize, Response)]
+/// #[response(content_type = "application/vnd.api+json")]
+/// struct Empty {}
+///
+/// let res = Empty {}.into_response();
+/// assert_eq!(res.status, HttpStatus::OK);
+/// assert_eq!(res.headers["Content-Type"], "application/vnd.api+json");
+/// ```
+///
+/// Types that can't be serialized don't compile:
+///
+/// ```compile_fail
+/// use torus_http_macros::Response;
+///
+/// #[derive(Response)]
+/// struct NotSerialize;
+/// ```
+///
+/// Neither do unknown status codes:
+///
+/// ```compile_fail
+/// use serde::Serialize;
+/// use torus_http_macros::Response;
+///
+/// #[derive(Serialize, Response)]
+/// #[response(status = 299)]
+/// struct Odd {}
+///
+/// let _ = torus_http::response::Response::into_response(Odd {});
+/// ```
+#[proc_macro_derive(Response, attributes(response))]
+pub fn derive_response(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_response(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_response(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut status = 200_u16;
+    let mut content_type = String::from("application/json");
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("response"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("status") {
+                status = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                Ok(())
+            } else if meta.path.is_ident("content_type") {
+                content_type = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("expected `status = ...` or `content_type = \"...\"`"))
+            }
+        })?;
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let unknown = format!("unknown status code {status}");
+    let status = quote! {
+        const STATUS: ::torus_http::status::HttpStatus =
+            ::torus_http::status::HttpStatus::from_code(#status).expect(#unknown);
+    };
+    Ok(quote! {
+        impl #impl_generics ::torus_http::response::Response for #ident #ty_generics #where_clause {
+            fn into_response(self) -> ::torus_http::response::HttpResponse {
+                #status
+                ::torus_http::response::__private::json_response(&self, STATUS, #content_type)
+            }
+
+            fn status_code(&self) -> ::torus_http::status::HttpStatus {
+                #status
+                STATUS
+            }
+        }
+    })
+}
+
+/// Turns a free function into a `GET` route, collect it with [`routes!`] and hand the result to
+/// `HttpServer::register`
+///
+/// The function itself stays as it is and can still be called directly. The path may use the
+/// `:name`, `*` and `**` placeholders of `torus_http::pattern` and is checked at compile time.
+/// [`post`], [`put`], [`patch`], [`delete`], [`head`] and [`options`] work the same way.
+///
+/// # Example:
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use torus_http::{prelude::*, testing::TestClient};
+/// use torus_http_macros::{get, post, routes};
+///
+/// #[get("/users")]
+/// fn list_users(_req: HttpRequest) -> &'static str {
+///     "ferris, corro"
+/// }
+///
+/// #[get("/users/:id")]
+/// fn show_user(req: HttpRequest) -> String {
+///     format!("user {}", req.param("id").unwrap_or("?"))
+/// }
+///
+/// #[post("/users")]
+/// fn create_user(req: HttpRequest) -> String {
+///     format!("created {}", req.body_str().unwrap_or_default())
+/// }
+///
+/// mod health {
+///     use torus_http::prelude::*;
+///     use torus_http_macros::get;
+///
+///     #[get("/health")]
+///     pub fn check(_req: HttpRequest) -> &'static str {
+///         "ok"
+///     }
+/// }
+///
+/// let client = TestClient::new(
+///     HttpServer::new().register(routes![list_users, show_user, create_user, health::check]),
+/// );
+/// assert_eq!(client.get("/users").send().body_str(), Some("ferris, corro"));
+/// assert_eq!(client.get("/users/7").send().body_str(), Some("user 7"));
+/// assert_eq!(client.post("/users").body("corro").send().body_str(), Some("created corro"));
+/// assert_eq!(client.get("/health").send().body_str(), Some("ok"));
+///
+/// // the functions are still there
+/// let req = HttpRequest::from_str("GET /users HTTP/1.1\r\n\r\n").unwrap();
+/// assert_eq!(list_users(req), "ferris, corro");
+/// ```
+///
+/// Malformed paths don't compile:
+///
+/// ```compile_fail
+/// use torus_http::prelude::*;
+/// use torus_http_macros::get;
+///
+/// #[get("/users/:")]
+/// fn show_user(_req: HttpRequest) -> &'static str {
+///     "no name"
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use torus_http::prelude::*;
+/// use torus_http_macros::get;
+///
+/// #[get("/users/{id}")]
+/// fn show_user(_req: HttpRequest) -> &'static str {
+///     "wrong syntax"
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use torus_http::prelude::*;
+/// use torus_http_macros::get;
+///
+/// #[get("/my users")]
+/// fn users(_req: HttpRequest) -> &'static str {
+///     "spaces"
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
+    route_attribute(&quote!(Get), attr, item)
+}
+
+/// Turns a free function into a `POST` route, see [`get`]
+#[proc_macro_attribute]
+pub fn post(attr: TokenStream, item: TokenStream) -> TokenStream {
+    route_attribute(&quote!(Post), attr, item)
+}
+
+/// Turns a free function into a `PUT` route, see [`get`]
+#[proc_macro_attribute]
+pub fn put(attr: TokenStream, item: TokenStream) -> TokenStream {
+    route_attribute(&quote!(Put), attr, item)
+}
+
+/// Turns a free function into a `PATCH` route, see [`get`]
+#[proc_macro_attribute]
+pub fn patch(attr: TokenStream, item: TokenStream) -> TokenStream {
+    route_attribute(&quote!(Patch), attr, item)
+}
+
+/// Turns a free function into a `DELETE` route, see [`get`]
+#[proc_macro_attribute]
+pub fn delete(attr: TokenStream, item: TokenStream) -> TokenStream {
+    route_attribute(&quote!(Delete), attr, item)
+}
+
+/// Turns a free function into a `HEAD` route, see [`get`]
+#[proc_macro_attribute]
+pub fn head(attr: TokenStream, item: TokenStream) -> TokenStream {
+    route_attribute(&quote!(Head), attr, item)
+}
+
+/// Turns a free function into an `OPTIONS` route, see [`get`]
+#[proc_macro_attribute]
+pub fn options(attr: TokenStream, item: TokenStream) -> TokenStream {
+    route_attribute(&quote!(Options), attr, item)
+}
+
+/// Collects functions marked with [`get`], [`post`], ... into a `Vec<RouteDef>` for
+/// `HttpServer::register`, see [`get`] for an example
+#[proc_macro]
+pub fn routes(input: TokenStream) -> TokenStream {
+    let paths = match Punctuated::<Path, Token![,]>::parse_terminated.parse(input) {
+        Ok(paths) => paths,
+        Err(err) => return err.into_compile_error().into(),
+    };
+    let defs = paths.into_iter().map(|mut path| {
+        // syn doesn't parse paths without segments
+        if let Some(last) = path.segments.last_mut() {
+            last.ident = route_def_ident(&last.ident);
+        }
+        quote!(#path())
+    });
+    quote!(::std::vec![#(#defs),*]).into()
+}
+
+fn route_attribute(
+    method: &proc_macro2::TokenStream,
+    attr: TokenStream,
+    item: TokenStream,
+) -> TokenStream {
+    let path = parse_macro_input!(attr as LitStr);
+    let function = parse_macro_input!(item as ItemFn);
+    if let Err(reason) = validate_path(&path.value()) {
+        return syn::Error::new(path.span(), reason)
+            .into_compile_error()
+            .into();
+    }
+
+    let vis = &function.vis;
+    let ident = &function.sig.ident;
+    let def = route_def_ident(ident);
+    quote! {
+        #function
+
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        #vis fn #def() -> ::torus_http::server::RouteDef {
+            ::torus_http::server::RouteDef::new(
+                #path,
+                ::torus_http::method::HttpMethod::#method,
+                ::std::concat!(::std::module_path!(), "::", ::std::stringify!(#ident)),
+                #ident,
+            )
+        }
+    }
+    .into()
+}
+
+/// The function generated next to a route handler, which [`routes!`] calls
+fn route_def_ident(handler: &syn::Ident) -> syn::Ident {
+    format_ident!("__torus_route_{}", handler)
+}
+
+/// The rules of `torus_http::pattern`, plus a few to catch typos early
+fn validate_path(path: &str) -> Result<(), String> {
+    let Some(rest) = path.strip_prefix('/') else {
+        return Err("route paths have to start with `/`".into());
+    };
+    if let Some(c) = path.chars().find(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("route paths can't contain {c:?}"));
+    }
+
+    let parts: Vec<&str> = rest.split('/').collect();
+    let mut names = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        if part.contains(['{', '}']) {
+            return Err("parameters are written as `:name`, not `{name}`".into());
+        }
+        if *part == "**" && i + 1 != parts.len() {
+            return Err("`**` has to be the last segment".into());
+        }
+        let Some(name) = part.strip_prefix(':') else {
+            continue;
+        };
+        if name.is_empty() {
+            return Err("parameter without a name".into());
+        }
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!(
+                "parameter `{name}` may only contain letters, digits and `_`"
+            ));
+        }
+        if names.contains(&name) {
+            return Err(format!("parameter `{name}` is used twice"));
+        }
+        names.push(name);
+    }
+    Ok(())
+}
@@ -0,0 +1,428 @@
+//! A small blocking http/1.1 client, for tests and talking to other services
+//!
+//! Requests go out through the same serialization as the ones built by
+//! [`TestClient`](crate::testing::TestClient) and responses are read with
+//! [`HttpResponse::from_bytes`], so a torus server and client talking to each other exercise
+//! both halves of the crate. Every request opens a connection of its own, and only plain
+//! `http://` urls are supported.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{thread, time::Duration};
+//! use torus_http::{client::{Client, ClientError}, prelude::*};
+//!
+//! let server = HttpServer::new()
+//!     .get("/users/:id", |req: HttpRequest| format!("user {}", req.param("id").unwrap()))
+//!     .head("/users/:id", |_| HttpResponse::new().insert_header("Content-Length", "6"))
+//!     .post("/echo", |req: HttpRequest| req.body_str().unwrap_or_default().to_owned())
+//!     .bind(("127.0.0.1", 0))
+//!     .unwrap();
+//! let base = format!("http://127.0.0.1:{}", server.port());
+//! thread::spawn(move || server.listen());
+//!
+//! let client = Client::new().timeout(Duration::from_secs(5));
+//! let res = client.get(format!("{base}/users/7")).send().unwrap();
+//! assert_eq!(res.status, HttpStatus::OK);
+//! assert_eq!(res.body_str(), Some("user 7"));
+//!
+//! let res = client
+//!     .post(format!("{base}/echo"))
+//!     .header("Content-Type", "text/plain")
+//!     .body("ping")
+//!     .send()
+//!     .unwrap();
+//! assert_eq!(res.body_str(), Some("ping"));
+//!
+//! // no waiting for a body that never comes
+//! let res = client.head(format!("{base}/users/7")).send().unwrap();
+//! assert_eq!(res.header("Content-Length"), Some("6"));
+//! assert_eq!(res.body, None);
+//!
+//! let res = client.get(format!("{base}/missing")).send().unwrap();
+//! assert_eq!(res.status, HttpStatus::NOT_FOUND);
+//!
+//! assert!(matches!(
+//!     client.get("https://example.com").send(),
+//!     Err(ClientError::UnsupportedScheme(scheme)) if scheme == "https"
+//! ));
+//! ```
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{
+    method::HttpMethod,
+    parser::{self, ChunkParseError},
+    request,
+    response::{HttpResponse, ResponseParseError},
+};
+
+/// What [`Client::timeout`] defaults to
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Makes requests, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Client {
+    timeout: Duration,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl Client {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long connecting, and then every single read or write, may take, 30 seconds by
+    /// default
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Start building a request, `url` looks like `http://host:port/path?query`
+    pub fn request(&self, method: HttpMethod, url: impl Into<String>) -> ClientRequest<'_> {
+        ClientRequest {
+            client: self,
+            method,
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(&self, url: impl Into<String>) -> ClientRequest<'_> {
+        self.request(HttpMethod::Get, url)
+    }
+
+    pub fn post(&self, url: impl Into<String>) -> ClientRequest<'_> {
+        self.request(HttpMethod::Post, url)
+    }
+
+    pub fn put(&self, url: impl Into<String>) -> ClientRequest<'_> {
+        self.request(HttpMethod::Put, url)
+    }
+
+    pub fn patch(&self, url: impl Into<String>) -> ClientRequest<'_> {
+        self.request(HttpMethod::Patch, url)
+    }
+
+    pub fn delete(&self, url: impl Into<String>) -> ClientRequest<'_> {
+        self.request(HttpMethod::Delete, url)
+    }
+
+    pub fn head(&self, url: impl Into<String>) -> ClientRequest<'_> {
+        self.request(HttpMethod::Head, url)
+    }
+}
+
+/// A request being built by a [`Client`]
+#[must_use = "requests do nothing until they are sent"]
+pub struct ClientRequest<'a> {
+    client: &'a Client,
+    method: HttpMethod,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+}
+
+impl ClientRequest<'_> {
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set the body, `Content-Length` is filled in unless a header for it or
+    /// `Transfer-Encoding` was given
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Sends the request and waits for the whole response
+    ///
+    /// `Host` and `Connection: close` are added unless they were set already
+    ///
+    /// # Errors
+    ///
+    /// - the url isn't an `http://` url
+    /// - connecting, writing or reading failed or timed out
+    /// - the response doesn't parse, see [`HttpResponse::from_bytes`]
+    /// - the response says its body is longer than could ever be read
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{io::{ErrorKind, Read, Write}, net::TcpListener, thread};
+    /// use torus_http::{client::{Client, ClientError}, prelude::*};
+    ///
+    /// // against a live torus server, a body spanning many reads both ways
+    /// let server = HttpServer::new()
+    ///     .post("/len", |req: HttpRequest| req.body.map_or(0, |b| b.len()).to_string())
+    ///     .get("/big", |_| "x".repeat(100_000))
+    ///     .bind(("127.0.0.1", 0))
+    ///     .unwrap();
+    /// let base = format!("http://127.0.0.1:{}", server.port());
+    /// thread::spawn(move || server.listen());
+    ///
+    /// let client = Client::new();
+    /// let res = client.post(format!("{base}/len")).body(vec![b'a'; 50_000]).send().unwrap();
+    /// assert_eq!(res.body_str(), Some("50000"));
+    /// let res = client.get(format!("{base}/big")).send().unwrap();
+    /// assert_eq!(res.body.map(|b| b.len()), Some(100_000));
+    ///
+    /// // and its answer to a request it can't read
+    /// let res = client
+    ///     .post(format!("{base}/len"))
+    ///     .header("Content-Length", "lots")
+    ///     .send()
+    ///     .unwrap();
+    /// assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+    ///
+    /// // a server sending raw responses
+    /// let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    /// let url = format!("http://{}/", listener.local_addr().unwrap());
+    /// thread::spawn(move || {
+    ///     let responses: [&[u8]; 2] = [
+    ///         b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\n",
+    ///         b"HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551615\r\n\r\n",
+    ///     ];
+    ///     for (response, stream) in responses.into_iter().zip(listener.incoming()) {
+    ///         let mut stream = stream.unwrap();
+    ///         _ = stream.read(&mut [0; 1024]);
+    ///         stream.write_all(response).unwrap();
+    ///     }
+    /// });
+    ///
+    /// let res = client.get(&url).send().unwrap();
+    /// assert_eq!(res.body_str(), Some("hi"));
+    /// assert!(matches!(
+    ///     client.get(&url).send(),
+    ///     Err(ClientError::Io(err)) if err.kind() == ErrorKind::InvalidData
+    /// ));
+    /// ```
+    pub fn send(mut self) -> Result<HttpResponse, ClientError> {
+        let url = Url::parse(&self.url)?;
+        let has_header = |headers: &[(String, String)], wanted: &str| {
+            headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(wanted))
+        };
+        if !has_header(&self.headers, "Host") {
+            self.headers.push(("Host".into(), url.authority.to_owned()));
+        }
+        if !has_header(&self.headers, "Connection") {
+            self.headers.push(("Connection".into(), "close".into()));
+        }
+
+        let mut stream = self.client.connect(&url)?;
+        let raw = request::encode_request(
+            &self.method,
+            url.target,
+            &self.headers,
+            self.body.as_deref(),
+        );
+        stream.write_all(&raw)?;
+        stream.flush()?;
+        read_response(&mut stream, self.method == HttpMethod::Head)
+    }
+}
+
+impl Client {
+    fn connect(&self, url: &Url) -> Result<TcpStream, ClientError> {
+        let mut last_err = None;
+        for addr in (url.host, url.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    stream.set_write_timeout(Some(self.timeout))?;
+                    return Ok(stream);
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err
+            .unwrap_or_else(|| std::io::Error::new(ErrorKind::NotFound, "host has no addresses"))
+            .into())
+    }
+}
+
+/// The parts of an `http://` url we need
+struct Url<'a> {
+    /// `host:port` as written, for the `Host` header
+    authority: &'a str,
+    host: &'a str,
+    port: u16,
+    /// Path and query
+    target: &'a str,
+}
+
+impl<'a> Url<'a> {
+    fn parse(url: &'a str) -> Result<Self, ClientError> {
+        let invalid = || ClientError::InvalidUrl(url.to_owned());
+        let rest = match url.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") => rest,
+            Some((scheme, _)) => return Err(ClientError::UnsupportedScheme(scheme.to_owned())),
+            None => return Err(invalid()),
+        };
+        let split = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, target) = rest.split_at(split);
+        let target = match target {
+            "" => "/",
+            query if query.starts_with('?') => return Err(invalid()),
+            target => target,
+        };
+        // the port colon comes after the brackets of an ipv6 address
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                (host, port.parse().map_err(|_| invalid())?)
+            }
+            _ => (authority, 80),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            authority,
+            host,
+            port,
+            target,
+        })
+    }
+}
+
+/// Upper bound for the head of a response
+const MAX_HEAD_SIZE: usize = 4096 * 4;
+
+/// Reads one full response, skipping interim `1xx` ones
+fn read_response(stream: &mut impl Read, head_request: bool) -> Result<HttpResponse, ClientError> {
+    let mut buf = Vec::new();
+    loop {
+        let head_len = loop {
+            if let Some(head_len) = request::head_len(&buf) {
+                break head_len;
+            }
+            if buf.len() > MAX_HEAD_SIZE {
+                return Err(ResponseParseError::IncompleteHead.into());
+            }
+            if fill(stream, &mut buf)? == 0 {
+                return Err(ResponseParseError::IncompleteHead.into());
+            }
+        };
+        let head = HttpResponse::from_bytes(&buf[..head_len])?;
+
+        let status = head.status.code();
+        if (100..200).contains(&status) && status != 101 {
+            buf.drain(..head_len);
+            continue;
+        }
+        if head_request || matches!(status, 101..200 | 204 | 304) {
+            return Ok(head);
+        }
+
+        let chunked = head
+            .header("Transfer-Encoding")
+            .is_some_and(parser::ends_in_chunked);
+        let total = if chunked {
+            loop {
+                match parser::chunked_len(&buf[head_len..]) {
+                    Ok(Some(body_len)) => break checked_total(head_len, body_len)?,
+                    Ok(None) => {}
+                    Err(err) => return Err(ResponseParseError::Chunked(err).into()),
+                }
+                if fill(stream, &mut buf)? == 0 {
+                    return Err(ResponseParseError::Chunked(ChunkParseError::Incomplete).into());
+                }
+            }
+        } else if let Some(len) = head.header("Content-Length") {
+            let len: usize = len
+                .parse()
+                .map_err(|_| ResponseParseError::InvalidContentLength)?;
+            let total = checked_total(head_len, len)?;
+            while buf.len() < total {
+                if fill(stream, &mut buf)? == 0 {
+                    return Err(ResponseParseError::IncompleteBody.into());
+                }
+            }
+            total
+        } else {
+            // the body goes on until the server hangs up
+            stream.read_to_end(&mut buf)?;
+            buf.len()
+        };
+        return Ok(HttpResponse::from_bytes(&buf[..total])?);
+    }
+}
+
+/// Length of a response with a body of `body_len` bytes after the head, a body too long to
+/// ever fit in memory is an error rather than an overflow
+fn checked_total(head_len: usize, body_len: usize) -> std::io::Result<usize> {
+    head_len.checked_add(body_len).ok_or_else(|| {
+        std::io::Error::new(ErrorKind::InvalidData, "response body length overflows")
+    })
+}
+
+/// Reads whatever is available into `buf`, returns how much that was
+fn fill(stream: &mut impl Read, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+    let mut chunk = [0; 4096];
+    let n = stream.read(&mut chunk)?;
+    buf.extend_from_slice(&chunk[..n]);
+    Ok(n)
+}
+
+/// Reasons a [`ClientRequest`] can fail
+#[derive(Debug)]
+pub enum ClientError {
+    /// Not a url we understand, the whole url is included
+    InvalidUrl(String),
+    /// Anything but `http`, tls isn't supported
+    UnsupportedScheme(String),
+    /// Connecting, sending or receiving failed, timeouts included
+    Io(std::io::Error),
+    InvalidResponse(ResponseParseError),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::InvalidUrl(url) => write!(f, "invalid url {url:?}"),
+            ClientError::UnsupportedScheme(scheme) => write!(f, "unsupported scheme {scheme:?}"),
+            ClientError::Io(err) => err.fmt(f),
+            ClientError::InvalidResponse(err) => write!(f, "invalid response: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Io(err) => Some(err),
+            ClientError::InvalidResponse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ResponseParseError> for ClientError {
+    fn from(err: ResponseParseError) -> Self {
+        Self::InvalidResponse(err)
+    }
+}
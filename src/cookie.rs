@@ -0,0 +1,361 @@
+//! Cookies, read from the request and set on the response
+//!
+//! A [`CookieJar`] starts out with the cookies the client sent and remembers what handlers add
+//! and remove, so only those changes go out as `Set-Cookie` headers. Build one with
+//! [`CookieJar::from_request`] and put its changes on the response with [`CookieJar::apply`], or
+//! register the [`Cookies`](crate::middleware::cookies::Cookies) middleware, which does both and
+//! hands handlers the jar through the request extensions.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use torus_http::{
+//!     cookie::{Cookie, CookieJar},
+//!     middleware::cookies::Cookies,
+//!     prelude::*,
+//!     testing::TestClient,
+//! };
+//!
+//! let client = TestClient::new(
+//!     HttpServer::new()
+//!         .add_around_middleware(Cookies)
+//!         .get("/theme", |req: HttpRequest| {
+//!             let jar = req.extensions.get::<CookieJar>().unwrap();
+//!             jar.get("theme").unwrap_or_else(|| "light".into())
+//!         })
+//!         .post("/theme", |req: HttpRequest| {
+//!             let jar = req.extensions.get::<CookieJar>().unwrap();
+//!             jar.add(Cookie::new("theme", req.body_str().unwrap_or_default()).path("/"));
+//!             "saved"
+//!         })
+//!         .post("/logout", |req: HttpRequest| {
+//!             let jar = req.extensions.get::<CookieJar>().unwrap();
+//!             jar.remove("session");
+//!             jar.remove("theme");
+//!             "bye"
+//!         }),
+//! );
+//!
+//! // reading leaves the cookies alone
+//! let res = client.get("/theme").header("Cookie", "theme=dark; session=abc").send();
+//! assert_eq!(res.body_str(), Some("dark"));
+//! assert_eq!(res.set_cookies().count(), 0);
+//!
+//! // adding one sends just that one, overwriting what the client had
+//! let res = client.post("/theme").header("Cookie", "theme=dark; session=abc").body("solarized").send();
+//! assert_eq!(res.set_cookies().collect::<Vec<_>>(), ["theme=solarized; Path=/"]);
+//!
+//! // removing sends an expired cookie for everything the client had
+//! let res = client.post("/logout").header("Cookie", "session=abc").send();
+//! assert_eq!(
+//!     res.set_cookies().collect::<Vec<_>>(),
+//!     ["session=; Path=/; Max-Age=0; Expires=Thu, 01 Jan 1970 00:00:00 GMT"]
+//! );
+//! ```
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{request::HttpRequest, response::HttpResponse, time::format_http_date};
+
+/// `SameSite` attribute of a cookie
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    /// The attribute value, as in `SameSite=Lax`
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// A cookie to set, formatted as the value of a `Set-Cookie` header
+///
+/// ```rust
+/// use std::time::Duration;
+/// use torus_http::cookie::{Cookie, SameSite};
+///
+/// let cookie = Cookie::new("id", "a3fWa")
+///     .path("/")
+///     .max_age(Duration::from_hours(1))
+///     .http_only(true)
+///     .secure(true)
+///     .same_site(SameSite::Strict);
+/// assert_eq!(
+///     cookie.to_string(),
+///     "id=a3fWa; Path=/; Max-Age=3600; HttpOnly; Secure; SameSite=Strict"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<Duration>,
+    expires: Option<SystemTime>,
+    http_only: bool,
+    secure: bool,
+    same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    #[must_use]
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            path: None,
+            domain: None,
+            max_age: None,
+            expires: None,
+            http_only: false,
+            secure: false,
+            same_site: None,
+        }
+    }
+
+    /// An already expired cookie, which makes the client delete its cookie `name`
+    fn removal(name: &str) -> Self {
+        Self::new(name, "")
+            .path("/")
+            .max_age(Duration::ZERO)
+            .expires(UNIX_EPOCH)
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[must_use]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    #[must_use]
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// How long the client keeps the cookie, counted in whole seconds
+    #[must_use]
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// When the client drops the cookie, for old clients that don't know `Max-Age`
+    #[must_use]
+    pub fn expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Hide the cookie from scripts
+    #[must_use]
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Only send the cookie over https, `SameSite=None` needs this
+    #[must_use]
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    #[must_use]
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    fn is_removal(&self) -> bool {
+        self.max_age == Some(Duration::ZERO)
+    }
+}
+
+impl Display for Cookie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", format_http_date(expires))?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// The `name=value` pairs of a `Cookie` header, pairs without a `=` are skipped
+///
+/// ```rust
+/// use torus_http::cookie::parse_cookies;
+/// let cookies: Vec<_> = parse_cookies("theme=dark; session=abc=; broken").collect();
+/// assert_eq!(cookies, [("theme", "dark"), ("session", "abc=")]);
+/// ```
+pub fn parse_cookies(header: &str) -> impl Iterator<Item = (&str, &str)> {
+    header.split(';').filter_map(|cookie| {
+        let (name, value) = cookie.split_once('=')?;
+        Some((name.trim(), value.trim()))
+    })
+}
+
+#[derive(Default)]
+struct JarState {
+    /// What the client sent
+    incoming: HashMap<String, String>,
+    /// What has to be sent back, at most one cookie per name
+    changes: Vec<Cookie>,
+}
+
+/// The cookies of a request plus the changes made to them, see the [module docs](self)
+///
+/// Clones share the same cookies, so the jar can sit in the request extensions while the
+/// middleware holding a clone applies the changes afterwards
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use torus_http::{cookie::{Cookie, CookieJar}, prelude::*};
+///
+/// let req = HttpRequest::from_str("GET / HTTP/1.1\r\nCookie: a=1; b=2\r\n\r\n").unwrap();
+/// let jar = CookieJar::from_request(&req);
+/// jar.add(Cookie::new("a", "one"));
+/// jar.add(Cookie::new("a", "uno"));
+/// jar.add(Cookie::new("c", "3"));
+/// jar.remove("b");
+/// // removing a cookie the client never had just forgets about it
+/// jar.remove("c");
+///
+/// assert_eq!(jar.get("a").as_deref(), Some("uno"));
+/// assert_eq!(jar.get("b"), None);
+/// assert_eq!(jar.get("c"), None);
+///
+/// let res = jar.apply(HttpResponse::new());
+/// assert_eq!(
+///     res.set_cookies().collect::<Vec<_>>(),
+///     ["a=uno", "b=; Path=/; Max-Age=0; Expires=Thu, 01 Jan 1970 00:00:00 GMT"]
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct CookieJar(Arc<Mutex<JarState>>);
+
+impl CookieJar {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A jar holding the cookies from the request's `Cookie` header
+    #[must_use]
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let incoming = req
+            .header("Cookie")
+            .map(|header| {
+                parse_cookies(header)
+                    .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self(Arc::new(Mutex::new(JarState {
+            incoming,
+            changes: Vec::new(),
+        })))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, JarState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The current value of the cookie `name`, taking changes into account
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<String> {
+        let state = self.state();
+        match state.changes.iter().find(|cookie| cookie.name == name) {
+            Some(cookie) if cookie.is_removal() => None,
+            Some(cookie) => Some(cookie.value.clone()),
+            None => state.incoming.get(name).cloned(),
+        }
+    }
+
+    /// Sets `cookie`, replacing an earlier change to a cookie of the same name
+    pub fn add(&self, cookie: Cookie) {
+        let mut state = self.state();
+        state.changes.retain(|other| other.name != cookie.name);
+        state.changes.push(cookie);
+    }
+
+    /// Deletes the cookie `name` on the client, assuming it was set with `Path=/`
+    ///
+    /// Cookies with another path or a domain have to be removed by adding an expired cookie
+    /// with the same path and domain
+    pub fn remove(&self, name: &str) {
+        let mut state = self.state();
+        state.changes.retain(|other| other.name != name);
+        if state.incoming.contains_key(name) {
+            state.changes.push(Cookie::removal(name));
+        }
+    }
+
+    /// The cookies that have to be sent to the client, in the order they were changed
+    #[must_use]
+    pub fn changes(&self) -> Vec<Cookie> {
+        self.state().changes.clone()
+    }
+
+    /// Adds a `Set-Cookie` header to `res` for every change
+    #[must_use]
+    pub fn apply(&self, res: HttpResponse) -> HttpResponse {
+        self.state()
+            .changes
+            .iter()
+            .fold(res, HttpResponse::add_cookie)
+    }
+}
+
+impl std::fmt::Debug for CookieJar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state();
+        f.debug_struct("CookieJar")
+            .field("incoming", &state.incoming)
+            .field("changes", &state.changes)
+            .finish()
+    }
+}
@@ -0,0 +1,247 @@
+//! Percent-encoding, the `%XX` escapes of urls
+//!
+//! Every part of a url allows a slightly different set of characters unescaped, pick the part
+//! being encoded with [`EncodeSet`]. Decoding is the same everywhere, so there is just the lossy
+//! [`percent_decode`] and the strict [`percent_decode_strict`].
+//!
+//! `+` is left alone in both directions, it only means a space in form bodies and query strings
+//! and handling that is up to whoever parses those.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use torus_http::encoding::{EncodeSet, percent_decode, percent_encode};
+//!
+//! let name = "résumé 2024/v2.pdf";
+//! let encoded = percent_encode(name, EncodeSet::PathSegment);
+//! assert_eq!(encoded, "r%C3%A9sum%C3%A9%202024%2Fv2.pdf");
+//! assert_eq!(percent_decode(&encoded), name);
+//!
+//! let query = format!("q={}", percent_encode("fish & chips=1+1", EncodeSet::Query));
+//! assert_eq!(query, "q=fish%20%26%20chips%3D1%2B1");
+//! ```
+use std::{borrow::Cow, fmt::Write as _};
+
+/// Which part of a url [`percent_encode`] encodes for
+///
+/// Unreserved characters (`A-Z a-z 0-9 - . _ ~`) are never escaped, `%`, controls, spaces and
+/// anything outside of ascii always are. The sets differ in the other ascii punctuation:
+///
+/// ```rust
+/// use torus_http::encoding::{EncodeSet, percent_encode};
+///
+/// let punctuation = "!$&'()*+,;=:@/?#[]\"<>`{}|\\^% ";
+/// assert_eq!(
+///     percent_encode(punctuation, EncodeSet::Component),
+///     "%21%24%26%27%28%29%2A%2B%2C%3B%3D%3A%40%2F%3F%23%5B%5D%22%3C%3E%60%7B%7D%7C%5C%5E%25%20"
+/// );
+/// assert_eq!(
+///     percent_encode(punctuation, EncodeSet::PathSegment),
+///     "!$&'()*+,;=:@%2F%3F%23%5B%5D%22%3C%3E%60%7B%7D%7C%5C%5E%25%20"
+/// );
+/// assert_eq!(
+///     percent_encode(punctuation, EncodeSet::Query),
+///     "!$%26'()*%2B,;%3D:@/?%23%5B%5D%22%3C%3E%60%7B%7D%7C%5C%5E%25%20"
+/// );
+/// assert_eq!(
+///     percent_encode(punctuation, EncodeSet::Userinfo),
+///     "!$&'()*+,;=%3A%40%2F%3F%23%5B%5D%22%3C%3E%60%7B%7D%7C%5C%5E%25%20"
+/// );
+///
+/// let unreserved = "AZaz09-._~";
+/// for set in [EncodeSet::Component, EncodeSet::PathSegment, EncodeSet::Query, EncodeSet::Userinfo] {
+///     assert_eq!(percent_encode(unreserved, set), unreserved);
+///     assert_eq!(percent_encode("\t\n\x7f", set), "%09%0A%7F");
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EncodeSet {
+    /// Everything but the unreserved characters, safe anywhere in a url and in html attributes
+    Component,
+    /// A single path segment, `/` is escaped while the sub-delimiters, `:` and `@` are kept
+    PathSegment,
+    /// A single query key or value, `&`, `=`, `+` and `#` are escaped while `/`, `?` and the
+    /// other sub-delimiters are kept
+    Query,
+    /// A user name or password, `:` and `@` are escaped while the sub-delimiters are kept
+    Userinfo,
+}
+
+impl EncodeSet {
+    /// Whether `byte` may stay as it is
+    fn keeps(self, byte: u8) -> bool {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            return true;
+        }
+        let sub_delim = matches!(
+            byte,
+            b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'='
+        );
+        match self {
+            EncodeSet::Component => false,
+            EncodeSet::PathSegment => sub_delim || matches!(byte, b':' | b'@'),
+            EncodeSet::Query => {
+                (sub_delim && !matches!(byte, b'&' | b'=' | b'+'))
+                    || matches!(byte, b':' | b'@' | b'/' | b'?')
+            }
+            EncodeSet::Userinfo => sub_delim,
+        }
+    }
+}
+
+/// Escapes every byte of `input` that `set` doesn't allow as `%XX`, with uppercase hex digits
+///
+/// Borrows `input` if there was nothing to escape
+///
+/// ```rust
+/// use std::borrow::Cow;
+/// use torus_http::encoding::{EncodeSet, percent_encode};
+///
+/// assert!(matches!(percent_encode("plain", EncodeSet::Component), Cow::Borrowed("plain")));
+/// assert_eq!(percent_encode("", EncodeSet::Component), "");
+/// // multi-byte characters are escaped byte by byte
+/// assert_eq!(percent_encode("ü", EncodeSet::PathSegment), "%C3%BC");
+/// assert_eq!(percent_encode("日本", EncodeSet::Query), "%E6%97%A5%E6%9C%AC");
+/// assert_eq!(percent_encode("🦀", EncodeSet::Userinfo), "%F0%9F%A6%80");
+/// // escapes get escaped again, encoding twice isn't a no-op
+/// assert_eq!(percent_encode("100%25", EncodeSet::PathSegment), "100%2525");
+/// ```
+#[must_use]
+pub fn percent_encode(input: &str, set: EncodeSet) -> Cow<'_, str> {
+    let Some(first) = input.bytes().position(|byte| !set.keeps(byte)) else {
+        return Cow::Borrowed(input);
+    };
+    let mut encoded = String::with_capacity(input.len() + 16);
+    encoded.push_str(&input[..first]);
+    for byte in input[first..].bytes() {
+        if set.keeps(byte) {
+            encoded.push(char::from(byte));
+        } else {
+            _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+/// Undoes `%XX` escapes, for input that may be broken
+///
+/// `%` that isn't followed by two hex digits is kept as it is, and bytes that don't decode to
+/// utf-8 become `U+FFFD`. Borrows `input` if there was nothing to decode
+///
+/// ```rust
+/// use std::borrow::Cow;
+/// use torus_http::encoding::percent_decode;
+///
+/// assert!(matches!(percent_decode("plain"), Cow::Borrowed("plain")));
+/// assert_eq!(percent_decode("a%20b%2fc%2F"), "a b/c/");
+/// assert_eq!(percent_decode("%C3%A9t%C3%A9"), "été");
+/// assert_eq!(percent_decode("%F0%9F%A6%80"), "🦀");
+/// // unescaped multi-byte characters pass through
+/// assert_eq!(percent_decode("日本%20語"), "日本 語");
+/// // `+` is not a space here
+/// assert_eq!(percent_decode("a+b%2B"), "a+b+");
+/// // broken escapes stay
+/// assert_eq!(percent_decode("100%"), "100%");
+/// assert_eq!(percent_decode("%zz%4"), "%zz%4");
+/// assert_eq!(percent_decode("%+1%-1"), "%+1%-1");
+/// // decoding happens once
+/// assert_eq!(percent_decode("%2541"), "%41");
+/// // bytes that aren't utf-8 are replaced, a cut off character included
+/// assert_eq!(percent_decode("a%FFb"), "a\u{FFFD}b");
+/// assert_eq!(percent_decode("%C3"), "\u{FFFD}");
+/// ```
+#[must_use]
+pub fn percent_decode(input: &str) -> Cow<'_, str> {
+    if !input.contains('%') {
+        return Cow::Borrowed(input);
+    }
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut rest = input.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if let Some(decoded) = decode_escape(byte, tail) {
+            bytes.push(decoded);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    Cow::Owned(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Undoes `%XX` escapes, refusing broken escapes and anything that doesn't decode to utf-8
+///
+/// Borrows `input` if there was nothing to decode
+///
+/// # Errors
+///
+/// - a `%` isn't followed by two hex digits
+/// - the decoded bytes aren't utf-8
+///
+/// ```rust
+/// use torus_http::encoding::{DecodeError, percent_decode_strict};
+///
+/// assert_eq!(percent_decode_strict("a%20b").unwrap(), "a b");
+/// assert_eq!(percent_decode_strict("%E2%82%AC%e2%82%ac").unwrap(), "€€");
+/// assert_eq!(percent_decode_strict("a+b").unwrap(), "a+b");
+///
+/// assert_eq!(percent_decode_strict("ok%2"), Err(DecodeError::InvalidEscape(2)));
+/// assert_eq!(percent_decode_strict("%g0"), Err(DecodeError::InvalidEscape(0)));
+/// assert_eq!(percent_decode_strict("%%41"), Err(DecodeError::InvalidEscape(0)));
+/// assert_eq!(percent_decode_strict("%+f"), Err(DecodeError::InvalidEscape(0)));
+/// assert_eq!(percent_decode_strict("%FF"), Err(DecodeError::InvalidUtf8));
+/// assert_eq!(percent_decode_strict("%E2%82"), Err(DecodeError::InvalidUtf8));
+/// ```
+pub fn percent_decode_strict(input: &str) -> Result<Cow<'_, str>, DecodeError> {
+    if !input.contains('%') {
+        return Ok(Cow::Borrowed(input));
+    }
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut rest = input.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let decoded = decode_escape(byte, tail)
+                .ok_or(DecodeError::InvalidEscape(input.len() - rest.len()))?;
+            bytes.push(decoded);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes)
+        .map(Cow::Owned)
+        .map_err(|_| DecodeError::InvalidUtf8)
+}
+
+/// The byte `%XX` stands for, if `byte` is a `%` and `tail` starts with two hex digits
+fn decode_escape(byte: u8, tail: &[u8]) -> Option<u8> {
+    if byte != b'%' {
+        return None;
+    }
+    let hex = |digit: u8| char::from(digit).to_digit(16);
+    match tail {
+        [high, low, ..] => Some(u8::try_from(hex(*high)? << 4 | hex(*low)?).ok()?),
+        _ => None,
+    }
+}
+
+/// Reasons [`percent_decode_strict`] refused its input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The `%` at this byte offset isn't followed by two hex digits
+    InvalidEscape(usize),
+    /// The decoded bytes aren't utf-8
+    InvalidUtf8,
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::InvalidEscape(offset) => write!(f, "invalid escape at byte {offset}"),
+            DecodeError::InvalidUtf8 => f.write_str("decoded text isn't utf-8"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
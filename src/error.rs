@@ -0,0 +1,197 @@
+//! A ready made error type for handlers
+//!
+//! [`HttpError`] carries a status, a message and optionally some headers. It implements
+//! [`Response`], so handlers can return `Result<T, HttpError>` and use `?` on anything that
+//! converts into it.
+//!
+//! Messages of internal errors often contain things the client shouldn't see, like file paths.
+//! [`HttpError::hide_message`] sends a generic message instead while the real one stays available
+//! through [`HttpResponse::error`], e.g. for a [`Logger`](crate::logger::Logger).
+//!
+//! # Example:
+//!
+//! ```rust
+//! use torus_http::{error::HttpError, prelude::*, testing::TestClient};
+//!
+//! fn user(req: HttpRequest) -> Result<String, HttpError> {
+//!     let id: u32 = req
+//!         .query
+//!         .as_ref()
+//!         .and_then(|query| query.get("id"))
+//!         .ok_or_else(|| HttpError::bad_request("missing id"))?
+//!         .parse()
+//!         .map_err(|_| HttpError::bad_request("id must be a number"))?;
+//!     match id {
+//!         1 => Ok("ferris".into()),
+//!         2 => Err(HttpError::internal("db at /var/lib/users.db is corrupt").hide_message()),
+//!         _ => Err(HttpError::not_found(format!("no user {id}"))),
+//!     }
+//! }
+//!
+//! let client = TestClient::new(HttpServer::new().get("/user", user));
+//!
+//! assert_eq!(client.get("/user?id=1").send().body_str(), Some("ferris"));
+//!
+//! let res = client.get("/user?id=7").send();
+//! assert_eq!(res.status, HttpStatus::NOT_FOUND);
+//! assert_eq!(res.body_str(), Some("no user 7"));
+//!
+//! let res = client.get("/user?id=x").send();
+//! assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+//! assert_eq!(res.body_str(), Some("id must be a number"));
+//!
+//! // the client only learns that something went wrong, the logs get the details
+//! let res = client.get("/user?id=2").send();
+//! assert_eq!(res.status, HttpStatus::INTERNAL_SERVER_ERROR);
+//! assert_eq!(res.body_str(), Some("internal server error"));
+//! assert_eq!(res.error().unwrap().message, "db at /var/lib/users.db is corrupt");
+//! ```
+use std::{fmt::Display, sync::Arc};
+
+use crate::{
+    response::{HttpResponse, Response},
+    status::{ClientErrorResponse, HttpStatus},
+};
+
+/// An error with the status and message to answer it with, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpError {
+    pub status: HttpStatus,
+    pub message: String,
+    /// Sent along with the error, e.g. `WWW-Authenticate` for a `401`
+    pub headers: Vec<(String, String)>,
+    /// Send a generic message instead of [`HttpError::message`]
+    pub hidden: bool,
+}
+
+impl HttpError {
+    #[must_use]
+    pub fn new(status: HttpStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            headers: Vec::new(),
+            hidden: false,
+        }
+    }
+
+    /// `400 Bad Request`
+    #[must_use]
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(HttpStatus::BAD_REQUEST, message)
+    }
+
+    /// `401 Unauthorized`
+    #[must_use]
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(HttpStatus::UNAUTHORIZED, message)
+    }
+
+    /// `403 Forbidden`
+    #[must_use]
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(HttpStatus::FORBIDDEN, message)
+    }
+
+    /// `404 Not Found`
+    #[must_use]
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(HttpStatus::NOT_FOUND, message)
+    }
+
+    /// `409 Conflict`
+    #[must_use]
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ClientErrorResponse::Conflict.into(), message)
+    }
+
+    /// `500 Internal Server Error`, consider [`HttpError::hide_message`]
+    #[must_use]
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(HttpStatus::INTERNAL_SERVER_ERROR, message)
+    }
+
+    #[must_use]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Tell the client `internal server error` (or whatever fits the status) instead of the
+    /// message
+    #[must_use]
+    pub fn hide_message(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    /// What the client gets to read
+    fn public_message(&self) -> &str {
+        if !self.hidden {
+            return &self.message;
+        }
+        if self.status.code() >= 500 {
+            "internal server error"
+        } else {
+            "request failed"
+        }
+    }
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.status.code(), self.message)
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+impl Response for HttpError {
+    fn into_response(self) -> HttpResponse {
+        let mut response =
+            HttpResponse::new_body(self.public_message().to_owned(), self.status.clone())
+                .insert_header("Content-Type", "text/plain; charset=utf-8");
+        for (name, value) in &self.headers {
+            response = response.insert_header(name.as_str(), value.as_str());
+        }
+        response.error = Some(Arc::new(self));
+        response
+    }
+
+    fn status_code(&self) -> HttpStatus {
+        self.status.clone()
+    }
+}
+
+/// A hidden `500`, io errors tend to mention paths
+///
+/// ```rust
+/// use std::io;
+/// use torus_http::{error::HttpError, prelude::*};
+///
+/// let err = HttpError::from(io::Error::other("disk full"));
+/// assert_eq!(err.status, HttpStatus::INTERNAL_SERVER_ERROR);
+/// assert!(err.hidden);
+/// assert_eq!(err.message, "disk full");
+/// ```
+impl From<std::io::Error> for HttpError {
+    fn from(err: std::io::Error) -> Self {
+        Self::internal(err.to_string()).hide_message()
+    }
+}
+
+/// A `400`, the body the client sent didn't fit
+///
+/// ```rust
+/// use torus_http::{error::HttpError, prelude::*};
+///
+/// let err: HttpError = serde_json::from_str::<u32>("nope").unwrap_err().into();
+/// assert_eq!(err.status, HttpStatus::BAD_REQUEST);
+/// assert!(!err.hidden);
+/// ```
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for HttpError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::bad_request(err.to_string())
+    }
+}
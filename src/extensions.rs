@@ -0,0 +1,89 @@
+//! Typed storage for passing data along with a request
+//!
+//! Middleware put values in [`HttpRequest::extensions`](crate::request::HttpRequest::extensions)
+//! and handlers further down pick them up again by type.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use torus_http::prelude::*;
+//!
+//! struct RequestId(u64);
+//!
+//! let server = HttpServer::new()
+//!     .add_middleware(|mut req: HttpRequest| {
+//!         req.extensions.insert(RequestId(42));
+//!         req
+//!     })
+//!     .get("/", |req: HttpRequest| {
+//!         let id = req.extensions.get::<RequestId>().map_or(0, |id| id.0);
+//!         format!("request {id}")
+//!     });
+//!
+//! let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+//! assert_eq!(res.body_str(), Some("request 42"));
+//! ```
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    sync::Arc,
+};
+
+/// A map holding at most one value per type
+///
+/// Values are reference counted so cloning a request doesn't clone them
+#[derive(Clone, Default)]
+pub struct Extensions(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+
+impl Extensions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `value`, replacing any earlier value of the same type
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.0.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    #[must_use]
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.0.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    /// Removes the value of type `T`, returns whether there was one
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> bool {
+        self.0.remove(&TypeId::of::<T>()).is_some()
+    }
+
+    #[must_use]
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.0.contains_key(&TypeId::of::<T>())
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Two extension maps are equal when they hold the very same values
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .all(|(k, v)| other.0.get(k).is_some_and(|o| Arc::ptr_eq(v, o)))
+    }
+}
+
+impl Eq for Extensions {}
+
+impl Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Extensions({} values)", self.0.len())
+    }
+}
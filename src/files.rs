@@ -0,0 +1,776 @@
+//! Serving files from disk or from the binary itself
+//!
+//! [`HttpServer::serve_dir`](crate::server::HttpServer::serve_dir) serves a whole directory
+//! under a path prefix, [`serve_file`] answers a request with a single file from inside a
+//! handler. Either way the response gets a `Content-Type` guessed from the file extension, an
+//! `ETag` and a `Last-Modified`, and single byte ranges are supported so browsers can seek in
+//! videos and downloads can be resumed.
+//!
+//! Files compiled in with `include_bytes!` are served by [`EmbeddedFile`] instead, for
+//! deployments that are a single binary.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::fs;
+//! use torus_http::{files::ServeDir, prelude::*, testing::TestClient};
+//!
+//! let root = std::env::temp_dir().join(format!("torus-files-{}", std::process::id()));
+//! fs::create_dir_all(root.join("css")).unwrap();
+//! fs::write(root.join("index.html"), "<h1>home</h1>").unwrap();
+//! fs::write(root.join("css/site.css"), "body {}").unwrap();
+//!
+//! let client = TestClient::new(HttpServer::new().serve_dir("/static", ServeDir::new(&root)));
+//!
+//! let res = client.get("/static/css/site.css").send();
+//! assert_eq!(res.body_str(), Some("body {}"));
+//! assert_eq!(res.header("Content-Type"), Some("text/css; charset=utf-8"));
+//! assert_eq!(res.header("Accept-Ranges"), Some("bytes"));
+//!
+//! // directories are served by their `index.html`
+//! assert_eq!(client.get("/static/").send().body_str(), Some("<h1>home</h1>"));
+//!
+//! // nothing outside of the directory
+//! assert_eq!(client.get("/static/../Cargo.toml").send().status, HttpStatus::NOT_FOUND);
+//! assert_eq!(client.get("/static/missing.txt").send().status, HttpStatus::NOT_FOUND);
+//! # fs::remove_dir_all(&root).unwrap();
+//! ```
+use std::{
+    fmt::Write as _,
+    fs::{self, File, Metadata},
+    io::{self, Read, Seek, SeekFrom},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    encoding::{EncodeSet, percent_decode_strict, percent_encode},
+    method::HttpMethod,
+    middleware::add_vary,
+    request::HttpRequest,
+    response::{HttpResponse, IoErrorResponse, Response},
+    server::HandlerFn,
+    status::{ClientErrorResponse, HttpStatus, SuccessResponse},
+    time::{format_http_date, parse_http_date},
+};
+
+/// Serves the files of a directory, see the [module docs](self)
+///
+/// Registered with [`HttpServer::serve_dir`](crate::server::HttpServer::serve_dir), which
+/// tells it the prefix to strip from request paths
+#[derive(Debug, Clone)]
+pub struct ServeDir {
+    root: PathBuf,
+    prefix: String,
+    listing: bool,
+    symlinks: SymlinkPolicy,
+    precompressed: bool,
+}
+
+/// Content codings [`ServeDir::with_precompressed`] looks for, with the extension of their files
+const PRECOMPRESSED: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+/// What [`ServeDir`] does with symbolic links, for serving as well as listing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Act as if they weren't there
+    Deny,
+    /// Follow them as long as they end up inside the served directory
+    #[default]
+    WithinRoot,
+    /// Follow them wherever they lead
+    Follow,
+}
+
+impl ServeDir {
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            prefix: String::new(),
+            listing: false,
+            symlinks: SymlinkPolicy::default(),
+            precompressed: false,
+        }
+    }
+
+    /// List the contents of directories without an `index.html` instead of answering
+    /// `404 Not Found`
+    ///
+    /// Directories come first, then files, each with their size and modification time
+    ///
+    /// ```rust
+    /// use std::fs;
+    /// use torus_http::{files::ServeDir, prelude::*, testing::TestClient};
+    ///
+    /// let root = std::env::temp_dir().join(format!("torus-listing-{}", std::process::id()));
+    /// fs::create_dir_all(root.join("public/docs")).unwrap();
+    /// fs::write(root.join("public/<b>&.txt"), "weird").unwrap();
+    /// fs::write(root.join("public/a.txt"), "plain").unwrap();
+    /// fs::write(root.join("secret.txt"), "hidden").unwrap();
+    /// #[cfg(unix)]
+    /// std::os::unix::fs::symlink(root.join("secret.txt"), root.join("public/escape.txt")).unwrap();
+    ///
+    /// let served = root.join("public");
+    /// let client = TestClient::new(
+    ///     HttpServer::new()
+    ///         .serve_dir("/files", ServeDir::new(&served).with_listing(true))
+    ///         .serve_dir("/plain", ServeDir::new(&served)),
+    /// );
+    ///
+    /// let res = client.get("/files/").send();
+    /// assert_eq!(res.header("Content-Type"), Some("text/html; charset=utf-8"));
+    /// let page = res.body_str().unwrap();
+    /// // names are escaped in the text, and percent-encoded in the links
+    /// assert!(page.contains(r#"<a href="/files/%3Cb%3E%26.txt">&lt;b&gt;&amp;.txt</a>"#), "{page}");
+    /// assert!(!page.contains("<b>&"));
+    /// // directories first
+    /// let docs = page.find(r#"<a href="/files/docs/">docs/</a>"#).unwrap();
+    /// assert!(docs < page.find(r#"href="/files/a.txt""#).unwrap());
+    /// // the link leads to the file
+    /// assert_eq!(client.get("/files/%3Cb%3E%26.txt").send().body_str(), Some("weird"));
+    ///
+    /// // a symlink out of the directory is neither listed nor served
+    /// assert!(!page.contains("escape.txt"));
+    /// assert_eq!(client.get("/files/escape.txt").send().status, HttpStatus::NOT_FOUND);
+    ///
+    /// // an empty directory still gets a listing, with a way back up
+    /// assert!(client.get("/files/docs").send().body_str().unwrap().contains(r#"<a href="/files/">../</a>"#));
+    ///
+    /// // without listing there is nothing to see
+    /// assert_eq!(client.get("/plain/").send().status, HttpStatus::NOT_FOUND);
+    /// # fs::remove_dir_all(&root).unwrap();
+    /// ```
+    #[must_use]
+    pub fn with_listing(mut self, listing: bool) -> Self {
+        self.listing = listing;
+        self
+    }
+
+    /// Serve `app.js.br` or `app.js.gz` instead of `app.js` to clients accepting that encoding,
+    /// whichever they like better
+    ///
+    /// The compressed file goes out with the `Content-Type` of the original and a
+    /// `Content-Encoding`. Responses for files that have a compressed variant get
+    /// `Vary: Accept-Encoding`, whichever version they carry
+    ///
+    /// ```rust
+    /// use std::fs;
+    /// use torus_http::{files::ServeDir, prelude::*, testing::TestClient};
+    ///
+    /// let root = std::env::temp_dir().join(format!("torus-precompressed-{}", std::process::id()));
+    /// fs::create_dir_all(&root).unwrap();
+    /// fs::write(root.join("app.js"), "console.log('hi')").unwrap();
+    /// fs::write(root.join("app.js.gz"), b"\x1f\x8b pretend this is gzip").unwrap();
+    ///
+    /// let client = TestClient::new(
+    ///     HttpServer::new().serve_dir("/", ServeDir::new(&root).with_precompressed(true)),
+    /// );
+    ///
+    /// let gzipped = client.get("/app.js").header("Accept-Encoding", "br;q=0, gzip").send();
+    /// assert_eq!(gzipped.header("Content-Encoding"), Some("gzip"));
+    /// assert_eq!(gzipped.header("Content-Type"), Some("text/javascript; charset=utf-8"));
+    /// assert_eq!(gzipped.header("Vary"), Some("Accept-Encoding"));
+    /// assert!(gzipped.body.as_deref().unwrap().starts_with(b"\x1f\x8b"));
+    ///
+    /// let plain = client.get("/app.js").send();
+    /// assert_eq!(plain.header("Content-Encoding"), None);
+    /// assert_eq!(plain.header("Vary"), Some("Accept-Encoding"));
+    /// assert_eq!(plain.body_str(), Some("console.log('hi')"));
+    ///
+    /// // nothing to choose between
+    /// fs::write(root.join("style.css"), "body {}").unwrap();
+    /// let only = client.get("/style.css").header("Accept-Encoding", "gzip").send();
+    /// assert_eq!(only.header("Content-Encoding"), None);
+    /// assert_eq!(only.header("Vary"), None);
+    ///
+    /// assert_ne!(plain.header("ETag"), gzipped.header("ETag"));
+    /// # fs::remove_dir_all(&root).unwrap();
+    /// ```
+    #[must_use]
+    pub fn with_precompressed(mut self, precompressed: bool) -> Self {
+        self.precompressed = precompressed;
+        self
+    }
+
+    /// How to treat symbolic links, by default they are only followed if they stay inside the
+    /// served directory
+    #[must_use]
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Set by [`HttpServer::serve_dir`](crate::server::HttpServer::serve_dir)
+    pub(crate) fn prefix(mut self, prefix: &str) -> Self {
+        prefix.trim_end_matches('/').clone_into(&mut self.prefix);
+        self
+    }
+
+    /// The file `path` points to, `None` if it would leave the root
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let rest = path.strip_prefix(&self.prefix)?;
+        let mut file = self.root.clone();
+        for segment in rest.split('/').filter(|segment| !segment.is_empty()) {
+            let segment = percent_decode_strict(segment).ok()?;
+            if matches!(segment.as_ref(), "." | "..") || segment.contains(['/', '\\', '\0']) {
+                return None;
+            }
+            file.push(segment.as_ref());
+        }
+        self.allows(&file).then_some(file)
+    }
+
+    /// Serves the file at `path`, or a precompressed version of it
+    fn serve(&self, req: &HttpRequest, path: &Path) -> HttpResponse {
+        if !self.precompressed {
+            return serve_file(req, path);
+        }
+        let mut best: Option<(&str, PathBuf, f32)> = None;
+        let mut has_variant = false;
+        for (coding, extension) in PRECOMPRESSED {
+            let mut variant = path.as_os_str().to_owned();
+            variant.push(".");
+            variant.push(extension);
+            let variant = PathBuf::from(variant);
+            if !variant.is_file() || !self.allows(&variant) {
+                continue;
+            }
+            has_variant = true;
+            let q = req.encoding_quality(coding);
+            if q > 0.0 && best.as_ref().is_none_or(|(_, _, best)| q > *best) {
+                best = Some((coding, variant, q));
+            }
+        }
+        let response = match best {
+            Some((coding, variant, _)) => serve_encoded_file(req, &variant, path, Some(coding)),
+            None => serve_file(req, path),
+        };
+        if has_variant {
+            add_vary(response, "Accept-Encoding")
+        } else {
+            response
+        }
+    }
+
+    /// Whether `path`, somewhere inside the root, may be served according to the symlink policy
+    fn allows(&self, path: &Path) -> bool {
+        match self.symlinks {
+            SymlinkPolicy::Follow => true,
+            SymlinkPolicy::Deny => {
+                let inside = path.strip_prefix(&self.root).unwrap_or(path);
+                let mut current = self.root.clone();
+                inside.components().all(|component| {
+                    current.push(component);
+                    !current.is_symlink()
+                })
+            }
+            // paths that don't exist are fine, they end in a `404` anyway
+            SymlinkPolicy::WithinRoot => match (path.canonicalize(), self.root.canonicalize()) {
+                (Ok(path), Ok(root)) => path.starts_with(root),
+                _ => true,
+            },
+        }
+    }
+
+    /// The html page listing the directory at `dir`, which `req` asked for
+    fn listing(&self, req: &HttpRequest, dir: &Path) -> io::Result<HttpResponse> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !self.allows(&entry.path()) {
+                continue;
+            }
+            // follows symlinks, so links show up as what they point to
+            let Ok(metadata) = fs::metadata(entry.path()) else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().into_owned();
+            entries.push((metadata.is_dir(), name, metadata));
+        }
+        entries.sort_by(|(a_dir, a_name, _), (b_dir, b_name, _)| {
+            b_dir.cmp(a_dir).then_with(|| a_name.cmp(b_name))
+        });
+
+        let base = req.path.trim_end_matches('/');
+        let title = escape_html(&format!("{base}/"));
+        let mut page = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\n\
+             <body>\n<h1>Index of {title}</h1>\n<table>\n"
+        );
+        if base.len() > self.prefix.len() {
+            let parent = &base[..base.rfind('/').unwrap_or(0)];
+            _ = writeln!(
+                page,
+                "<tr><td><a href=\"{}/\">../</a></td><td></td><td></td></tr>",
+                escape_html(parent)
+            );
+        }
+        for (is_dir, name, metadata) in &entries {
+            let slash = if *is_dir { "/" } else { "" };
+            let size = if *is_dir {
+                String::new()
+            } else {
+                metadata.len().to_string()
+            };
+            let modified = metadata
+                .modified()
+                .map(format_http_date)
+                .unwrap_or_default();
+            _ = writeln!(
+                page,
+                "<tr><td><a href=\"{}/{}{slash}\">{}{slash}</a></td><td>{size}</td><td>{modified}</td></tr>",
+                escape_html(base),
+                percent_encode(name, EncodeSet::Component),
+                escape_html(name),
+            );
+        }
+        page.push_str("</table>\n</body>\n</html>\n");
+
+        let response =
+            HttpResponse::new().insert_header("Content-Type", "text/html; charset=utf-8");
+        Ok(if req.method == HttpMethod::Head {
+            response.insert_header("Content-Length", page.len().to_string())
+        } else {
+            response.set_body(page)
+        })
+    }
+}
+
+impl HandlerFn for ServeDir {
+    fn call(&self, req: HttpRequest) -> HttpResponse {
+        let Some(path) = self.resolve(&req.path) else {
+            return not_found();
+        };
+        if !path.is_dir() {
+            return self.serve(&req, &path);
+        }
+        let index = path.join("index.html");
+        if index.is_file() && self.allows(&index) {
+            return self.serve(&req, &index);
+        }
+        if !self.listing {
+            return not_found();
+        }
+        self.listing(&req, &path)
+            .unwrap_or_else(|err| IoErrorResponse(err).into_response())
+    }
+}
+
+/// Escapes text for html, attribute values included
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn not_found() -> HttpResponse {
+    IoErrorResponse(io::ErrorKind::NotFound.into()).into_response()
+}
+
+/// Answers `req` with the file at `path`
+///
+/// A `Range: bytes=...` header asking for a single range is answered with
+/// `206 Partial Content` holding just that range, or `416 Range Not Satisfiable` if the range
+/// lies past the end of the file. Several ranges at once and `If-Range` validators that don't
+/// match the file get the whole file instead. `HEAD` requests get the headers only
+///
+/// Failing to read the file is answered like an [`IoErrorResponse`], a missing file or a
+/// directory with `404 Not Found`
+///
+/// ```rust
+/// use std::fs;
+/// use torus_http::{files::serve_file, prelude::*, testing::TestClient};
+///
+/// let path = std::env::temp_dir().join(format!("torus-range-{}.txt", std::process::id()));
+/// fs::write(&path, "0123456789").unwrap();
+/// let file = path.clone();
+/// let client = TestClient::new(
+///     HttpServer::new().get("/digits", move |req: HttpRequest| serve_file(&req, &file)),
+/// );
+/// let range = |range: &str| client.get("/digits").header("Range", range).send();
+///
+/// let res = range("bytes=2-4");
+/// assert_eq!(res.status, HttpStatus::from_code(206).unwrap());
+/// assert_eq!(res.header("Content-Range"), Some("bytes 2-4/10"));
+/// assert_eq!(res.body_str(), Some("234"));
+///
+/// // the last three bytes
+/// assert_eq!(range("bytes=-3").body_str(), Some("789"));
+/// // everything from byte 7 on
+/// assert_eq!(range("bytes=7-").body_str(), Some("789"));
+/// // ends past the end are cut off
+/// assert_eq!(range("bytes=8-100").header("Content-Range"), Some("bytes 8-9/10"));
+///
+/// let res = range("bytes=10-");
+/// assert_eq!(res.status, HttpStatus::from_code(416).unwrap());
+/// assert_eq!(res.header("Content-Range"), Some("bytes */10"));
+///
+/// // several ranges and garbage get the whole file
+/// assert_eq!(range("bytes=0-1,4-5").body_str(), Some("0123456789"));
+/// assert_eq!(range("lines=1-2").status, HttpStatus::OK);
+///
+/// // only resume if the file is still the same
+/// let etag = client.get("/digits").send().header("ETag").unwrap().to_owned();
+/// let res = client.get("/digits").header("Range", "bytes=5-").header("If-Range", &etag).send();
+/// assert_eq!(res.body_str(), Some("56789"));
+/// let res = client.get("/digits").header("Range", "bytes=5-").header("If-Range", "\"old\"").send();
+/// assert_eq!(res.status, HttpStatus::OK);
+/// assert_eq!(res.body_str(), Some("0123456789"));
+/// let modified = client.get("/digits").send().header("Last-Modified").unwrap().to_owned();
+/// let res = client.get("/digits").header("Range", "bytes=5-").header("If-Range", &modified).send();
+/// assert_eq!(res.body_str(), Some("56789"));
+/// let res = client
+///     .get("/digits")
+///     .header("Range", "bytes=5-")
+///     .header("If-Range", "Thu Jan  1 00:00:00 1970")
+///     .send();
+/// assert_eq!(res.status, HttpStatus::OK);
+/// # fs::remove_file(&path).unwrap();
+/// ```
+#[must_use]
+pub fn serve_file(req: &HttpRequest, path: impl AsRef<Path>) -> HttpResponse {
+    let path = path.as_ref();
+    serve_encoded_file(req, path, path, None)
+}
+
+/// [`serve_file`] for `file`, which holds `path` in the content coding `encoding`
+fn serve_encoded_file(
+    req: &HttpRequest,
+    file: &Path,
+    path: &Path,
+    encoding: Option<&str>,
+) -> HttpResponse {
+    match try_serve_file(req, file, path, encoding) {
+        Ok(response) => response,
+        Err(err) if err.kind() == io::ErrorKind::IsADirectory => not_found(),
+        Err(err) => IoErrorResponse(err).into_response(),
+    }
+}
+
+fn try_serve_file(
+    req: &HttpRequest,
+    file: &Path,
+    path: &Path,
+    encoding: Option<&str>,
+) -> io::Result<HttpResponse> {
+    let mut file = File::open(file)?;
+    let metadata = file.metadata()?;
+    if metadata.is_dir() {
+        return Err(io::ErrorKind::IsADirectory.into());
+    }
+    let len = metadata.len();
+    let etag = etag(&metadata, encoding);
+    let last_modified = metadata.modified().ok().map(format_http_date);
+
+    let mut response = HttpResponse::new()
+        .insert_header("Content-Type", content_type(path))
+        .insert_header("Accept-Ranges", "bytes")
+        .insert_header("ETag", etag.as_str());
+    if let Some(encoding) = encoding {
+        response = response.insert_header("Content-Encoding", encoding);
+    }
+    if let Some(last_modified) = &last_modified {
+        response = response.insert_header("Last-Modified", last_modified.as_str());
+    }
+
+    // dates may come in any of the http date formats, comparing them formatted alike
+    let if_range_matches = req.header("If-Range").is_none_or(|validator| {
+        validator == etag || parse_http_date(validator).ok().map(format_http_date) == last_modified
+    });
+    let range = match req.header("Range") {
+        Some(range) if if_range_matches => parse_range(range, len),
+        _ => None,
+    };
+    let (status, range) = match range {
+        Some(Ok(range)) => {
+            let content_range = format!("bytes {}-{}/{len}", range.start(), range.end());
+            response = response.insert_header("Content-Range", content_range);
+            (SuccessResponse::PartialContent.into(), range)
+        }
+        Some(Err(Unsatisfiable)) => {
+            return Ok(response
+                .set_status(ClientErrorResponse::RangeNotSatisfiable.into())
+                .insert_header("Content-Range", format!("bytes */{len}")));
+        }
+        None if len == 0 => return Ok(response),
+        None => (HttpStatus::OK, 0..=len - 1),
+    };
+
+    let range_len = range.end() - range.start() + 1;
+    response = response.set_status(status);
+    if req.method == HttpMethod::Head {
+        return Ok(response.insert_header("Content-Length", range_len.to_string()));
+    }
+    file.seek(SeekFrom::Start(*range.start()))?;
+    let mut body = Vec::with_capacity(usize::try_from(range_len).unwrap_or_default());
+    file.take(range_len).read_to_end(&mut body)?;
+    Ok(response.set_body(body))
+}
+
+/// The range is past the end of the file
+struct Unsatisfiable;
+
+/// The byte range a `Range` header asks for out of `len` bytes
+///
+/// `None` for headers we ignore, meaning the whole file is sent: anything but a single
+/// `bytes` range
+fn parse_range(header: &str, len: u64) -> Option<Result<RangeInclusive<u64>, Unsatisfiable>> {
+    let spec = header.trim().strip_prefix("bytes=")?.trim();
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    let range = if start.is_empty() {
+        // the last `end` bytes
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(Err(Unsatisfiable));
+        }
+        len.saturating_sub(suffix)..=len - 1
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = match end {
+            "" => u64::MAX,
+            end => end.parse().ok()?,
+        };
+        if end < start {
+            return None;
+        }
+        if start >= len {
+            return Some(Err(Unsatisfiable));
+        }
+        start..=end.min(len - 1)
+    };
+    Some(Ok(range))
+}
+
+/// A strong validator made from the size and modification time, plus the content coding so
+/// the compressed and plain versions of a file never share one
+fn etag(metadata: &Metadata, encoding: Option<&str>) -> String {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    let mut etag = format!(
+        "\"{:x}-{:x}.{:x}",
+        metadata.len(),
+        modified.as_secs(),
+        modified.subsec_nanos()
+    );
+    if let Some(encoding) = encoding {
+        _ = write!(etag, "-{encoding}");
+    }
+    etag.push('"');
+    etag
+}
+
+/// A file compiled into the binary, see
+/// [`HttpServer::embedded`](crate::server::HttpServer::embedded)
+///
+/// Served with the `Content-Type` it was given and a strong `ETag` hashed from its contents.
+/// [`EmbeddedFile::new`] is a `const fn`, in a `static` the hash is worked out at compile time.
+/// Requests whose `If-None-Match` has the `ETag` get `304 Not Modified` without a body, `HEAD`
+/// requests get the headers only
+///
+/// Responses borrow the bytes, nothing is copied per request
+///
+/// Hashing megabytes at compile time trips the `long_running_const_eval` lint, allow it on the
+/// `static` or use [`HttpServer::embedded`](crate::server::HttpServer::embedded), which hashes
+/// once at startup
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use torus_http::{files::EmbeddedFile, prelude::*, testing::TestClient};
+///
+/// static APP_JS: EmbeddedFile =
+///     EmbeddedFile::new(b"console.log(\"hi\")", "text/javascript; charset=utf-8");
+/// static LARGE: EmbeddedFile = EmbeddedFile::new(&[b'x'; 64 << 10], "application/octet-stream");
+///
+/// let client = TestClient::new(
+///     HttpServer::new()
+///         .get("/app.js", &APP_JS)
+///         .get("/large", &LARGE)
+///         .get("/", |req: HttpRequest| APP_JS.serve(&req)),
+/// );
+/// assert_eq!(client.get("/large").send().body.unwrap().len(), 64 << 10);
+/// // the response points at the static itself
+/// let res = LARGE.serve(&HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+/// assert_eq!(res.body.unwrap().as_ptr(), LARGE.bytes().as_ptr());
+///
+/// let res = client.get("/app.js").send();
+/// assert_eq!(res.body_str(), Some("console.log(\"hi\")"));
+/// assert_eq!(res.header("Content-Type"), Some("text/javascript; charset=utf-8"));
+/// let etag = res.header("ETag").unwrap().to_owned();
+/// assert_eq!(etag, APP_JS.etag());
+/// assert_eq!(client.get("/").send().header("ETag"), Some(etag.as_str()));
+///
+/// let res = client.get("/app.js").header("If-None-Match", &etag).send();
+/// assert_eq!(res.status, HttpStatus::NOT_MODIFIED);
+/// assert_eq!(res.body, None);
+/// assert_eq!(res.header("ETag"), Some(etag.as_str()));
+/// // lists, weak tags and `*` match too
+/// for validator in [format!("\"old\", {etag}"), format!("W/{etag}"), "*".to_owned()] {
+///     let res = client.get("/app.js").header("If-None-Match", &validator).send();
+///     assert_eq!(res.status, HttpStatus::NOT_MODIFIED);
+/// }
+/// let res = client.get("/app.js").header("If-None-Match", "\"old\"").send();
+/// assert_eq!(res.status, HttpStatus::OK);
+///
+/// // different contents, different tags
+/// assert_ne!(EmbeddedFile::new(b"a", "text/plain").etag(), EmbeddedFile::new(b"b", "text/plain").etag());
+///
+/// // and known before the program runs
+/// const ETAG: &str = APP_JS.etag();
+/// assert_eq!(ETAG, etag);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedFile {
+    bytes: &'static [u8],
+    content_type: &'static str,
+    /// The quoted hash, as ascii
+    etag: [u8; 18],
+}
+
+impl EmbeddedFile {
+    #[must_use]
+    pub const fn new(bytes: &'static [u8], content_type: &'static str) -> Self {
+        Self {
+            bytes,
+            content_type,
+            etag: quoted_hex(fnv1a(bytes)),
+        }
+    }
+
+    #[must_use]
+    pub const fn bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+
+    #[must_use]
+    pub const fn content_type(&self) -> &'static str {
+        self.content_type
+    }
+
+    /// The `ETag` it is served with, quotes included
+    #[must_use]
+    pub const fn etag(&self) -> &str {
+        match std::str::from_utf8(&self.etag) {
+            Ok(etag) => etag,
+            // hex digits and quotes are always ascii
+            Err(_) => "",
+        }
+    }
+
+    /// Answers `req` with the file, for use inside a handler
+    #[must_use]
+    pub fn serve(&self, req: &HttpRequest) -> HttpResponse {
+        let etag = self.etag();
+        let response = HttpResponse::new().insert_header("ETag", etag);
+        if req
+            .header("If-None-Match")
+            .is_some_and(|validators| matches_etag(validators, etag))
+        {
+            return response.set_status(HttpStatus::NOT_MODIFIED);
+        }
+        let response = response.insert_header("Content-Type", self.content_type);
+        if req.method == HttpMethod::Head {
+            return response.insert_header("Content-Length", self.bytes.len().to_string());
+        }
+        response.set_static_body(self.bytes)
+    }
+}
+
+impl HandlerFn for EmbeddedFile {
+    fn call(&self, req: HttpRequest) -> HttpResponse {
+        self.serve(&req)
+    }
+}
+
+/// For files kept in a `static`, which aren't cloned that way
+impl HandlerFn for &'static EmbeddedFile {
+    fn call(&self, req: HttpRequest) -> HttpResponse {
+        self.serve(&req)
+    }
+}
+
+/// Whether the `If-None-Match` list `validators` has `etag`, comparing weakly as that header
+/// asks for
+fn matches_etag(validators: &str, etag: &str) -> bool {
+    validators.split(',').map(str::trim).any(|validator| {
+        validator == "*" || validator.strip_prefix("W/").unwrap_or(validator) == etag
+    })
+}
+
+/// 64 bit FNV-1a, good enough to tell file contents apart
+const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = (hash ^ bytes[i] as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// `hash` as 16 lower case hex digits in double quotes
+const fn quoted_hex(hash: u64) -> [u8; 18] {
+    let mut quoted = [b'"'; 18];
+    let mut i = 0;
+    while i < 16 {
+        let digit = (hash >> (60 - 4 * i)) & 0xf;
+        quoted[i + 1] = b"0123456789abcdef"[digit as usize];
+        i += 1;
+    }
+    quoted
+}
+
+/// Guesses the `Content-Type` from the file extension
+pub(crate) fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
@@ -35,9 +35,26 @@
 //! }
 //! ```
 
+#[cfg(feature = "client")]
+pub mod client;
+pub mod cookie;
+pub mod encoding;
+pub mod error;
+pub mod extensions;
+pub mod files;
+pub mod logger;
 pub mod method;
+pub mod metrics;
+pub mod middleware;
+pub mod parser;
+pub mod pattern;
 pub mod prelude;
+pub mod query;
 pub mod request;
 pub mod response;
 pub mod server;
 pub mod status;
+pub mod testing;
+pub mod time;
+pub mod timing;
+pub mod upgrade;
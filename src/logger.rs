@@ -0,0 +1,152 @@
+//! Pluggable logging for the http server
+//!
+//! Implement [`Logger`] to send request logs wherever you like (`tracing`, `log`, a file, ...)
+//! or just use the [`StderrLogger`] that ships with torus.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use torus_http::prelude::*;
+//!
+//! struct CountingLogger;
+//!
+//! impl Logger for CountingLogger {
+//!     fn log_request(&self, req: &HttpRequest, resp: &HttpResponse, duration: Duration) {
+//!         println!("{} took {duration:?} and returned {}", req.path, resp.status);
+//!     }
+//! }
+//!
+//! let server = HttpServer::new().with_logger(CountingLogger);
+//! ```
+use std::{net::SocketAddr, time::Duration};
+
+use crate::{
+    method::HttpMethod, request::HttpRequest, response::HttpResponse, server::ServerError,
+};
+
+/// Trait for anything that wants to be told about handled requests
+///
+/// The logger is shared between all connection threads, hence the `Send + Sync` bound
+pub trait Logger: Send + Sync {
+    /// Called once the response for a request has been written to the client
+    fn log_request(&self, req: &HttpRequest, resp: &HttpResponse, duration: Duration);
+
+    /// Called when handling a connection failed, does nothing by default
+    fn log_error(&self, err: &ServerError) {
+        _ = err;
+    }
+
+    /// Called when a middleware or handler panicked while handling the request `req` describes,
+    /// with the panic message. The client gets a `500` either way, does nothing by default
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, sync::{Arc, Mutex}, thread, time::Duration};
+    /// use torus_http::{logger::PanickedRequest, prelude::*};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct PanicLog(Arc<Mutex<Vec<String>>>);
+    ///
+    /// impl Logger for PanicLog {
+    ///     fn log_request(&self, _: &HttpRequest, _: &HttpResponse, _: Duration) {}
+    ///
+    ///     fn log_panic(&self, req: &PanickedRequest, message: &str) {
+    ///         let peer = req.peer_addr.unwrap().ip();
+    ///         let line = format!("{} {} from {peer}: {message}", req.method, req.path);
+    ///         self.0.lock().unwrap().push(line);
+    ///     }
+    /// }
+    ///
+    /// let log = PanicLog::default();
+    /// let server = HttpServer::new()
+    ///     .with_logger(log.clone())
+    ///     .get("/users/:id", |req: HttpRequest| -> String {
+    ///         req.query.unwrap_or_default().remove("name").unwrap()
+    ///     })
+    ///     .bind(("127.0.0.1", 0))
+    ///     .unwrap();
+    /// let port = server.port();
+    /// thread::spawn(move || server.listen());
+    ///
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    /// stream.write_all(b"GET /users/42 HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+    ///
+    /// assert_eq!(
+    ///     *log.0.lock().unwrap(),
+    ///     ["GET /users/42 from 127.0.0.1: called `Option::unwrap()` on a `None` value"]
+    /// );
+    /// ```
+    fn log_panic(&self, req: &PanickedRequest, message: &str) {
+        _ = (req, message);
+    }
+}
+
+/// The request a handler panicked on, as much of it as [`Logger::log_panic`] needs
+///
+/// The handler owns the request, so this is taken before it runs and kept small
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PanickedRequest {
+    pub method: HttpMethod,
+    /// Without the query string, like [`HttpRequest::path`]
+    pub path: String,
+    /// See [`HttpRequest::peer_addr`]
+    pub peer_addr: Option<SocketAddr>,
+}
+
+impl PanickedRequest {
+    pub(crate) fn new(req: &HttpRequest) -> Self {
+        Self {
+            method: req.method.clone(),
+            path: req.path.clone(),
+            peer_addr: req.peer_addr,
+        }
+    }
+}
+
+/// Simple logger that prints one line per request to `stderr`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StderrLogger;
+
+impl Logger for StderrLogger {
+    fn log_request(&self, req: &HttpRequest, resp: &HttpResponse, duration: Duration) {
+        match resp.error() {
+            Some(err) => eprintln!(
+                "[torus-http] {} {} -> {} ({duration:?}): {}",
+                req.method.as_str(),
+                req.path,
+                resp.status,
+                err.message
+            ),
+            None => eprintln!(
+                "[torus-http] {} {} -> {} ({duration:?})",
+                req.method.as_str(),
+                req.path,
+                resp.status
+            ),
+        }
+    }
+
+    fn log_error(&self, err: &ServerError) {
+        eprintln!("[torus-http] error handling connection: {err:?}");
+    }
+
+    fn log_panic(&self, req: &PanickedRequest, message: &str) {
+        match req.peer_addr {
+            Some(peer) => eprintln!(
+                "[torus-http] handler panic on {} {} from {}: {message}",
+                req.method.as_str(),
+                req.path,
+                peer.ip()
+            ),
+            None => eprintln!(
+                "[torus-http] handler panic on {} {}: {message}",
+                req.method.as_str(),
+                req.path
+            ),
+        }
+    }
+}
@@ -1,36 +1,88 @@
-//! Contains safe wrappers for http methods, note that the conversion is case insensitive so custom methods might not behave as expected
+//! Contains safe wrappers for http methods
+//!
+//! Standard methods are recognised case insensitively, custom ones keep the case they were sent
+//! in. Http methods are case sensitive, so a route for `PURGE` only matches requests sending
+//! exactly `PURGE`.
 //!
 //! # example:
 //!
 //! ```rust
 //! use torus_http::method::HttpMethod;
 //! assert_eq!(HttpMethod::Get, HttpMethod::from_str_val("GET"));
+//! assert_eq!(HttpMethod::Get, HttpMethod::from_str_val("get"));
+//! assert_eq!(HttpMethod::other("PURGE"), HttpMethod::from_str_val("PURGE"));
+//! assert_ne!(HttpMethod::other("PURGE"), HttpMethod::from_str_val("purge"));
 //! ```
 //!
 //! ## Note!!!
 //!
-//! due to how it is written right now, you cannot get a custom overwrite for the method (TODO: rephrase this I apparently don't know english)
+//! Standard method names always become their own variant, so an `Other` spelling one of them
+//! never matches a request
 //!
 //! ```rust
 //! use torus_http::method::HttpMethod;
 //! assert!(HttpMethod::other("GET") != HttpMethod::from_str_val("GET"));
 //! ```
+//!
+//! Custom methods work end to end:
+//!
+//! ```rust
+//! use std::{io::{Read, Write}, net::TcpStream, thread};
+//! use torus_http::prelude::*;
+//!
+//! let server = HttpServer::new()
+//!     .route("/cache", HttpMethod::other("PURGE"), |_| "purged")
+//!     .bind(("127.0.0.1", 0))
+//!     .unwrap();
+//! let port = server.port();
+//! thread::spawn(move || server.listen());
+//! let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+//!
+//! stream.write_all(b"PURGE /cache HTTP/1.1\r\n\r\n").unwrap();
+//! let mut buf = [0; 1024];
+//! let n = stream.read(&mut buf).unwrap();
+//! assert!(buf[..n].starts_with(b"HTTP/1.1 200"));
+//! assert!(buf[..n].ends_with(b"purged"));
+//!
+//! stream.write_all(b"purge /cache HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+//! let mut response = String::new();
+//! stream.read_to_string(&mut response).unwrap();
+//! assert!(response.starts_with("HTTP/1.1 405"));
+//! ```
+
+use std::{fmt::Display, str::FromStr};
+
+/// Every standard method, i.e. all but [`HttpMethod::Other`] and the deprecated
+/// [`HttpMethod::Update`], in declaration order
+pub const ALL_METHODS: [HttpMethod; 9] = [
+    HttpMethod::Get,
+    HttpMethod::Post,
+    HttpMethod::Delete,
+    HttpMethod::Put,
+    HttpMethod::Patch,
+    HttpMethod::Head,
+    HttpMethod::Options,
+    HttpMethod::Connect,
+    HttpMethod::Trace,
+];
+
+/// [`ALL_METHODS`] with a `'static` address to borrow from
+static STANDARD_METHODS: [HttpMethod; ALL_METHODS.len()] = ALL_METHODS;
 
 impl HttpMethod {
-    /// Generate an http method from a string
+    /// Generate an http method from a string, like [`str::parse`] but turning an empty string
+    /// into `Other("")` instead of failing
     #[must_use]
     pub fn from_str_val(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "get" => HttpMethod::Get,
-            "post" => HttpMethod::Post,
-            "update" => HttpMethod::Update,
-            "delete" => HttpMethod::Delete,
-            "put" => HttpMethod::Put,
-            "patch" => HttpMethod::Patch,
-            "head" => HttpMethod::Head,
-            "options" => HttpMethod::Options,
-            other => HttpMethod::Other(other.to_string()),
-        }
+        s.parse()
+            .unwrap_or_else(|_| HttpMethod::Other(String::new()))
+    }
+
+    fn from_token(s: &str) -> Self {
+        ALL_METHODS
+            .into_iter()
+            .find(|method| method.as_str().eq_ignore_ascii_case(s))
+            .unwrap_or_else(|| HttpMethod::Other(s.to_owned()))
     }
 
     /// Generate a non standard `HttpMethod`
@@ -38,6 +90,136 @@ impl HttpMethod {
     pub fn other(s: impl Into<String>) -> Self {
         Self::Other(s.into())
     }
+
+    /// The method as it would appear on the wire, uppercase for the standard ones and the
+    /// stored string for custom ones
+    ///
+    /// ```rust
+    /// use torus_http::method::{ALL_METHODS, HttpMethod};
+    /// let names: Vec<&str> = ALL_METHODS.iter().map(HttpMethod::as_str).collect();
+    /// assert_eq!(
+    ///     names,
+    ///     ["GET", "POST", "DELETE", "PUT", "PATCH", "HEAD", "OPTIONS", "CONNECT", "TRACE"]
+    /// );
+    /// assert_eq!(HttpMethod::other("x-custom").as_str(), "x-custom");
+    /// #[allow(deprecated)]
+    /// let update = HttpMethod::Update;
+    /// assert_eq!(update.as_str(), "UPDATE");
+    /// ```
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Delete => "DELETE",
+            #[allow(deprecated)]
+            HttpMethod::Update => "UPDATE",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Options => "OPTIONS",
+            HttpMethod::Connect => "CONNECT",
+            HttpMethod::Trace => "TRACE",
+            HttpMethod::Other(s) => s,
+        }
+    }
+
+    /// Whether this is one of the methods torus knows about, i.e. anything but `Other`
+    ///
+    /// ```rust
+    /// use torus_http::method::HttpMethod;
+    /// assert!(HttpMethod::Get.is_known());
+    /// assert!(!HttpMethod::Other("x-custom".into()).is_known());
+    /// ```
+    #[must_use]
+    pub fn is_known(&self) -> bool {
+        !self.is_other()
+    }
+
+    /// All standard methods, handy for `Allow` headers or testing every method
+    ///
+    /// ```rust
+    /// use torus_http::method::HttpMethod;
+    /// let allow: Vec<&str> = HttpMethod::all_standard().map(HttpMethod::as_str).collect();
+    /// assert_eq!(allow.join(", "), "GET, POST, DELETE, PUT, PATCH, HEAD, OPTIONS, CONNECT, TRACE");
+    /// assert_eq!(HttpMethod::all_standard().count(), HttpMethod::count());
+    /// assert!(HttpMethod::all_standard().all(HttpMethod::is_known));
+    /// ```
+    #[must_use]
+    pub fn all_standard() -> impl ExactSizeIterator<Item = &'static HttpMethod> {
+        STANDARD_METHODS.iter()
+    }
+
+    /// Number of standard methods
+    #[must_use]
+    pub const fn count() -> usize {
+        ALL_METHODS.len()
+    }
+
+    /// Whether the method only reads, so clients and caches may send it without asking
+    ///
+    /// `GET`, `HEAD`, `OPTIONS` and `TRACE`, custom methods are assumed to change something
+    ///
+    /// ```rust
+    /// use torus_http::method::HttpMethod;
+    ///
+    /// // (method, safe, idempotent, typically has a body)
+    /// # #[allow(deprecated)]
+    /// let table = [
+    ///     (HttpMethod::Get, true, true, false),
+    ///     (HttpMethod::Post, false, false, true),
+    ///     (HttpMethod::Delete, false, true, true),
+    ///     (HttpMethod::Update, false, false, true),
+    ///     (HttpMethod::Put, false, true, true),
+    ///     (HttpMethod::Patch, false, false, true),
+    ///     (HttpMethod::Head, true, true, false),
+    ///     (HttpMethod::Options, true, true, true),
+    ///     (HttpMethod::Connect, false, false, false),
+    ///     (HttpMethod::Trace, true, true, false),
+    ///     (HttpMethod::other("PURGE"), false, false, true),
+    /// ];
+    /// // every standard method, `Update` and a custom one
+    /// assert_eq!(table.len(), HttpMethod::count() + 2);
+    /// for (method, safe, idempotent, body) in table {
+    ///     assert_eq!(method.is_safe(), safe, "{method}");
+    ///     assert_eq!(method.is_idempotent(), idempotent, "{method}");
+    ///     assert_eq!(method.typically_has_body(), body, "{method}");
+    /// }
+    /// ```
+    #[must_use]
+    pub fn is_safe(&self) -> bool {
+        matches!(
+            self,
+            HttpMethod::Get | HttpMethod::Head | HttpMethod::Options | HttpMethod::Trace
+        )
+    }
+
+    /// Whether sending the method twice has the same effect as sending it once, so it can be
+    /// retried
+    ///
+    /// The safe methods plus `PUT` and `DELETE`, see [`HttpMethod::is_safe`]
+    #[must_use]
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, HttpMethod::Put | HttpMethod::Delete)
+    }
+
+    /// Whether a request body means anything for this method, torus drops the bodies of `GET`,
+    /// `HEAD`, `TRACE` and `CONNECT` requests
+    ///
+    /// See [`HttpMethod::is_safe`] for the whole table
+    #[must_use]
+    pub fn typically_has_body(&self) -> bool {
+        !matches!(
+            self,
+            HttpMethod::Get | HttpMethod::Head | HttpMethod::Trace | HttpMethod::Connect
+        )
+    }
+
+    /// Shorthand for `matches!(method, HttpMethod::Other(_))`
+    #[must_use]
+    pub fn is_other(&self) -> bool {
+        matches!(self, HttpMethod::Other(_))
+    }
 }
 
 /// Enum covering most standard http methods and also allowing for custom ones
@@ -46,10 +228,169 @@ pub enum HttpMethod {
     Get,
     Post,
     Delete,
+    /// Not an http method, no rfc defines `UPDATE`. Requests never parse into this, an `UPDATE`
+    /// request becomes `Other("UPDATE")` like any custom method
+    #[deprecated(
+        since = "0.2.10",
+        note = "UPDATE isn't an http method, use `Patch` or `Put`, or `HttpMethod::other(\"UPDATE\")` for a custom method; will be removed in the next breaking release"
+    )]
     Update,
     Put,
     Patch,
     Head,
     Options,
+    /// Asks for a tunnel to the authority in the request target, the server answers it with
+    /// `501 Not Implemented` unless a route for it exists
+    Connect,
+    Trace,
     Other(String),
 }
+
+/// The method as it goes over the wire, see [`HttpMethod::as_str`]
+///
+/// ```rust
+/// use torus_http::method::{HttpMethod, ALL_METHODS};
+///
+/// for method in ALL_METHODS {
+///     let shown = method.to_string();
+///     assert_eq!(shown.parse::<HttpMethod>(), Ok(method));
+/// }
+/// assert_eq!(HttpMethod::Options.to_string(), "OPTIONS");
+/// assert_eq!(HttpMethod::Connect.to_string(), "CONNECT");
+/// assert_eq!(HttpMethod::Trace.to_string(), "TRACE");
+/// // custom methods keep their case both ways
+/// assert_eq!("PURGE".parse::<HttpMethod>().unwrap().to_string(), "PURGE");
+/// assert_eq!("Purge".parse::<HttpMethod>().unwrap().to_string(), "Purge");
+/// ```
+impl Display for HttpMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Compares against a method name the way parsing would, case insensitively for the standard
+/// methods and exactly for custom ones
+///
+/// ```rust
+/// use torus_http::method::HttpMethod;
+///
+/// assert!(HttpMethod::Get == "GET");
+/// assert!(HttpMethod::Get == "get");
+/// assert!(HttpMethod::Get != "POST");
+/// assert!(HttpMethod::other("custom") == "custom");
+/// assert!(HttpMethod::other("custom") != "CUSTOM");
+/// assert!("HEAD" == HttpMethod::Head);
+/// assert!(HttpMethod::Put == String::from("put"));
+///
+/// let method = HttpMethod::Head;
+/// let served = match method {
+///     m if m == "GET" || m == "HEAD" => "page",
+///     _ => "nothing",
+/// };
+/// assert_eq!(served, "page");
+/// ```
+impl PartialEq<str> for HttpMethod {
+    fn eq(&self, other: &str) -> bool {
+        match self {
+            HttpMethod::Other(method) => method == other,
+            method => method.as_str().eq_ignore_ascii_case(other),
+        }
+    }
+}
+
+impl PartialEq<&str> for HttpMethod {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialEq<String> for HttpMethod {
+    fn eq(&self, other: &String) -> bool {
+        *self == *other.as_str()
+    }
+}
+
+impl PartialEq<HttpMethod> for str {
+    fn eq(&self, other: &HttpMethod) -> bool {
+        *other == *self
+    }
+}
+
+impl PartialEq<HttpMethod> for &str {
+    fn eq(&self, other: &HttpMethod) -> bool {
+        *other == **self
+    }
+}
+
+impl PartialEq<HttpMethod> for String {
+    fn eq(&self, other: &HttpMethod) -> bool {
+        *other == *self.as_str()
+    }
+}
+
+/// Standard methods are matched case insensitively, anything else becomes [`HttpMethod::Other`]
+/// as it was written
+///
+/// ```rust
+/// use torus_http::method::{HttpMethod, ALL_METHODS};
+///
+/// for method in ALL_METHODS {
+///     assert_eq!(method.as_str().parse::<HttpMethod>(), Ok(method.clone()));
+///     assert_eq!(method.as_str().to_lowercase().parse::<HttpMethod>(), Ok(method));
+/// }
+/// assert_eq!("CONNECT".parse::<HttpMethod>(), Ok(HttpMethod::Connect));
+/// assert_eq!("trace".parse::<HttpMethod>(), Ok(HttpMethod::Trace));
+/// assert_eq!("PURGE".parse::<HttpMethod>(), Ok(HttpMethod::other("PURGE")));
+/// assert_eq!("purge".parse::<HttpMethod>(), Ok(HttpMethod::other("purge")));
+/// assert!("".parse::<HttpMethod>().is_err());
+/// ```
+impl FromStr for HttpMethod {
+    type Err = EmptyMethodError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(EmptyMethodError);
+        }
+        Ok(Self::from_token(s))
+    }
+}
+
+/// An empty string isn't a method, not even a custom one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyMethodError;
+
+impl Display for EmptyMethodError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("empty http method")
+    }
+}
+
+impl std::error::Error for EmptyMethodError {}
+
+/// Serialized as the method name, deserializing accepts any case for the standard methods
+///
+/// ```rust
+/// use torus_http::method::HttpMethod;
+///
+/// for method in [HttpMethod::Get, HttpMethod::Trace, HttpMethod::other("PURGE")] {
+///     let json = serde_json::to_string(&method).unwrap();
+///     assert_eq!(serde_json::from_str::<HttpMethod>(&json).unwrap(), method);
+/// }
+/// assert_eq!(serde_json::to_string(&HttpMethod::Get).unwrap(), r#""GET""#);
+/// assert_eq!(serde_json::from_str::<HttpMethod>(r#""get""#).unwrap(), HttpMethod::Get);
+/// assert!(serde_json::from_str::<HttpMethod>(r#""""#).is_err());
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for HttpMethod {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HttpMethod {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let method = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        method.parse().map_err(serde::de::Error::custom)
+    }
+}
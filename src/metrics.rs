@@ -0,0 +1,305 @@
+//! Request metrics in the Prometheus text format
+//!
+//! [`HttpServer::metrics_endpoint`](crate::server::HttpServer::metrics_endpoint) starts counting
+//! and serves what was counted so far:
+//!
+//! - `torus_http_requests_total`, a counter by `method`, `route` and `status`
+//! - `torus_http_request_duration_seconds`, a histogram by `method` and `route`
+//! - `torus_http_active_connections`, a gauge of the connections currently open
+//!
+//! `route` is the pattern a request was routed by, like `/users/:id`, so every user doesn't get
+//! a series of their own. Requests no route matched count as `unmatched`, and their method as
+//! `OTHER` unless it is a standard one, since clients can send whatever they like there.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{io::{Read, Write}, net::TcpStream, thread};
+//! use torus_http::prelude::*;
+//!
+//! let server = HttpServer::new()
+//!     .get("/users/:id", |req: HttpRequest| format!("user {}", req.param("id").unwrap()))
+//!     .metrics_endpoint("/metrics")
+//!     .bind(("127.0.0.1", 0))
+//!     .unwrap();
+//! let port = server.port();
+//! thread::spawn(move || server.listen());
+//!
+//! let send = |request: &str| {
+//!     let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+//!     stream.write_all(request.as_bytes()).unwrap();
+//!     let mut response = String::new();
+//!     stream.read_to_string(&mut response).unwrap();
+//!     response
+//! };
+//!
+//! for id in 1..=3 {
+//!     send(&format!("GET /users/{id} HTTP/1.1\r\nConnection: close\r\n\r\n"));
+//! }
+//! send("BREW /pot HTTP/1.1\r\nConnection: close\r\n\r\n");
+//!
+//! // stays open while scraping
+//! let mut open = TcpStream::connect(("127.0.0.1", port)).unwrap();
+//! open.write_all(b"GET /users/4 HTTP/1.1\r\n\r\n").unwrap();
+//! _ = open.read(&mut [0; 1024]).unwrap();
+//!
+//! let scraped = send("GET /metrics HTTP/1.1\r\nConnection: close\r\n\r\n");
+//! let (head, body) = scraped.split_once("\r\n\r\n").unwrap();
+//! assert!(head.lines().any(|line| line == "Content-Type: text/plain; version=0.0.4; charset=utf-8"));
+//!
+//! assert!(body.contains(
+//!     "# TYPE torus_http_requests_total counter\n\
+//!      torus_http_requests_total{method=\"GET\",route=\"/users/:id\",status=\"200\"} 4\n\
+//!      torus_http_requests_total{method=\"OTHER\",route=\"unmatched\",status=\"404\"} 1\n"
+//! ));
+//! assert!(body.contains("# TYPE torus_http_request_duration_seconds histogram\n"));
+//! assert!(body.contains(
+//!     "torus_http_request_duration_seconds_bucket{method=\"GET\",route=\"/users/:id\",le=\"+Inf\"} 4\n"
+//! ));
+//! assert!(body.contains(
+//!     "torus_http_request_duration_seconds_count{method=\"GET\",route=\"/users/:id\"} 4\n"
+//! ));
+//! assert!(body.ends_with(
+//!     "# TYPE torus_http_active_connections gauge\ntorus_http_active_connections 2\n"
+//! ));
+//! ```
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicI64, Ordering},
+    },
+    time::Duration,
+};
+
+/// What [`Metrics::buckets`] defaults to, in seconds
+const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The `Content-Type` of [`Metrics::render`]
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Default)]
+struct Histogram {
+    /// One count per bucket, not cumulative yet
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Default)]
+struct Collected {
+    /// Keyed by `(method, route, status)`
+    requests: BTreeMap<(String, String, u16), u64>,
+    /// Keyed by `(method, route)`
+    durations: BTreeMap<(String, String), Histogram>,
+}
+
+/// Counts requests, see the [module docs](self)
+///
+/// Clones count into the same metrics, so one can be kept around for rendering elsewhere
+///
+/// ```rust
+/// use std::time::Duration;
+/// use torus_http::metrics::Metrics;
+///
+/// let metrics = Metrics::new().buckets([0.5, 0.1]);
+/// metrics.record("GET", "/a", 200, Duration::from_millis(50));
+/// metrics.record("GET", "/a", 200, Duration::from_millis(300));
+/// metrics.record("GET", "/quote\"back\\slash\nnewline", 500, Duration::from_secs(1));
+///
+/// let rendered = metrics.render();
+/// assert!(rendered.contains(
+///     "torus_http_request_duration_seconds_bucket{method=\"GET\",route=\"/a\",le=\"0.1\"} 1\n\
+///      torus_http_request_duration_seconds_bucket{method=\"GET\",route=\"/a\",le=\"0.5\"} 2\n\
+///      torus_http_request_duration_seconds_bucket{method=\"GET\",route=\"/a\",le=\"+Inf\"} 2\n\
+///      torus_http_request_duration_seconds_sum{method=\"GET\",route=\"/a\"} 0.35\n"
+/// ));
+/// assert!(rendered.contains(
+///     r#"torus_http_requests_total{method="GET",route="/quote\"back\\slash\nnewline",status="500"} 1"#
+/// ));
+/// // every metric is announced before its samples
+/// for line in rendered.lines().filter(|line| line.starts_with('#')) {
+///     let mut parts = line.splitn(4, ' ');
+///     assert_eq!(parts.next(), Some("#"));
+///     assert!(matches!(parts.next(), Some("HELP" | "TYPE")));
+///     assert!(parts.next().unwrap().starts_with("torus_http_"));
+///     assert!(parts.next().is_some());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Metrics {
+    buckets: Arc<[f64]>,
+    collected: Arc<Mutex<Collected>>,
+    active_connections: Arc<AtomicI64>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            buckets: DEFAULT_BUCKETS.into(),
+            collected: Arc::default(),
+            active_connections: Arc::default(),
+        }
+    }
+}
+
+impl Metrics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Upper bounds of the duration histogram in seconds, in any order, `+Inf` is always added
+    ///
+    /// Defaults to the usual `0.005` up to `10` seconds. Bounds that aren't finite are dropped,
+    /// as is everything counted so far
+    #[must_use]
+    pub fn buckets(mut self, buckets: impl Into<Vec<f64>>) -> Self {
+        let mut buckets = buckets.into();
+        buckets.retain(|bound| bound.is_finite());
+        buckets.sort_by(f64::total_cmp);
+        buckets.dedup();
+        self.buckets = buckets.into();
+        self.collected = Arc::default();
+        self
+    }
+
+    /// Counts one handled request
+    pub fn record(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let mut collected = self
+            .collected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *collected
+            .requests
+            .entry((method.to_owned(), route.to_owned(), status))
+            .or_default() += 1;
+
+        let histogram = collected
+            .durations
+            .entry((method.to_owned(), route.to_owned()))
+            .or_default();
+        histogram.counts.resize(self.buckets.len(), 0);
+        let seconds = elapsed.as_secs_f64();
+        if let Some(bucket) = self.buckets.iter().position(|bound| seconds <= *bound) {
+            histogram.counts[bucket] += 1;
+        }
+        histogram.sum += seconds;
+        histogram.count += 1;
+    }
+
+    /// Counts a connection as open until the returned guard is dropped
+    pub(crate) fn connection_opened(&self) -> ConnectionGuard {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard(self.active_connections.clone())
+    }
+
+    /// Everything counted so far, in the Prometheus text format
+    #[must_use]
+    pub fn render(&self) -> String {
+        let collected = self
+            .collected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut out = String::new();
+
+        describe(
+            &mut out,
+            "torus_http_requests_total",
+            "Requests handled, by method, route and status",
+            "counter",
+        );
+        for ((method, route, status), count) in &collected.requests {
+            _ = writeln!(
+                out,
+                "torus_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{status}\"}} {count}",
+                escape(method),
+                escape(route),
+            );
+        }
+
+        describe(
+            &mut out,
+            "torus_http_request_duration_seconds",
+            "Time spent handling requests, by method and route",
+            "histogram",
+        );
+        for ((method, route), histogram) in &collected.durations {
+            let labels = format!("method=\"{}\",route=\"{}\"", escape(method), escape(route));
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(&histogram.counts) {
+                cumulative += count;
+                _ = writeln!(
+                    out,
+                    "torus_http_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            _ = writeln!(
+                out,
+                "torus_http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            _ = writeln!(
+                out,
+                "torus_http_request_duration_seconds_sum{{{labels}}} {}",
+                histogram.sum
+            );
+            _ = writeln!(
+                out,
+                "torus_http_request_duration_seconds_count{{{labels}}} {}",
+                histogram.count
+            );
+        }
+
+        describe(
+            &mut out,
+            "torus_http_active_connections",
+            "Connections currently open",
+            "gauge",
+        );
+        _ = writeln!(
+            out,
+            "torus_http_active_connections {}",
+            self.active_connections.load(Ordering::Relaxed)
+        );
+        out
+    }
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics")
+            .field("buckets", &self.buckets)
+            .field(
+                "active_connections",
+                &self.active_connections.load(Ordering::Relaxed),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keeps a connection counted in [`Metrics`] while it is alive
+pub(crate) struct ConnectionGuard(Arc<AtomicI64>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The `# HELP` and `# TYPE` lines of a metric
+fn describe(out: &mut String, name: &str, help: &str, kind: &str) {
+    _ = writeln!(out, "# HELP {name} {help}");
+    _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escapes a label value, only `\`, `"` and line breaks need it
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,402 @@
+//! Building blocks for middleware, the functions that get to look at (and mess with) every
+//! request before it reaches a handler
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use torus_http::prelude::*;
+//!
+//! let server = HttpServer::new()
+//!     .add_middleware(|req: HttpRequest| {
+//!         if req.headers.contains_key("Authorization") {
+//!             MiddlewareResult::Continue(req)
+//!         } else {
+//!             MiddlewareResult::Respond(HttpResponse::new().set_status(HttpStatus::UNAUTHORIZED))
+//!         }
+//!     })
+//!     .get("/", |_| "secret stuff");
+//!
+//! let request = HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+//! assert_eq!(server.handle(request).status, HttpStatus::UNAUTHORIZED);
+//!
+//! let request = HttpRequest::from_str("GET / HTTP/1.1\r\nAuthorization: yes\r\n\r\n").unwrap();
+//! assert_eq!(server.handle(request).body_str(), Some("secret stuff"));
+//! ```
+//!
+//! # Middleware order
+//!
+//! A request passes through the layers in this order, each kind running in its registration
+//! order unless stated otherwise:
+//!
+//! 1. the https redirect ([`HttpServer::with_https_redirect`]), if enabled
+//! 2. around middleware ([`HttpServer::add_around_middleware`]), the first one outermost
+//! 3. request middleware ([`HttpServer::add_middleware`] and friends), sorted by priority
+//! 4. route middleware ([`HttpServer::route_with_middleware`])
+//! 5. the handler
+//! 6. response middleware ([`HttpServer::add_response_middleware`])
+//! 7. back out through the around middleware, the last one first
+//!
+//! A middleware answering early skips everything up to the response middleware, which see every
+//! response, the ones to requests that never reach the handler included. The exceptions are the
+//! https redirect, which goes out before any middleware ran, and the `503` a full worker pool
+//! ([`HttpServer::workers`]) answers with before reading anything, there is no request to hand
+//! them
+//!
+//! ```rust
+//! use std::{str::FromStr, sync::Mutex};
+//! use torus_http::prelude::*;
+//!
+//! static TRAIL: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+//! fn visit(step: &'static str) {
+//!     TRAIL.lock().unwrap().push(step);
+//! }
+//!
+//! let server = HttpServer::new()
+//!     .add_response_middleware(|_, res| {
+//!         visit("response");
+//!         res
+//!     })
+//!     .add_middleware(|req| {
+//!         visit("request");
+//!         req
+//!     })
+//!     .add_around_middleware(|req, next: Next| {
+//!         visit("outer before");
+//!         let res = next.run(req);
+//!         visit("outer after");
+//!         res
+//!     })
+//!     .add_middleware_with_priority("early", -1, |req| {
+//!         visit("early request");
+//!         req
+//!     })
+//!     .add_around_middleware(|req, next: Next| {
+//!         visit("inner before");
+//!         let res = next.run(req);
+//!         visit("inner after");
+//!         res
+//!     })
+//!     .get_with_middleware("/", vec![|req| {
+//!         visit("route");
+//!         MiddlewareResult::Continue(req)
+//!     }], |_| {
+//!         visit("handler");
+//!         "done"
+//!     });
+//!
+//! _ = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+//! assert_eq!(
+//!     *TRAIL.lock().unwrap(),
+//!     [
+//!         "outer before",
+//!         "inner before",
+//!         "early request",
+//!         "request",
+//!         "route",
+//!         "handler",
+//!         "response",
+//!         "inner after",
+//!         "outer after",
+//!     ]
+//! );
+//! ```
+//!
+//! [`HttpServer::add_around_middleware`]: crate::server::HttpServer::add_around_middleware
+//! [`HttpServer::add_middleware`]: crate::server::HttpServer::add_middleware
+//! [`HttpServer::route_with_middleware`]: crate::server::HttpServer::route_with_middleware
+//! [`HttpServer::add_response_middleware`]: crate::server::HttpServer::add_response_middleware
+//! [`HttpServer::workers`]: crate::server::HttpServer::workers
+//! [`HttpServer::with_https_redirect`]: crate::server::HttpServer::with_https_redirect
+pub mod basic_auth;
+pub mod cache_policy;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod cookies;
+pub mod cors;
+pub mod csrf;
+pub mod normalize_path;
+pub mod rate_limit;
+pub mod request_logger;
+pub mod security_headers;
+pub mod server_timing;
+pub mod session;
+pub mod timeout;
+
+use std::{error::Error, sync::Arc};
+
+use crate::{request::HttpRequest, response::HttpResponse};
+
+/// Middleware that wraps around everything after it, getting to run code both before and after
+/// the handler
+///
+/// Implemented for closures of the shape `Fn(HttpRequest, Next) -> HttpResponse`, register them
+/// with [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// # Example:
+///
+/// ```rust
+/// use std::{str::FromStr, time::Instant};
+/// use torus_http::prelude::*;
+///
+/// let server = HttpServer::new()
+///     .add_around_middleware(|req, next: Next| {
+///         let start = Instant::now();
+///         let res = next.run(req);
+///         res.insert_header("X-Elapsed-Micros", start.elapsed().as_micros().to_string())
+///     })
+///     .add_around_middleware(|req: HttpRequest, next: Next| {
+///         if req.path == "/cached" {
+///             return HttpResponse::new().set_body("from cache");
+///         }
+///         next.run(req)
+///     })
+///     .get("/", |_| "hi")
+///     .get("/cached", |_| "from the handler");
+///
+/// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+/// assert_eq!(res.body_str(), Some("hi"));
+/// assert!(res.headers.contains_key("X-Elapsed-Micros"));
+///
+/// let res = server.handle(HttpRequest::from_str("GET /cached HTTP/1.1\r\n\r\n").unwrap());
+/// assert_eq!(res.body_str(), Some("from cache"));
+/// assert!(res.headers.contains_key("X-Elapsed-Micros"));
+/// ```
+pub trait Middleware: Send + Sync {
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse;
+
+    /// Only run this middleware for requests `predicate` accepts, the others go straight on to
+    /// the rest of the chain
+    ///
+    /// The predicate is asked once per request, before the middleware would run. See
+    /// [`HttpServer::add_middleware_when`](crate::server::HttpServer::add_middleware_when) for
+    /// an example
+    #[must_use]
+    fn when<P>(self, predicate: P) -> When<Self, P>
+    where
+        Self: Sized,
+        P: Fn(&HttpRequest) -> bool + Send + Sync,
+    {
+        When {
+            middleware: self,
+            predicate,
+        }
+    }
+}
+
+/// A middleware that only runs for some requests, see [`Middleware::when`]
+pub struct When<M, P> {
+    middleware: M,
+    predicate: P,
+}
+
+impl<M, P> Middleware for When<M, P>
+where
+    M: Middleware,
+    P: Fn(&HttpRequest) -> bool + Send + Sync,
+{
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        if (self.predicate)(&req) {
+            self.middleware.call(req, next)
+        } else {
+            next.run(req)
+        }
+    }
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(HttpRequest, Next<'_>) -> HttpResponse + Send + Sync,
+{
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        self(req, next)
+    }
+}
+
+/// The rest of the middleware chain, ending in the handler
+pub struct Next<'a> {
+    layers: &'a [Box<dyn Middleware>],
+    endpoint: &'a dyn Fn(HttpRequest) -> HttpResponse,
+}
+
+impl<'a> Next<'a> {
+    /// A chain running `layers` in order and then `endpoint`, mostly useful for testing
+    /// middleware without a server
+    #[must_use]
+    pub fn new(
+        layers: &'a [Box<dyn Middleware>],
+        endpoint: &'a dyn Fn(HttpRequest) -> HttpResponse,
+    ) -> Self {
+        Self { layers, endpoint }
+    }
+
+    /// Pass the request on to the rest of the chain and get its response back
+    #[must_use]
+    pub fn run(self, req: HttpRequest) -> HttpResponse {
+        match self.layers.split_first() {
+            Some((layer, layers)) => layer.call(req, Next::new(layers, self.endpoint)),
+            None => (self.endpoint)(req),
+        }
+    }
+}
+
+/// What a middleware wants to happen next
+///
+/// Middleware returning a plain [`HttpRequest`] always continue, ones returning a `Result` stop
+/// the chain on `Err`
+///
+/// # Example:
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use torus_http::prelude::*;
+///
+/// let server = HttpServer::new()
+///     .add_middleware(|req: HttpRequest| {
+///         if req.header("Authorization").is_none() {
+///             return Err(HttpResponse::new().set_status(HttpStatus::UNAUTHORIZED));
+///         }
+///         Ok(req)
+///     })
+///     .get("/", |_| "welcome");
+///
+/// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+/// assert_eq!(res.status, HttpStatus::UNAUTHORIZED);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MiddlewareResult {
+    /// Pass the request on to the next middleware or the handler
+    Continue(HttpRequest),
+    /// Skip the remaining middleware and the handler, answering with this response instead
+    Respond(HttpResponse),
+    /// Skip the remaining middleware and the handler, the server turns the error into a response
+    /// with the mapper set through
+    /// [`HttpServer::on_middleware_error`](crate::server::HttpServer::on_middleware_error)
+    Fail(MiddlewareError),
+}
+
+impl From<HttpRequest> for MiddlewareResult {
+    fn from(value: HttpRequest) -> Self {
+        Self::Continue(value)
+    }
+}
+
+impl From<HttpResponse> for MiddlewareResult {
+    fn from(value: HttpResponse) -> Self {
+        Self::Respond(value)
+    }
+}
+
+impl From<Result<HttpRequest, HttpResponse>> for MiddlewareResult {
+    fn from(value: Result<HttpRequest, HttpResponse>) -> Self {
+        match value {
+            Ok(req) => Self::Continue(req),
+            Err(res) => Self::Respond(res),
+        }
+    }
+}
+
+impl From<Result<HttpRequest, MiddlewareError>> for MiddlewareResult {
+    fn from(value: Result<HttpRequest, MiddlewareError>) -> Self {
+        match value {
+            Ok(req) => Self::Continue(req),
+            Err(err) => Self::Fail(err),
+        }
+    }
+}
+
+/// Any error a middleware ran into, so `?` works in middleware returning
+/// `Result<HttpRequest, MiddlewareError>`
+///
+/// Look at the original error again with [`MiddlewareError::downcast_ref`]
+#[derive(Clone)]
+pub struct MiddlewareError(Arc<dyn Error + Send + Sync>);
+
+impl MiddlewareError {
+    /// The original error, if it was an `E`
+    #[must_use]
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        self.0.downcast_ref()
+    }
+}
+
+impl<E: Error + Send + Sync + 'static> From<E> for MiddlewareError {
+    fn from(value: E) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
+impl std::fmt::Debug for MiddlewareError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MiddlewareError").field(&self.0).finish()
+    }
+}
+
+impl std::fmt::Display for MiddlewareError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Errors are compared by identity, clones of the same error are equal
+impl PartialEq for MiddlewareError {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for MiddlewareError {}
+
+/// Adds `value` to the `Vary` header, keeping whatever was in there already
+pub(crate) fn add_vary(res: HttpResponse, value: &str) -> HttpResponse {
+    match res.headers.get("Vary") {
+        Some(vary)
+            if vary
+                .split(',')
+                .any(|v| v.trim().eq_ignore_ascii_case(value)) =>
+        {
+            res
+        }
+        Some(vary) => {
+            let vary = format!("{vary}, {value}");
+            res.insert_header("Vary", vary)
+        }
+        None => res.insert_header("Vary", value),
+    }
+}
+
+/// 128 random bits as hex, for session ids and the like
+///
+/// Comes from the os where `/dev/urandom` exists, otherwise from the randomly seeded std hasher
+pub(crate) fn random_token() -> String {
+    use std::{
+        fmt::Write,
+        hash::{BuildHasher, RandomState},
+        io::Read,
+    };
+
+    let mut bytes = [0u8; 16];
+    let from_os = std::fs::File::open("/dev/urandom")
+        .and_then(|mut urandom| urandom.read_exact(&mut bytes))
+        .is_ok();
+    if !from_os {
+        // `RandomState` keys are seeded randomly, so hashing anything gives unpredictable output
+        for half in bytes.chunks_mut(8) {
+            half.copy_from_slice(&RandomState::new().hash_one(0u8).to_le_bytes());
+        }
+    }
+    bytes.iter().fold(String::with_capacity(32), |mut hex, b| {
+        _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
+
+/// Compares two byte strings without bailing out at the first difference
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let len = a.len().max(b.len());
+    let diff = (0..len).fold(a.len() ^ b.len(), |diff, i| {
+        let (x, y) = (a.get(i).copied(), b.get(i).copied());
+        diff | usize::from(x.unwrap_or(0) ^ y.unwrap_or(0))
+    });
+    diff == 0
+}
@@ -0,0 +1,160 @@
+//! `Cache-Control` headers by path prefix
+//!
+//! [`CachePolicy`] adds a [`CacheControl`] to successful responses below the prefixes it was
+//! given, unless the handler already decided on a `Cache-Control` itself.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use torus_http::{
+//!     middleware::cache_policy::{CacheControl, CachePolicy},
+//!     prelude::*,
+//!     testing::TestClient,
+//! };
+//!
+//! let client = TestClient::new(
+//!     HttpServer::new()
+//!         .add_around_middleware(
+//!             CachePolicy::new()
+//!                 .prefix(
+//!                     "/static",
+//!                     CacheControl::public().max_age(Duration::from_hours(24 * 30)).immutable(),
+//!                 )
+//!                 .prefix("/api", CacheControl::no_store()),
+//!         )
+//!         .get("/static/app.js", |_| "console.log('hi')")
+//!         .get("/api/x", |_| "{}")
+//!         .get("/api/live", |_| HttpResponse::new().insert_header("Cache-Control", "max-age=5")),
+//! );
+//! let cache_control = |path: &str| client.get(path).send().header("Cache-Control").map(str::to_owned);
+//!
+//! assert_eq!(cache_control("/static/app.js").unwrap(), "public, max-age=2592000, immutable");
+//! assert_eq!(cache_control("/api/x").unwrap(), "no-store");
+//! // the handler knows best
+//! assert_eq!(cache_control("/api/live").unwrap(), "max-age=5");
+//! // errors aren't cached
+//! assert_eq!(cache_control("/static/missing.js"), None);
+//! ```
+use std::{fmt::Display, time::Duration};
+
+use crate::{
+    middleware::{Middleware, Next},
+    request::HttpRequest,
+    response::HttpResponse,
+};
+
+/// A `Cache-Control` value
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    directives: Vec<String>,
+}
+
+impl CacheControl {
+    fn with(mut self, directive: impl Into<String>) -> Self {
+        self.directives.push(directive.into());
+        self
+    }
+
+    /// Cacheable by browsers and shared caches
+    #[must_use]
+    pub fn public() -> Self {
+        Self::default().with("public")
+    }
+
+    /// Cacheable by browsers only
+    #[must_use]
+    pub fn private() -> Self {
+        Self::default().with("private")
+    }
+
+    /// Never stored anywhere
+    #[must_use]
+    pub fn no_store() -> Self {
+        Self::default().with("no-store")
+    }
+
+    /// Stored, but revalidated before every use
+    #[must_use]
+    pub fn no_cache() -> Self {
+        Self::default().with("no-cache")
+    }
+
+    /// Fresh for `age`, counted in whole seconds
+    #[must_use]
+    pub fn max_age(self, age: Duration) -> Self {
+        self.with(format!("max-age={}", age.as_secs()))
+    }
+
+    /// The content never changes, so don't even revalidate on reload
+    #[must_use]
+    pub fn immutable(self) -> Self {
+        self.with("immutable")
+    }
+
+    #[must_use]
+    pub fn must_revalidate(self) -> Self {
+        self.with("must-revalidate")
+    }
+}
+
+impl Display for CacheControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.directives.join(", "))
+    }
+}
+
+/// Cache policy middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// When prefixes overlap the longest one wins
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    prefixes: Vec<(String, CacheControl)>,
+}
+
+impl CachePolicy {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `control` for `prefix` and everything below it, `/static` covers `/static/app.js` but
+    /// not `/statics`
+    #[must_use]
+    pub fn prefix(mut self, prefix: impl Into<String>, control: CacheControl) -> Self {
+        let prefix = prefix.into().trim_end_matches('/').to_owned();
+        self.prefixes.push((prefix, control));
+        self
+    }
+
+    fn policy_for(&self, path: &str) -> Option<&CacheControl> {
+        self.prefixes
+            .iter()
+            .filter(|(prefix, _)| {
+                path.strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, control)| control)
+    }
+}
+
+impl Middleware for CachePolicy {
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let policy = self.policy_for(&req.path);
+        let res = next.run(req);
+        match policy {
+            Some(control)
+                if (200..300).contains(&res.status.code())
+                    && !res
+                        .headers
+                        .keys()
+                        .any(|k| k.eq_ignore_ascii_case("Cache-Control")) =>
+            {
+                res.insert_header("Cache-Control", control.to_string())
+            }
+            _ => res,
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Transparent response compression, needs the `compression` feature
+//!
+//! [`Compression`] looks at the client's `Accept-Encoding` and gzips (or deflates) text-ish
+//! response bodies on the way out, so handlers don't have to care about it.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{io::Read, str::FromStr};
+//! use flate2::read::GzDecoder;
+//! use torus_http::{middleware::compression::Compression, prelude::*};
+//!
+//! let json = format!("[{}0]", "1234567890,".repeat(200));
+//! let body = json.clone();
+//! let server = HttpServer::new()
+//!     .add_around_middleware(Compression::default())
+//!     .get("/data", move |_| {
+//!         HttpResponse::new()
+//!             .set_body(body.clone())
+//!             .insert_header("Content-Type", "application/json")
+//!     })
+//!     .get("/logo.png", |_| {
+//!         HttpResponse::new()
+//!             .set_body(vec![0x89; 4096])
+//!             .insert_header("Content-Type", "image/png")
+//!     });
+//!
+//! let request = |path: &str| {
+//!     let raw = format!("GET {path} HTTP/1.1\r\nAccept-Encoding: gzip, deflate\r\n\r\n");
+//!     HttpRequest::from_str(&raw).unwrap()
+//! };
+//!
+//! let res = server.handle(request("/data"));
+//! assert_eq!(res.headers["Content-Encoding"], "gzip");
+//! assert_eq!(res.headers["Vary"], "Accept-Encoding");
+//! let compressed = res.body.unwrap();
+//! assert_eq!(res.headers["Content-Length"], compressed.len().to_string());
+//! let mut decompressed = String::new();
+//! GzDecoder::new(&compressed[..]).read_to_string(&mut decompressed).unwrap();
+//! assert_eq!(decompressed, json);
+//!
+//! // images are compressed already
+//! let res = server.handle(request("/logo.png"));
+//! assert!(!res.headers.contains_key("Content-Encoding"));
+//! assert_eq!(res.body.unwrap(), vec![0x89; 4096]);
+//! ```
+use std::io::Write;
+
+use flate2::{
+    Compression as Level,
+    write::{DeflateEncoder, GzEncoder},
+};
+
+use crate::{
+    middleware::{Middleware, Next, add_vary},
+    request::HttpRequest,
+    response::HttpResponse,
+    status::{HttpStatus, RedirectionResponse, SuccessResponse},
+};
+
+/// Content codings [`Compression`] can apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coding {
+    Gzip,
+    Deflate,
+}
+
+impl Coding {
+    /// The `Content-Encoding` token
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Coding::Gzip => "gzip",
+            Coding::Deflate => "deflate",
+        }
+    }
+
+    fn encode(self, body: &[u8], level: Level) -> std::io::Result<Vec<u8>> {
+        match self {
+            Coding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            Coding::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), level);
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Compression middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// By default bodies of at least 1KiB with a text, json, javascript, xml or svg content type get
+/// compressed, preferring gzip over deflate
+#[derive(Debug, Clone)]
+pub struct Compression {
+    threshold: usize,
+    codings: Vec<Coding>,
+    excluded: Vec<String>,
+    level: Level,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            threshold: 1024,
+            codings: vec![Coding::Gzip, Coding::Deflate],
+            excluded: Vec::new(),
+            level: Level::default(),
+        }
+    }
+}
+
+impl Compression {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bodies smaller than this many bytes are sent as they are
+    #[must_use]
+    pub fn threshold(mut self, bytes: usize) -> Self {
+        self.threshold = bytes;
+        self
+    }
+
+    /// Codings to offer, in order of preference
+    #[must_use]
+    pub fn codings(mut self, codings: impl IntoIterator<Item = Coding>) -> Self {
+        self.codings = codings.into_iter().collect();
+        self
+    }
+
+    /// Never compress this content type (ignoring parameters like `charset`), e.g.
+    /// `text/event-stream`
+    #[must_use]
+    pub fn exclude_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.excluded.push(content_type.into());
+        self
+    }
+
+    /// Compression level from 0 (none) to 9 (best)
+    #[must_use]
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Level::new(level.min(9));
+        self
+    }
+
+    fn is_eligible(&self, res: &HttpResponse) -> bool {
+        let no_body = [
+            HttpStatus::Success(SuccessResponse::NoContent),
+            HttpStatus::Redirection(RedirectionResponse::NotModified),
+        ];
+        if no_body.contains(&res.status)
+            || res.status.code() < 200
+            || header(res, "Content-Encoding").is_some()
+            || res
+                .body
+                .as_ref()
+                .is_none_or(|body| body.len() < self.threshold)
+        {
+            return false;
+        }
+        let Some(content_type) = header(res, "Content-Type") else {
+            return false;
+        };
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        if self
+            .excluded
+            .iter()
+            .any(|ex| ex.eq_ignore_ascii_case(&mime))
+        {
+            return false;
+        }
+        mime.starts_with("text/")
+            || mime.ends_with("+json")
+            || mime.ends_with("+xml")
+            || matches!(
+                mime.as_str(),
+                "application/json" | "application/javascript" | "application/xml" | "image/svg+xml"
+            )
+    }
+
+    /// The coding the client likes best, ties going to our own preference order
+    fn negotiate(&self, req: &HttpRequest) -> Option<Coding> {
+        let mut best: Option<(Coding, f32)> = None;
+        for &coding in &self.codings {
+            let q = req.encoding_quality(coding.as_str());
+            if q > 0.0 && best.is_none_or(|(_, best)| q > best) {
+                best = Some((coding, q));
+            }
+        }
+        best.map(|(coding, _)| coding)
+    }
+}
+
+impl Middleware for Compression {
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let coding = self.negotiate(&req);
+        let res = next.run(req);
+        if !self.is_eligible(&res) {
+            return res;
+        }
+        let res = add_vary(res, "Accept-Encoding");
+        let Some(coding) = coding else {
+            return res;
+        };
+        let body = res.body.as_deref().unwrap_or_default();
+        match coding.encode(body, self.level) {
+            // not worth it if it didn't get any smaller
+            Ok(compressed) if compressed.len() < body.len() => res
+                .set_body(compressed)
+                .insert_header("Content-Encoding", coding.as_str()),
+            _ => res,
+        }
+    }
+}
+
+/// Case insensitive response header lookup
+fn header<'a>(res: &'a HttpResponse, name: &str) -> Option<&'a str> {
+    res.headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
@@ -0,0 +1,23 @@
+//! Hands handlers a [`CookieJar`] and sends back whatever they changed in it
+//!
+//! See the [`cookie`](crate::cookie) module for an example
+use crate::{
+    cookie::CookieJar,
+    middleware::{Middleware, Next},
+    request::HttpRequest,
+    response::HttpResponse,
+};
+
+/// Cookie jar middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cookies;
+
+impl Middleware for Cookies {
+    fn call(&self, mut req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let jar = CookieJar::from_request(&req);
+        req.extensions.insert(jar.clone());
+        let res = next.run(req);
+        jar.apply(res)
+    }
+}
@@ -0,0 +1,278 @@
+//! Cross-origin resource sharing
+//!
+//! [`Cors`] answers preflight requests itself and adds the `Access-Control-*` headers to the
+//! responses for allowed origins. Requests from other origins go through untouched, it is up to
+//! the browser to refuse them.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{str::FromStr, time::Duration};
+//! use torus_http::{middleware::cors::Cors, prelude::*, status::SuccessResponse};
+//!
+//! let server = HttpServer::new()
+//!     .add_around_middleware(
+//!         Cors::new()
+//!             .allow_origin("https://app.example")
+//!             .allow_methods([HttpMethod::Get, HttpMethod::Put])
+//!             .allow_headers(["Content-Type"])
+//!             .max_age(Duration::from_secs(600)),
+//!     )
+//!     .put("/items", |_| "updated");
+//!
+//! let preflight = HttpRequest::from_str(
+//!     "OPTIONS /items HTTP/1.1\r\nOrigin: https://app.example\r\n\
+//!      Access-Control-Request-Method: PUT\r\n\r\n",
+//! )
+//! .unwrap();
+//! let res = server.handle(preflight);
+//! assert_eq!(res.status, SuccessResponse::NoContent.into());
+//! assert_eq!(res.headers["Access-Control-Allow-Origin"], "https://app.example");
+//! assert_eq!(res.headers["Access-Control-Allow-Methods"], "GET, PUT");
+//! assert_eq!(res.headers["Access-Control-Allow-Headers"], "Content-Type");
+//! assert_eq!(res.headers["Access-Control-Max-Age"], "600");
+//!
+//! let actual = HttpRequest::from_str(
+//!     "PUT /items HTTP/1.1\r\nOrigin: https://app.example\r\n\r\nnew",
+//! )
+//! .unwrap();
+//! let res = server.handle(actual);
+//! assert_eq!(res.body_str(), Some("updated"));
+//! assert_eq!(res.headers["Access-Control-Allow-Origin"], "https://app.example");
+//! assert_eq!(res.headers["Vary"], "Origin");
+//!
+//! // other origins get a normal response, just without the cors headers
+//! let foreign = HttpRequest::from_str(
+//!     "PUT /items HTTP/1.1\r\nOrigin: https://evil.example\r\n\r\nnew",
+//! )
+//! .unwrap();
+//! let res = server.handle(foreign);
+//! assert_eq!(res.body_str(), Some("updated"));
+//! assert!(!res.headers.contains_key("Access-Control-Allow-Origin"));
+//! ```
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    method::HttpMethod,
+    middleware::{Middleware, Next, add_vary},
+    request::HttpRequest,
+    response::HttpResponse,
+    status::HttpStatus,
+};
+
+type OriginPredicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+enum AllowOrigin {
+    Any,
+    List(Vec<String>),
+    Predicate(OriginPredicate),
+}
+
+#[derive(Clone)]
+enum AllowHeaders {
+    List(Vec<String>),
+    /// Echo whatever the preflight asked for
+    Mirror,
+}
+
+/// Configurable cors middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// By default no origin is allowed, the methods are `GET`, `HEAD` and `POST` and no extra
+/// headers are allowed
+#[derive(Clone)]
+pub struct Cors {
+    origins: AllowOrigin,
+    methods: Vec<HttpMethod>,
+    headers: AllowHeaders,
+    expose_headers: Vec<String>,
+    max_age: Option<Duration>,
+    credentials: bool,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self {
+            origins: AllowOrigin::List(Vec::new()),
+            methods: vec![HttpMethod::Get, HttpMethod::Head, HttpMethod::Post],
+            headers: AllowHeaders::List(Vec::new()),
+            expose_headers: Vec::new(),
+            max_age: None,
+            credentials: false,
+        }
+    }
+}
+
+impl Cors {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow requests from this exact origin, e.g. `https://example.com`
+    ///
+    /// Can be called multiple times to allow several origins
+    #[must_use]
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        match &mut self.origins {
+            AllowOrigin::List(origins) => origins.push(origin.into()),
+            origins => *origins = AllowOrigin::List(vec![origin.into()]),
+        }
+        self
+    }
+
+    /// Allow requests from any origin, answered with `Access-Control-Allow-Origin: *`
+    ///
+    /// # Panics
+    ///
+    /// Browsers refuse `*` for credentialed requests, so this panics if
+    /// [`Cors::allow_credentials`] was set
+    #[must_use]
+    pub fn allow_any_origin(mut self) -> Self {
+        assert!(
+            !self.credentials,
+            "cors: credentials can't be combined with a wildcard origin"
+        );
+        self.origins = AllowOrigin::Any;
+        self
+    }
+
+    /// Decide per origin whether it is allowed
+    ///
+    /// ```rust
+    /// use torus_http::middleware::cors::Cors;
+    ///
+    /// let cors = Cors::new().allow_origin_fn(|origin| origin.ends_with(".example.com"));
+    /// ```
+    #[must_use]
+    pub fn allow_origin_fn(mut self, f: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.origins = AllowOrigin::Predicate(Arc::new(f));
+        self
+    }
+
+    /// Methods a preflight may ask for, replacing the defaults
+    #[must_use]
+    pub fn allow_methods(mut self, methods: impl IntoIterator<Item = HttpMethod>) -> Self {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Request headers a preflight may ask for
+    #[must_use]
+    pub fn allow_headers<S: Into<String>>(mut self, headers: impl IntoIterator<Item = S>) -> Self {
+        self.headers = AllowHeaders::List(headers.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Allow whatever headers a preflight asks for
+    #[must_use]
+    pub fn allow_any_header(mut self) -> Self {
+        self.headers = AllowHeaders::Mirror;
+        self
+    }
+
+    /// Response headers scripts on the other origin get to read
+    #[must_use]
+    pub fn expose_headers<S: Into<String>>(mut self, headers: impl IntoIterator<Item = S>) -> Self {
+        self.expose_headers = headers.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// How long browsers may cache the preflight answer
+    #[must_use]
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Allow cookies and `Authorization` headers on cross origin requests
+    ///
+    /// # Panics
+    ///
+    /// When combined with [`Cors::allow_any_origin`], browsers would reject every response
+    /// anyway:
+    ///
+    /// ```rust,should_panic
+    /// use torus_http::middleware::cors::Cors;
+    ///
+    /// let cors = Cors::new().allow_any_origin().allow_credentials(true);
+    /// ```
+    #[must_use]
+    pub fn allow_credentials(mut self, credentials: bool) -> Self {
+        assert!(
+            !(credentials && matches!(self.origins, AllowOrigin::Any)),
+            "cors: credentials can't be combined with a wildcard origin"
+        );
+        self.credentials = credentials;
+        self
+    }
+
+    /// The `Access-Control-Allow-Origin` value for `origin`, `None` if it isn't allowed
+    fn allowed_origin<'a>(&self, origin: &'a str) -> Option<&'a str> {
+        let allowed = match &self.origins {
+            AllowOrigin::Any => return Some("*"),
+            AllowOrigin::List(origins) => origins.iter().any(|o| o == origin),
+            AllowOrigin::Predicate(f) => f(origin),
+        };
+        allowed.then_some(origin)
+    }
+
+    fn preflight(&self, req: &HttpRequest, allow_origin: &str) -> HttpResponse {
+        let methods: Vec<&str> = self.methods.iter().map(HttpMethod::as_str).collect();
+        let mut res = HttpResponse::new()
+            .set_status(HttpStatus::NO_CONTENT)
+            .insert_header("Access-Control-Allow-Methods", methods.join(", "));
+        let headers = match &self.headers {
+            AllowHeaders::List(headers) => headers.join(", "),
+            AllowHeaders::Mirror => req
+                .header("Access-Control-Request-Headers")
+                .unwrap_or_default()
+                .to_owned(),
+        };
+        if !headers.is_empty() {
+            res = res.insert_header("Access-Control-Allow-Headers", headers);
+        }
+        if let Some(max_age) = self.max_age {
+            res = res.insert_header("Access-Control-Max-Age", max_age.as_secs().to_string());
+        }
+        self.decorate(res, allow_origin)
+    }
+
+    fn decorate(&self, mut res: HttpResponse, allow_origin: &str) -> HttpResponse {
+        if allow_origin != "*" {
+            res = add_vary(res, "Origin");
+        }
+        if self.credentials {
+            res = res.insert_header("Access-Control-Allow-Credentials", "true");
+        }
+        if !self.expose_headers.is_empty() {
+            res = res.insert_header(
+                "Access-Control-Expose-Headers",
+                self.expose_headers.join(", "),
+            );
+        }
+        res.insert_header("Access-Control-Allow-Origin", allow_origin)
+    }
+}
+
+impl Middleware for Cors {
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let Some(origin) = req.header("Origin").map(str::to_owned) else {
+            return next.run(req);
+        };
+        let is_preflight = req.method == HttpMethod::Options
+            && req.header("Access-Control-Request-Method").is_some();
+
+        match self.allowed_origin(&origin) {
+            Some(allow_origin) if is_preflight => self.preflight(&req, allow_origin),
+            Some(allow_origin) => self.decorate(next.run(req), allow_origin),
+            // the answer depends on the origin even when it is refused
+            None if is_preflight => add_vary(
+                HttpResponse::new().set_status(HttpStatus::NO_CONTENT),
+                "Origin",
+            ),
+            None => add_vary(next.run(req), "Origin"),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Cross-site request forgery protection using double submit cookies
+//!
+//! [`Csrf`] hands every client a random token in a cookie and makes it available to handlers as a
+//! [`CsrfToken`] in the request extensions, so it can be put into forms or read by scripts.
+//! Requests with a state changing method (anything but `GET`, `HEAD`, `OPTIONS` and `TRACE`) have
+//! to send the same token back in the `X-CSRF-Token` header or a `csrf_token` form field, or they
+//! get a `403 Forbidden`. Other sites can make the browser send the cookie but can't read it, so they
+//! can't come up with the matching token.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use torus_http::{
+//!     middleware::csrf::{Csrf, CsrfToken},
+//!     prelude::*,
+//! };
+//!
+//! let server = HttpServer::new()
+//!     .add_around_middleware(Csrf::new())
+//!     .get("/form", |req: HttpRequest| {
+//!         let token = req.extensions.get::<CsrfToken>().unwrap();
+//!         format!("<input type=\"hidden\" name=\"csrf_token\" value=\"{}\">", token.0)
+//!     })
+//!     .post("/transfer", |_| "done");
+//!
+//! // getting the form issues a token
+//! let res = server.handle(HttpRequest::from_str("GET /form HTTP/1.1\r\n\r\n").unwrap());
+//! let cookie = res.headers["Set-Cookie"].split(';').next().unwrap().to_owned();
+//! let token = cookie.strip_prefix("torus_csrf=").unwrap();
+//! assert!(res.body_str().unwrap().contains(token));
+//!
+//! // posting without it fails
+//! let forged = format!("POST /transfer HTTP/1.1\r\nCookie: {cookie}\r\n\r\n");
+//! let res = server.handle(HttpRequest::from_str(&forged).unwrap());
+//! assert_eq!(res.status, HttpStatus::FORBIDDEN);
+//!
+//! // with the token in the header or the form it goes through
+//! let header = format!("POST /transfer HTTP/1.1\r\nCookie: {cookie}\r\nX-CSRF-Token: {token}\r\n\r\n");
+//! let res = server.handle(HttpRequest::from_str(&header).unwrap());
+//! assert_eq!(res.body_str(), Some("done"));
+//!
+//! let form = format!(
+//!     "POST /transfer HTTP/1.1\r\nCookie: {cookie}\r\n\
+//!      Content-Type: application/x-www-form-urlencoded\r\n\r\namount=10&csrf_token={token}"
+//! );
+//! let res = server.handle(HttpRequest::from_str(&form).unwrap());
+//! assert_eq!(res.body_str(), Some("done"));
+//!
+//! // a token that doesn't match the cookie doesn't help either
+//! let wrong = format!("POST /transfer HTTP/1.1\r\nCookie: {cookie}\r\nX-CSRF-Token: 1234\r\n\r\n");
+//! let res = server.handle(HttpRequest::from_str(&wrong).unwrap());
+//! assert_eq!(res.status, HttpStatus::FORBIDDEN);
+//! ```
+use crate::{
+    cookie::SameSite,
+    middleware::{Middleware, Next, constant_time_eq, random_token},
+    request::HttpRequest,
+    response::HttpResponse,
+    server::ErrorFormat,
+    status::HttpStatus,
+};
+
+/// The token of the current client, found in the request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrfToken(pub String);
+
+/// Csrf middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// Defaults to a `torus_csrf` cookie with `SameSite=Lax`, checked against the `X-CSRF-Token`
+/// header or the `csrf_token` form field. The cookie isn't `HttpOnly` so scripts can copy it into
+/// the header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Csrf {
+    cookie_name: String,
+    header_name: String,
+    field_name: String,
+    same_site: SameSite,
+    secure: bool,
+}
+
+impl Default for Csrf {
+    fn default() -> Self {
+        Self {
+            cookie_name: "torus_csrf".into(),
+            header_name: "X-CSRF-Token".into(),
+            field_name: "csrf_token".into(),
+            same_site: SameSite::Lax,
+            secure: false,
+        }
+    }
+}
+
+impl Csrf {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    #[must_use]
+    pub fn header_name(mut self, name: impl Into<String>) -> Self {
+        self.header_name = name.into();
+        self
+    }
+
+    /// Name of the field in `application/x-www-form-urlencoded` bodies holding the token
+    #[must_use]
+    pub fn field_name(mut self, name: impl Into<String>) -> Self {
+        self.field_name = name.into();
+        self
+    }
+
+    #[must_use]
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Only send the cookie over https
+    #[must_use]
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    fn cookie(&self, token: &str) -> String {
+        let mut cookie = format!(
+            "{}={token}; Path=/; SameSite={}",
+            self.cookie_name,
+            self.same_site.as_str()
+        );
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+
+    /// The token the request came with, from the header or else the form body
+    fn submitted_token<'a>(&self, req: &'a HttpRequest) -> Option<&'a str> {
+        if let Some(token) = req.header(&self.header_name) {
+            return Some(token.trim());
+        }
+        let is_form = req.header("Content-Type").is_some_and(|content_type| {
+            content_type
+                .split(';')
+                .next()
+                .is_some_and(|mime| mime.trim() == "application/x-www-form-urlencoded")
+        });
+        if !is_form {
+            return None;
+        }
+        req.body_str()?.split('&').find_map(|field| {
+            let (name, value) = field.split_once('=')?;
+            (name == self.field_name).then_some(value)
+        })
+    }
+}
+
+impl Middleware for Csrf {
+    fn call(&self, mut req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let existing = req
+            .cookie(&self.cookie_name)
+            .filter(|token| !token.is_empty())
+            .map(str::to_owned);
+
+        if !req.method.is_safe() {
+            let valid = existing.as_deref().is_some_and(|expected| {
+                self.submitted_token(&req)
+                    .is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+            });
+            if !valid {
+                return ErrorFormat::of(&req)
+                    .response(HttpStatus::FORBIDDEN, "missing or invalid csrf token");
+            }
+        }
+
+        let issued = existing.is_none();
+        let token = existing.unwrap_or_else(random_token);
+        req.extensions.insert(CsrfToken(token.clone()));
+        let res = next.run(req);
+        if issued {
+            res.insert_header("Set-Cookie", self.cookie(&token))
+        } else {
+            res
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Cleaning up request paths before they are routed
+//!
+//! [`NormalizePath`] collapses duplicate slashes and resolves `.` and `..` segments, so
+//! `/a/./b//../c` reaches the route for `/a/c`. Paths climbing above the root are answered with
+//! `400 Bad Request`, which keeps `..` tricks away from anything serving files.
+//!
+//! Dot segments are recognised percent-encoded as well (`%2e%2e`), any other escapes are left as
+//! they are. The path as the client sent it is kept in the extensions as [`OriginalPath`].
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use torus_http::{
+//!     middleware::normalize_path::{NormalizePath, OriginalPath},
+//!     prelude::*,
+//! };
+//!
+//! let server = HttpServer::new()
+//!     .add_around_middleware(NormalizePath)
+//!     .get("/a/c", |req: HttpRequest| {
+//!         let original = req.extensions.get::<OriginalPath>().unwrap();
+//!         format!("{} from {}", req.path, original.0)
+//!     });
+//!
+//! let res = server.handle(HttpRequest::from_str("GET /a/./b//../c HTTP/1.1\r\n\r\n").unwrap());
+//! assert_eq!(res.body_str(), Some("/a/c from /a/./b//../c"));
+//!
+//! let res = server.handle(HttpRequest::from_str("GET /../etc/passwd HTTP/1.1\r\n\r\n").unwrap());
+//! assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+//! ```
+//!
+//! What [`normalize`] makes of some nastier paths:
+//!
+//! ```rust
+//! use torus_http::middleware::normalize_path::normalize;
+//!
+//! let cases = [
+//!     ("/", Some("/")),
+//!     ("/a/b", Some("/a/b")),
+//!     ("/a/b/", Some("/a/b/")),
+//!     ("//etc/passwd", Some("/etc/passwd")),
+//!     ("/a/./b", Some("/a/b")),
+//!     ("/a/b/..", Some("/a/")),
+//!     ("/a/%2e%2e/b", Some("/b")),
+//!     ("/a/%2E/b", Some("/a/b")),
+//!     ("/a/..%2fb", Some("/a/..%2fb")),
+//!     ("/...", Some("/...")),
+//!     ("/ééé/..", Some("/")),
+//!     ("/..", None),
+//!     ("/a/../..", None),
+//!     ("/%2e%2e/secret", None),
+//!     ("*", Some("*")),
+//! ];
+//! for (path, expected) in cases {
+//!     assert_eq!(normalize(path).as_deref(), expected, "{path}");
+//! }
+//! ```
+use crate::{
+    middleware::{Middleware, Next},
+    request::HttpRequest,
+    response::HttpResponse,
+    server::ErrorFormat,
+    status::HttpStatus,
+};
+
+/// Path normalization middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// Register it first so every other middleware sees the normalized path
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NormalizePath;
+
+/// The request path before [`NormalizePath`] touched it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalPath(pub String);
+
+impl Middleware for NormalizePath {
+    fn call(&self, mut req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let Some(path) = normalize(&req.path) else {
+            return ErrorFormat::of(&req).response(HttpStatus::BAD_REQUEST, "invalid path");
+        };
+        let original = std::mem::replace(&mut req.path, path);
+        req.extensions.insert(OriginalPath(original));
+        next.run(req)
+    }
+}
+
+/// `path` with duplicate slashes collapsed and dot segments resolved, `None` if it escapes the
+/// root
+///
+/// Paths not starting with `/`, like `*`, are returned unchanged
+#[must_use]
+pub fn normalize(path: &str) -> Option<String> {
+    if !path.starts_with('/') {
+        return Some(path.to_owned());
+    }
+    let mut segments: Vec<&str> = Vec::new();
+    // whether the result ends in a directory, i.e. needs a trailing slash
+    let mut trailing = false;
+    for segment in path.split('/').skip(1) {
+        trailing = true;
+        match dot_segment(segment) {
+            _ if segment.is_empty() => {}
+            Some(DotSegment::Current) => {}
+            Some(DotSegment::Parent) => {
+                segments.pop()?;
+            }
+            None => {
+                segments.push(segment);
+                trailing = false;
+            }
+        }
+    }
+
+    let mut normalized = String::with_capacity(path.len());
+    for segment in &segments {
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+    if trailing || segments.is_empty() {
+        normalized.push('/');
+    }
+    Some(normalized)
+}
+
+enum DotSegment {
+    Current,
+    Parent,
+}
+
+/// Whether `segment` is `.` or `..`, also when the dots are written as `%2e`
+fn dot_segment(segment: &str) -> Option<DotSegment> {
+    let mut dots = 0;
+    let mut rest = segment;
+    while !rest.is_empty() {
+        rest = if let Some(rest) = rest.strip_prefix('.') {
+            rest
+        } else if rest.len() >= 3 && rest.as_bytes()[..3].eq_ignore_ascii_case(b"%2e") {
+            &rest[3..]
+        } else {
+            return None;
+        };
+        dots += 1;
+    }
+    match dots {
+        1 => Some(DotSegment::Current),
+        2 => Some(DotSegment::Parent),
+        _ => None,
+    }
+}
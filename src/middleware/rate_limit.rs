@@ -0,0 +1,186 @@
+//! Fixed window rate limiting
+//!
+//! [`RateLimit`] counts requests per key (the client ip by default) and answers with
+//! `429 Too Many Requests` and a `Retry-After` header once a key used up its budget for the
+//! current window.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{str::FromStr, time::Duration};
+//! use torus_http::{middleware::rate_limit::RateLimit, prelude::*};
+//!
+//! let server = HttpServer::new()
+//!     .add_around_middleware(RateLimit::new(2, Duration::from_secs(60)).key_by_header("X-Api-Key"))
+//!     .get("/", |_| "hi");
+//!
+//! let request = |key: &str| {
+//!     HttpRequest::from_str(&format!("GET / HTTP/1.1\r\nX-Api-Key: {key}\r\n\r\n")).unwrap()
+//! };
+//! assert_eq!(server.handle(request("alice")).status, HttpStatus::OK);
+//! assert_eq!(server.handle(request("alice")).status, HttpStatus::OK);
+//!
+//! let limited = server.handle(request("alice"));
+//! assert_eq!(limited.status, HttpStatus::TOO_MANY_REQUESTS);
+//! assert_eq!(limited.headers["Retry-After"], "60");
+//!
+//! // every key has its own budget
+//! assert_eq!(server.handle(request("bob")).status, HttpStatus::OK);
+//! ```
+//!
+//! Requests the key function returns `None` for aren't limited at all, which also makes it
+//! possible to limit a single route:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use torus_http::middleware::rate_limit::RateLimit;
+//!
+//! let login_limit = RateLimit::new(5, Duration::from_secs(60))
+//!     .key_by(|req| (req.path == "/login").then(|| req.client_ip()).flatten());
+//! ```
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    middleware::{Middleware, Next},
+    request::HttpRequest,
+    response::HttpResponse,
+    server::ErrorFormat,
+    status::HttpStatus,
+};
+
+type KeyFn = Box<dyn Fn(&HttpRequest) -> Option<String> + Send + Sync>;
+
+/// Requests seen for one key in its current window
+struct Window {
+    start: Instant,
+    count: u32,
+}
+
+struct Windows {
+    by_key: HashMap<String, Window>,
+    last_sweep: Instant,
+}
+
+/// Rate limiting middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// The counters are shared by all connection threads
+pub struct RateLimit {
+    requests: u32,
+    per: Duration,
+    key: KeyFn,
+    windows: Mutex<Windows>,
+}
+
+impl RateLimit {
+    /// Allow `requests` requests per client ip every `per`
+    #[must_use]
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self {
+            requests,
+            per,
+            key: Box::new(|req| req.client_ip().as_ref().map(IpAddr::to_string)),
+            windows: Mutex::new(Windows {
+                by_key: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+
+    /// Count requests per whatever `f` returns instead of per client ip
+    #[must_use]
+    pub fn key_by<K: ToString>(
+        mut self,
+        f: impl Fn(&HttpRequest) -> Option<K> + Send + Sync + 'static,
+    ) -> Self {
+        self.key = Box::new(move |req| f(req).map(|key| key.to_string()));
+        self
+    }
+
+    /// Count requests per value of a header, e.g. an api key
+    #[must_use]
+    pub fn key_by_header(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.key_by(move |req| req.header(&name).map(str::to_owned))
+    }
+
+    /// How many keys are currently being tracked
+    ///
+    /// Keys whose window ran out are dropped every so often, so this doesn't grow forever:
+    ///
+    /// ```rust
+    /// use std::{str::FromStr, thread, time::Duration};
+    /// use torus_http::{middleware::{Next, rate_limit::RateLimit}, prelude::*};
+    ///
+    /// let limit = RateLimit::new(1, Duration::from_millis(20)).key_by_header("X-Key");
+    /// let endpoint = |_| HttpResponse::new();
+    /// let request = |key: &str| {
+    ///     HttpRequest::from_str(&format!("GET / HTTP/1.1\r\nX-Key: {key}\r\n\r\n")).unwrap()
+    /// };
+    ///
+    /// for key in ["a", "b", "c"] {
+    ///     _ = limit.call(request(key), Next::new(&[], &endpoint));
+    /// }
+    /// assert_eq!(limit.tracked_keys(), 3);
+    ///
+    /// thread::sleep(Duration::from_millis(50));
+    /// _ = limit.call(request("d"), Next::new(&[], &endpoint));
+    /// assert_eq!(limit.tracked_keys(), 1);
+    /// ```
+    #[must_use]
+    pub fn tracked_keys(&self) -> usize {
+        let windows = self.windows.lock().unwrap_or_else(PoisonError::into_inner);
+        windows.by_key.len()
+    }
+
+    /// Counts a request for `key`, returning how long to wait if it is over budget
+    fn hit(&self, key: String) -> Option<Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap_or_else(PoisonError::into_inner);
+        if now.duration_since(windows.last_sweep) >= self.per {
+            windows
+                .by_key
+                .retain(|_, window| now.duration_since(window.start) < self.per);
+            windows.last_sweep = now;
+        }
+
+        let window = windows.by_key.entry(key).or_insert(Window {
+            start: now,
+            count: 0,
+        });
+        if now.duration_since(window.start) >= self.per {
+            *window = Window {
+                start: now,
+                count: 0,
+            };
+        }
+        if window.count >= self.requests {
+            return Some(self.per.saturating_sub(now.duration_since(window.start)));
+        }
+        window.count += 1;
+        None
+    }
+}
+
+impl Middleware for RateLimit {
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let Some(key) = (self.key)(&req) else {
+            return next.run(req);
+        };
+        match self.hit(key) {
+            None => next.run(req),
+            Some(wait) => {
+                // round up, `Retry-After: 0` would invite an immediate retry
+                let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+                ErrorFormat::of(&req)
+                    .response(HttpStatus::TOO_MANY_REQUESTS, "too many requests")
+                    .insert_header("Retry-After", secs.max(1).to_string())
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Ready made access log
+//!
+//! Unlike the [`Logger`](crate::logger::Logger) hook, which is told about requests once they
+//! have been written out, [`RequestLogger`] is a middleware: it times the rest of the chain and
+//! formats one line per request from a template.
+//!
+//! Supported placeholders are `{method}`, `{path}` (including the query string), `{status}`,
+//! `{latency}`, `{size}` (body length in bytes), `{user_agent}` and `{ip}`.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{str::FromStr, sync::{Arc, Mutex}};
+//! use torus_http::{middleware::request_logger::RequestLogger, prelude::*};
+//!
+//! let lines = Arc::new(Mutex::new(Vec::new()));
+//! let sink = lines.clone();
+//! let server = HttpServer::new()
+//!     .add_around_middleware(
+//!         RequestLogger::with_format("{method} {path} -> {status} {size}b ua={user_agent}")
+//!             .mask_query_param("token")
+//!             .with_sink(move |line| sink.lock().unwrap().push(line.to_owned())),
+//!     )
+//!     .get("/", |_| "hello");
+//!
+//! _ = server.handle(HttpRequest::from_str("GET /?token=hunter2&page=2 HTTP/1.1\r\nUser-Agent: curl\r\n\r\n").unwrap());
+//! _ = server.handle(HttpRequest::from_str("POST /missing HTTP/1.1\r\n\r\n").unwrap());
+//!
+//! let lines = lines.lock().unwrap();
+//! assert_eq!(lines[0], "GET /?page=2&token=*** -> 200 OK 5b ua=curl");
+//! assert!(lines[1].starts_with("POST /missing -> 404"));
+//! assert!(lines[1].ends_with("ua=-"));
+//! ```
+use std::{fmt::Write, time::Instant};
+
+use crate::{
+    middleware::{Middleware, Next},
+    request::HttpRequest,
+    response::HttpResponse,
+};
+
+/// What `RequestLogger::default()` logs
+pub const DEFAULT_FORMAT: &str = "{method} {path} -> {status} ({latency}, {size}b) {user_agent}";
+
+type Sink = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Piece of a parsed format string
+enum Segment {
+    Text(String),
+    Method,
+    Path,
+    Status,
+    Latency,
+    Size,
+    UserAgent,
+    Ip,
+}
+
+/// Middleware logging one line per request, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// Lines go to `stderr` unless another sink is set with [`RequestLogger::with_sink`]
+pub struct RequestLogger {
+    format: Vec<Segment>,
+    masked: Vec<String>,
+    sink: Sink,
+}
+
+impl Default for RequestLogger {
+    fn default() -> Self {
+        Self::with_format(DEFAULT_FORMAT)
+    }
+}
+
+impl RequestLogger {
+    /// Log lines following `format`, placeholders this doesn't know are printed as they are
+    #[must_use]
+    pub fn with_format(format: &str) -> Self {
+        Self {
+            format: parse_format(format),
+            masked: Vec::new(),
+            sink: Box::new(|line| eprintln!("[torus-http] {line}")),
+        }
+    }
+
+    /// Send the lines somewhere other than `stderr`, e.g. the `log` crate
+    #[must_use]
+    pub fn with_sink(mut self, sink: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.sink = Box::new(sink);
+        self
+    }
+
+    /// Replace the value of this query parameter with `***` in logged paths
+    #[must_use]
+    pub fn mask_query_param(mut self, name: impl Into<String>) -> Self {
+        self.masked.push(name.into());
+        self
+    }
+
+    fn format_path(&self, req: &HttpRequest) -> String {
+        let Some(query) = req.query.as_ref().filter(|query| !query.is_empty()) else {
+            return req.path.clone();
+        };
+        let mut params: Vec<_> = query.iter().collect();
+        params.sort();
+        let params: Vec<String> = params
+            .into_iter()
+            .map(|(k, v)| {
+                if self.masked.iter().any(|masked| masked == k) {
+                    format!("{k}=***")
+                } else {
+                    format!("{k}={v}")
+                }
+            })
+            .collect();
+        format!("{}?{}", req.path, params.join("&"))
+    }
+}
+
+impl Middleware for RequestLogger {
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let start = Instant::now();
+        let method = req.method.as_str().to_owned();
+        let path = self.format_path(&req);
+        let user_agent = req.header("User-Agent").unwrap_or("-").to_owned();
+        let ip = req
+            .client_ip()
+            .map_or_else(|| "-".to_owned(), |ip| ip.to_string());
+
+        let res = next.run(req);
+        let latency = start.elapsed();
+
+        let mut line = String::new();
+        for segment in &self.format {
+            // writing into a String can't fail
+            _ = match segment {
+                Segment::Text(text) => line.write_str(text),
+                Segment::Method => line.write_str(&method),
+                Segment::Path => line.write_str(&path),
+                Segment::Status => write!(line, "{}", res.status),
+                Segment::Latency => write!(line, "{latency:?}"),
+                Segment::Size => write!(line, "{}", res.body.as_deref().map_or(0, <[u8]>::len)),
+                Segment::UserAgent => line.write_str(&user_agent),
+                Segment::Ip => line.write_str(&ip),
+            };
+        }
+        (self.sink)(&line);
+        res
+    }
+}
+
+fn parse_format(format: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = format;
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let placeholder = match &rest[open + 1..open + close] {
+            "method" => Segment::Method,
+            "path" => Segment::Path,
+            "status" => Segment::Status,
+            "latency" => Segment::Latency,
+            "size" => Segment::Size,
+            "user_agent" => Segment::UserAgent,
+            "ip" => Segment::Ip,
+            _ => {
+                text.push_str(&rest[open..=open + close]);
+                rest = &rest[open + close + 1..];
+                continue;
+            }
+        };
+        if !text.is_empty() {
+            segments.push(Segment::Text(std::mem::take(&mut text)));
+        }
+        segments.push(placeholder);
+        rest = &rest[open + close + 1..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
@@ -0,0 +1,162 @@
+//! The usual security related response headers in one place
+//!
+//! [`SecurityHeaders`] adds `X-Content-Type-Options`, `X-Frame-Options` and `Referrer-Policy` to
+//! every response, `Strict-Transport-Security` to https ones and a `Content-Security-Policy` if
+//! one was configured. Headers the handler set itself are left alone.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use torus_http::{middleware::security_headers::SecurityHeaders, prelude::*, testing::TestClient};
+//!
+//! let client = TestClient::new(
+//!     HttpServer::new()
+//!         .add_around_middleware(
+//!             SecurityHeaders::new().content_security_policy("default-src 'self'"),
+//!         )
+//!         .get("/", |_| "hi")
+//!         .get("/embed", |_| {
+//!             HttpResponse::new()
+//!                 .set_body("embedded")
+//!                 .insert_header("Content-Security-Policy", "frame-ancestors *")
+//!         }),
+//! );
+//!
+//! let res = client.get("/").send();
+//! assert_eq!(res.header("X-Content-Type-Options"), Some("nosniff"));
+//! assert_eq!(res.header("X-Frame-Options"), Some("DENY"));
+//! assert_eq!(res.header("Referrer-Policy"), Some("strict-origin-when-cross-origin"));
+//! assert_eq!(res.header("Content-Security-Policy"), Some("default-src 'self'"));
+//! // plain http, so no hsts
+//! assert_eq!(res.header("Strict-Transport-Security"), None);
+//!
+//! let res = client.get("/").header("X-Forwarded-Proto", "https").send();
+//! assert_eq!(
+//!     res.header("Strict-Transport-Security"),
+//!     Some("max-age=31536000; includeSubDomains")
+//! );
+//!
+//! // the handler's own policy wins
+//! let res = client.get("/embed").send();
+//! assert_eq!(res.header("Content-Security-Policy"), Some("frame-ancestors *"));
+//! ```
+use crate::{
+    middleware::{Middleware, Next},
+    request::HttpRequest,
+    response::HttpResponse,
+};
+
+const HSTS: &str = "Strict-Transport-Security";
+
+/// Security headers middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// Defaults to `nosniff`, `DENY` for framing, `strict-origin-when-cross-origin` as referrer
+/// policy and a year of hsts including subdomains. There is no default content security policy
+/// since no single one fits every site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityHeaders {
+    headers: Vec<(&'static str, String)>,
+    force_hsts: bool,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self {
+            headers: vec![
+                ("X-Content-Type-Options", "nosniff".into()),
+                ("X-Frame-Options", "DENY".into()),
+                ("Referrer-Policy", "strict-origin-when-cross-origin".into()),
+                (HSTS, "max-age=31536000; includeSubDomains".into()),
+            ],
+            force_hsts: false,
+        }
+    }
+}
+
+impl SecurityHeaders {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        let value = value.into();
+        match self.headers.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, old)) => *old = value,
+            None => self.headers.push((name, value)),
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn content_type_options(self, value: impl Into<String>) -> Self {
+        self.set("X-Content-Type-Options", value)
+    }
+
+    /// e.g. `SAMEORIGIN` to allow framing by your own pages
+    #[must_use]
+    pub fn frame_options(self, value: impl Into<String>) -> Self {
+        self.set("X-Frame-Options", value)
+    }
+
+    #[must_use]
+    pub fn referrer_policy(self, value: impl Into<String>) -> Self {
+        self.set("Referrer-Policy", value)
+    }
+
+    /// The `Strict-Transport-Security` value, only sent over https unless
+    /// [`SecurityHeaders::force_hsts`] is set
+    #[must_use]
+    pub fn hsts(self, value: impl Into<String>) -> Self {
+        self.set(HSTS, value)
+    }
+
+    /// Send hsts on plain http responses as well, for when the proxy in front doesn't tell us
+    /// about https through `X-Forwarded-Proto`
+    #[must_use]
+    pub fn force_hsts(mut self, force: bool) -> Self {
+        self.force_hsts = force;
+        self
+    }
+
+    #[must_use]
+    pub fn content_security_policy(self, policy: impl Into<String>) -> Self {
+        self.set("Content-Security-Policy", policy)
+    }
+
+    /// Don't send the header `name` at all
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::{middleware::security_headers::SecurityHeaders, prelude::*};
+    ///
+    /// let server = HttpServer::new()
+    ///     .add_around_middleware(SecurityHeaders::new().without("x-frame-options"))
+    ///     .get("/", |_| "hi");
+    ///
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert!(!res.headers.contains_key("X-Frame-Options"));
+    /// assert_eq!(res.headers["X-Content-Type-Options"], "nosniff");
+    /// ```
+    #[must_use]
+    pub fn without(mut self, name: &str) -> Self {
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self
+    }
+}
+
+impl Middleware for SecurityHeaders {
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let https = self.force_hsts || req.is_https();
+        let mut res = next.run(req);
+        for (name, value) in &self.headers {
+            let already_set = res.headers.keys().any(|k| k.eq_ignore_ascii_case(name));
+            if already_set || (*name == HSTS && !https) {
+                continue;
+            }
+            res = res.insert_header(*name, value.as_str());
+        }
+        res
+    }
+}
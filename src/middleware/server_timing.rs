@@ -0,0 +1,28 @@
+//! Reports how long the server took for each request as a `total` metric in the
+//! `Server-Timing` header
+//!
+//! See the [`timing`](crate::timing) module for an example
+use std::time::Instant;
+
+use crate::{
+    middleware::{Middleware, Next},
+    request::HttpRequest,
+    response::HttpResponse,
+    timing::ServerTiming,
+};
+
+/// Total timing middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// Register it first so the time of the other middleware is included. Metrics the handler
+/// added itself are kept, `total` comes after them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TotalTiming;
+
+impl Middleware for TotalTiming {
+    fn call(&self, req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let started = Instant::now();
+        let res = next.run(req);
+        res.with_server_timing(ServerTiming::from_elapsed("total", started))
+    }
+}
@@ -0,0 +1,272 @@
+//! Cookie based server side sessions
+//!
+//! [`Sessions`] looks up the session named by the session cookie in a [`SessionStore`] and hands
+//! handlers a [`Session`] through the request extensions. Whatever they change is saved once the
+//! response is on its way, and new sessions get their cookie set.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{str::FromStr, thread, time::Duration};
+//! use torus_http::{
+//!     middleware::session::{MemoryStore, Session, Sessions},
+//!     prelude::*,
+//! };
+//!
+//! let server = HttpServer::new()
+//!     .add_around_middleware(Sessions::new(MemoryStore::new()).ttl(Duration::from_millis(200)))
+//!     .get("/visit", |req: HttpRequest| {
+//!         let session = req.extensions.get::<Session>().unwrap();
+//!         let visits = session.get::<u32>("visits").unwrap_or(0) + 1;
+//!         session.insert("visits", visits);
+//!         format!("visit {visits}")
+//!     });
+//!
+//! let res = server.handle(HttpRequest::from_str("GET /visit HTTP/1.1\r\n\r\n").unwrap());
+//! assert_eq!(res.body_str(), Some("visit 1"));
+//! let set_cookie = &res.headers["Set-Cookie"];
+//! assert!(set_cookie.contains("HttpOnly") && set_cookie.contains("SameSite=Lax"));
+//! let cookie = set_cookie.split(';').next().unwrap();
+//!
+//! let again = format!("GET /visit HTTP/1.1\r\nCookie: theme=dark; {cookie}\r\n\r\n");
+//! let res = server.handle(HttpRequest::from_str(&again).unwrap());
+//! assert_eq!(res.body_str(), Some("visit 2"));
+//!
+//! // once the session expired the count starts over
+//! thread::sleep(Duration::from_millis(300));
+//! let res = server.handle(HttpRequest::from_str(&again).unwrap());
+//! assert_eq!(res.body_str(), Some("visit 1"));
+//! ```
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+pub use crate::cookie::SameSite;
+use crate::{
+    middleware::{Middleware, Next, random_token},
+    request::HttpRequest,
+    response::HttpResponse,
+};
+
+/// The values of one session
+pub type SessionData = HashMap<String, String>;
+
+/// Where sessions live between requests
+///
+/// Implement it to keep sessions in a database, [`MemoryStore`] keeps them in memory
+pub trait SessionStore: Send + Sync {
+    /// The data of a session, `None` if it doesn't exist or expired
+    fn load(&self, id: &str) -> Option<SessionData>;
+
+    /// Create or overwrite a session, it should expire `ttl` from now
+    fn save(&self, id: &str, data: SessionData, ttl: Duration);
+
+    /// Delete a session
+    fn remove(&self, id: &str);
+}
+
+/// Keeps sessions in a `HashMap`, they are gone once the server stops
+///
+/// Expired sessions are dropped when they are looked up and in a sweep every so often
+#[derive(Default)]
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, (Instant, SessionData)>>,
+    last_sweep: Mutex<Option<Instant>>,
+}
+
+/// How often [`MemoryStore`] goes looking for expired sessions
+const SWEEP_INTERVAL: Duration = Duration::from_mins(1);
+
+impl MemoryStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of sessions currently held, including expired ones that weren't purged yet
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> Option<SessionData> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        match sessions.get(id) {
+            Some((expires, data)) if *expires > Instant::now() => Some(data.clone()),
+            Some(_) => {
+                sessions.remove(id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn save(&self, id: &str, data: SessionData, ttl: Duration) {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut last_sweep = self
+            .last_sweep
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if last_sweep.is_none_or(|last| now.duration_since(last) >= SWEEP_INTERVAL) {
+            sessions.retain(|_, (expires, _)| *expires > now);
+            *last_sweep = Some(now);
+        }
+        sessions.insert(id.to_owned(), (now + ttl, data));
+    }
+
+    fn remove(&self, id: &str) {
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+    }
+}
+
+struct SessionState {
+    data: SessionData,
+    changed: bool,
+}
+
+/// Handle to the current session, found in the request extensions
+///
+/// Values go in as strings, so anything implementing `ToString` can be stored and anything
+/// implementing `FromStr` read back
+#[derive(Clone)]
+pub struct Session(Arc<Mutex<SessionState>>);
+
+impl Session {
+    fn state(&self) -> std::sync::MutexGuard<'_, SessionState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The value stored under `key`, `None` if there is none or it doesn't parse as `T`
+    #[must_use]
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.state().data.get(key)?.parse().ok()
+    }
+
+    #[allow(clippy::needless_pass_by_value)] // taking a reference would make `insert("n", 1)` awkward
+    pub fn insert(&self, key: impl Into<String>, value: impl ToString) {
+        let mut state = self.state();
+        state.data.insert(key.into(), value.to_string());
+        state.changed = true;
+    }
+
+    /// Removes the value under `key`, returns whether there was one
+    #[allow(clippy::must_use_candidate)]
+    pub fn remove(&self, key: &str) -> bool {
+        let mut state = self.state();
+        let removed = state.data.remove(key).is_some();
+        state.changed |= removed;
+        removed
+    }
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Session").field(&self.state().data).finish()
+    }
+}
+
+/// Session middleware, register it with
+/// [`HttpServer::add_around_middleware`](crate::server::HttpServer::add_around_middleware)
+///
+/// Defaults to a cookie called `torus_session` that lives for a day with `SameSite=Lax`
+pub struct Sessions {
+    store: Box<dyn SessionStore>,
+    cookie_name: String,
+    ttl: Duration,
+    same_site: SameSite,
+    secure: bool,
+}
+
+impl Sessions {
+    #[must_use]
+    pub fn new(store: impl SessionStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+            cookie_name: "torus_session".into(),
+            ttl: Duration::from_hours(24),
+            same_site: SameSite::Lax,
+            secure: false,
+        }
+    }
+
+    #[must_use]
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// How long a session lives after it was last changed
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    #[must_use]
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Only send the cookie over https, `SameSite=None` needs this
+    #[must_use]
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    fn cookie(&self, id: &str) -> String {
+        let mut cookie = format!(
+            "{}={id}; Path=/; Max-Age={}; HttpOnly; SameSite={}",
+            self.cookie_name,
+            self.ttl.as_secs(),
+            self.same_site.as_str()
+        );
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+}
+
+impl Middleware for Sessions {
+    fn call(&self, mut req: HttpRequest, next: Next<'_>) -> HttpResponse {
+        let existing = req.cookie(&self.cookie_name).and_then(|id| {
+            let data = self.store.load(id)?;
+            Some((id.to_owned(), data))
+        });
+        let (id, data) = existing.unwrap_or_else(|| (random_token(), SessionData::new()));
+
+        let session = Session(Arc::new(Mutex::new(SessionState {
+            data,
+            changed: false,
+        })));
+        req.extensions.insert(session.clone());
+        let res = next.run(req);
+
+        let state = session.state();
+        if !state.changed {
+            return res;
+        }
+        self.store.save(&id, state.data.clone(), self.ttl);
+        // (re)send the cookie so it expires together with the stored session
+        res.insert_header("Set-Cookie", self.cookie(&id))
+    }
+}
@@ -0,0 +1,92 @@
+//! Upper bounds for how long a handler may take
+//!
+//! Handlers are plain synchronous functions that can't be interrupted, so a timed handler runs on
+//! a helper thread while the connection waits for it with a deadline. Once the deadline passed
+//! the client gets a `504 Gateway Timeout`, the helper thread keeps running until the handler
+//! returns and its response is then thrown away.
+//!
+//! Wrap single handlers with [`Timeout::wrap`], or all of them with
+//! [`HttpServer::with_handler_timeout`](crate::server::HttpServer::with_handler_timeout).
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{str::FromStr, thread, time::{Duration, Instant}};
+//! use torus_http::{middleware::timeout::Timeout, prelude::*};
+//!
+//! let server = HttpServer::new()
+//!     .get("/slow", Timeout::new(Duration::from_millis(50)).wrap(|_| {
+//!         thread::sleep(Duration::from_secs(2));
+//!         "finally"
+//!     }))
+//!     .get("/fast", Timeout::new(Duration::from_millis(50)).wrap(|_| "quick"));
+//!
+//! let start = Instant::now();
+//! let res = server.handle(HttpRequest::from_str("GET /slow HTTP/1.1\r\n\r\n").unwrap());
+//! assert_eq!(res.status, HttpStatus::GATEWAY_TIMEOUT);
+//! assert!(start.elapsed() < Duration::from_secs(1));
+//!
+//! let res = server.handle(HttpRequest::from_str("GET /fast HTTP/1.1\r\n\r\n").unwrap());
+//! assert_eq!(res.body_str(), Some("quick"));
+//! ```
+use std::{
+    sync::{Arc, mpsc},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    request::HttpRequest,
+    response::HttpResponse,
+    server::{ErrorFormat, Handler, HandlerFn},
+    status::HttpStatus,
+};
+
+/// Puts a deadline on handlers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout {
+    limit: Duration,
+}
+
+impl Timeout {
+    #[must_use]
+    pub fn new(limit: Duration) -> Self {
+        Self { limit }
+    }
+
+    /// A handler answering with `504 Gateway Timeout` if `handler` takes longer than the limit
+    pub fn wrap(
+        self,
+        handler: impl HandlerFn + 'static,
+    ) -> impl Fn(HttpRequest) -> HttpResponse + Send + Sync + 'static {
+        let handler: Handler = Arc::new(handler);
+        move |req| {
+            let format = ErrorFormat::of(&req);
+            run_with_deadline(&handler, req, self.limit)
+                .unwrap_or_else(|(status, message)| format.response(status, message))
+        }
+    }
+}
+
+/// Runs `handler` on its own thread and waits at most `limit` for its response, failing with
+/// the status and message to answer with instead
+pub(crate) fn run_with_deadline(
+    handler: &Handler,
+    req: HttpRequest,
+    limit: Duration,
+) -> Result<HttpResponse, (HttpStatus, &'static str)> {
+    let (tx, rx) = mpsc::sync_channel(1);
+    let handler = handler.clone();
+    thread::spawn(move || {
+        let res = handler.call(req);
+        // nobody is listening anymore if we took too long, which is fine
+        _ = tx.send(res);
+    });
+    rx.recv_timeout(limit).map_err(|err| match err {
+        mpsc::RecvTimeoutError::Timeout => (HttpStatus::GATEWAY_TIMEOUT, "handler timed out"),
+        // the handler panicked
+        mpsc::RecvTimeoutError::Disconnected => {
+            (HttpStatus::INTERNAL_SERVER_ERROR, "internal server error")
+        }
+    })
+}
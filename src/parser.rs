@@ -0,0 +1,183 @@
+//! Low level helpers for decoding http/1.1 wire formats
+//!
+//! # Example:
+//!
+//! ```rust
+//! use torus_http::parser::dechunk;
+//!
+//! let body = b"4\r\nWiki\r\n7;note=ext\r\npedia i\r\nB\r\nn \r\nchunks.\r\n0\r\nExpires: never\r\n\r\n";
+//! assert_eq!(dechunk(body).unwrap(), b"Wikipedia in \r\nchunks.");
+//! ```
+
+/// Decodes a complete `Transfer-Encoding: chunked` body (RFC 7230 §4.1)
+///
+/// Chunk extensions and trailer fields are skipped
+///
+/// # Errors
+///
+/// - the body ends before the terminating `0` chunk and empty line
+/// - a chunk size isn't valid hex
+/// - a chunk's data isn't followed by `\r\n`
+///
+/// ```rust
+/// use torus_http::parser::{dechunk, ChunkParseError};
+///
+/// assert_eq!(dechunk(b"3\r\nabc\r\n0\r\n\r\n"), Ok(b"abc".to_vec()));
+/// assert_eq!(dechunk(b"3\r\nabc\r\n"), Err(ChunkParseError::Incomplete));
+/// assert_eq!(dechunk(b"zz\r\nabc\r\n0\r\n\r\n"), Err(ChunkParseError::InvalidSize));
+/// assert_eq!(dechunk(b"2\r\nabc\r\n0\r\n\r\n"), Err(ChunkParseError::MissingCrlf));
+///
+/// // sizes that would run past the end of memory
+/// let width = format!("{:x}", usize::MAX).len();
+/// for size in [usize::MAX, usize::MAX - width - 2] {
+///     let body = format!("{size:x}\r\nabc\r\n0\r\n\r\n");
+///     assert_eq!(dechunk(body.as_bytes()), Err(ChunkParseError::InvalidSize));
+/// }
+/// ```
+pub fn dechunk(data: &[u8]) -> Result<Vec<u8>, ChunkParseError> {
+    let mut out = Vec::with_capacity(data.len());
+    match walk_chunks(data, Some(&mut out))? {
+        Some(_) => Ok(out),
+        None => Err(ChunkParseError::Incomplete),
+    }
+}
+
+/// Whether a `Transfer-Encoding` value makes the body chunked, chunked has to be the last coding
+/// applied, e.g. `gzip, chunked`
+pub(crate) fn ends_in_chunked(value: &str) -> bool {
+    value
+        .rsplit(',')
+        .next()
+        .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+}
+
+/// Length of the chunked body at the start of `data`, `None` if more bytes are needed
+pub(crate) fn chunked_len(data: &[u8]) -> Result<Option<usize>, ChunkParseError> {
+    walk_chunks(data, None)
+}
+
+/// Walks the chunks in `data`, copying their contents into `out` if given, and returns how many
+/// bytes the encoded body took up
+fn walk_chunks(
+    data: &[u8],
+    mut out: Option<&mut Vec<u8>>,
+) -> Result<Option<usize>, ChunkParseError> {
+    let mut pos = 0;
+    loop {
+        let Some(line) = line_at(data, pos) else {
+            return Ok(None);
+        };
+        let size = line.split(|&b| b == b';').next().unwrap_or(line);
+        let size = std::str::from_utf8(size)
+            .ok()
+            .and_then(|size| usize::from_str_radix(size.trim(), 16).ok())
+            .ok_or(ChunkParseError::InvalidSize)?;
+        pos += line.len() + 2;
+
+        if size == 0 {
+            // trailer fields until the final empty line
+            loop {
+                let Some(line) = line_at(data, pos) else {
+                    return Ok(None);
+                };
+                pos += line.len() + 2;
+                if line.is_empty() {
+                    return Ok(Some(pos));
+                }
+            }
+        }
+
+        let end = pos.checked_add(size).ok_or(ChunkParseError::InvalidSize)?;
+        let next = end.checked_add(2).ok_or(ChunkParseError::InvalidSize)?;
+        let Some(crlf) = data.get(end..next) else {
+            return Ok(None);
+        };
+        if crlf != b"\r\n" {
+            return Err(ChunkParseError::MissingCrlf);
+        }
+        if let Some(out) = out.as_deref_mut() {
+            out.extend_from_slice(&data[pos..end]);
+        }
+        pos = next;
+    }
+}
+
+/// The `\r\n` terminated line starting at `pos`, without the terminator
+fn line_at(data: &[u8], pos: usize) -> Option<&[u8]> {
+    let rest = data.get(pos..)?;
+    rest.windows(2)
+        .position(|w| w == b"\r\n")
+        .map(|len| &rest[..len])
+}
+
+/// Decodes standard (padded or unpadded) base64, `None` on invalid input
+pub(crate) fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim_end_matches('=').as_bytes();
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &b in input {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits).to_le_bytes()[0]);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    // a single leftover character can't encode a whole byte
+    (bits < 6).then_some(out)
+}
+
+/// Encodes `bytes` as standard padded base64
+#[cfg(feature = "serde")]
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                out.push(char::from(ALPHABET[index as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Reasons a chunked body can be rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkParseError {
+    /// The body ended before the last chunk
+    Incomplete,
+    /// A chunk size line isn't a hex number
+    InvalidSize,
+    /// Chunk data wasn't followed by `\r\n`
+    MissingCrlf,
+}
+
+impl std::fmt::Display for ChunkParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkParseError::Incomplete => write!(f, "chunked body ended early"),
+            ChunkParseError::InvalidSize => write!(f, "invalid chunk size"),
+            ChunkParseError::MissingCrlf => write!(f, "chunk data not followed by CRLF"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkParseError {}
@@ -0,0 +1,184 @@
+//! Route paths with placeholders, compiled once when the route is registered
+//!
+//! A path is split at `/` into segments, each of which is one of
+//!
+//! - a literal like `users`, matching exactly that
+//! - `:name`, matching any single segment and capturing it as the parameter `name`
+//! - `*`, matching any single segment without capturing it
+//! - `**`, only allowed last, matching the rest of the path including nothing at all
+//!
+//! Routes without placeholders are looked up directly, the others are tried in registration
+//! order. Captured parameters end up in [`HttpRequest::param`](crate::request::HttpRequest::param).
+//!
+//! # Example:
+//!
+//! ```rust
+//! use torus_http::{prelude::*, testing::TestClient};
+//!
+//! let client = TestClient::new(
+//!     HttpServer::new()
+//!         .get("/users/:id/posts", |req: HttpRequest| {
+//!             format!("posts of user {}", req.param("id").unwrap())
+//!         })
+//!         .get("/users/me/posts", |_| "my posts")
+//!         .get("/static/**", |req: HttpRequest| format!("file {}", req.path)),
+//! );
+//!
+//! assert_eq!(client.get("/users/42/posts").send().body_str(), Some("posts of user 42"));
+//! // literal routes win over patterns
+//! assert_eq!(client.get("/users/me/posts").send().body_str(), Some("my posts"));
+//! assert_eq!(client.get("/static/css/site.css").send().body_str(), Some("file /static/css/site.css"));
+//! assert_eq!(client.get("/users/42").send().status, HttpStatus::NOT_FOUND);
+//! ```
+//!
+//! Patterns can be compiled and matched on their own as well:
+//!
+//! ```rust
+//! use torus_http::pattern::{CompiledPattern, Segment};
+//!
+//! let pattern: CompiledPattern = "/users/:id/posts".parse().unwrap();
+//! assert_eq!(
+//!     pattern.segments(),
+//!     [
+//!         Segment::Literal("users".into()),
+//!         Segment::Named("id".into()),
+//!         Segment::Literal("posts".into()),
+//!     ]
+//! );
+//! let params = pattern.matches("/users/42/posts").unwrap();
+//! assert_eq!(params["id"], "42");
+//! assert_eq!(pattern.matches("/users/42/comments"), None);
+//! assert_eq!(pattern.matches("/users/42/posts/1"), None);
+//!
+//! assert!("/files/**/edit".parse::<CompiledPattern>().is_err());
+//! assert!("/users/:".parse::<CompiledPattern>().is_err());
+//! ```
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+/// One `/` separated piece of a [`CompiledPattern`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment {
+    Literal(String),
+    /// `:name`
+    Named(String),
+    /// `*`
+    Wildcard,
+    /// `**`
+    DoubleStar,
+}
+
+/// A route path split into [`Segment`]s, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CompiledPattern {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl CompiledPattern {
+    /// The pattern as it was written
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    #[must_use]
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Whether the pattern has no placeholders, i.e. only matches the path it was written as
+    #[must_use]
+    pub fn is_literal(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|segment| matches!(segment, Segment::Literal(_)))
+    }
+
+    /// The captured parameters if `path` matches, `None` otherwise
+    #[must_use]
+    pub fn matches(&self, path: &str) -> Option<HashMap<String, String>> {
+        let mut params = HashMap::new();
+        let mut parts = path.strip_prefix('/')?.split('/');
+        for segment in &self.segments {
+            if *segment == Segment::DoubleStar {
+                return Some(params);
+            }
+            let part = parts.next()?;
+            match segment {
+                Segment::Literal(literal) if literal == part => {}
+                Segment::Named(name) if !part.is_empty() => {
+                    params.insert(name.clone(), part.to_owned());
+                }
+                Segment::Wildcard if !part.is_empty() => {}
+                _ => return None,
+            }
+        }
+        parts.next().is_none().then_some(params)
+    }
+}
+
+impl FromStr for CompiledPattern {
+    type Err = InvalidPattern;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason| InvalidPattern {
+            pattern: source.to_owned(),
+            reason,
+        };
+        let Some(rest) = source.strip_prefix('/') else {
+            // `*` for `OPTIONS *` and the like
+            return Ok(Self {
+                source: source.to_owned(),
+                segments: vec![Segment::Literal(source.to_owned())],
+            });
+        };
+        let parts: Vec<&str> = rest.split('/').collect();
+        let mut segments = Vec::with_capacity(parts.len());
+        for (i, part) in parts.iter().enumerate() {
+            let segment = match *part {
+                "*" => Segment::Wildcard,
+                "**" if i + 1 == parts.len() => Segment::DoubleStar,
+                "**" => return Err(invalid("`**` has to be the last segment")),
+                ":" => return Err(invalid("parameter without a name")),
+                part => match part.strip_prefix(':') {
+                    Some(name) => Segment::Named(name.to_owned()),
+                    None => Segment::Literal(part.to_owned()),
+                },
+            };
+            segments.push(segment);
+        }
+        Ok(Self {
+            source: source.to_owned(),
+            segments,
+        })
+    }
+}
+
+impl Display for CompiledPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// The parameters captured from the path of the matched route, found in the request extensions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathParams(pub HashMap<String, String>);
+
+/// A route path that doesn't compile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidPattern {
+    pub pattern: String,
+    pub reason: &'static str,
+}
+
+impl Display for InvalidPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid route pattern {:?}: {}",
+            self.pattern, self.reason
+        )
+    }
+}
+
+impl std::error::Error for InvalidPattern {}
@@ -1,6 +1,9 @@
 //! Re-export of the common things required for making a rudimentary http server
+pub use crate::error::HttpError;
+pub use crate::logger::{Logger, StderrLogger};
 pub use crate::method::HttpMethod;
+pub use crate::middleware::{Middleware, MiddlewareResult, Next};
 pub use crate::request::HttpRequest;
 pub use crate::response::*;
-pub use crate::server::{HttpServer, ServerError};
+pub use crate::server::{BoundServer, HttpServer, ServerError};
 pub use crate::status::HttpStatus;
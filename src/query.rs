@@ -0,0 +1,95 @@
+//! Typed access to query parameters
+//!
+//! Implement [`QueryParams`] by hand or derive it with the `derive` feature:
+//!
+//! ```rust
+//! use std::{collections::HashMap, str::FromStr};
+//! use torus_http::{prelude::*, query::{QueryExtractError, QueryParams}};
+//!
+//! struct Page {
+//!     number: u32,
+//!     sort_by: Option<String>,
+//! }
+//!
+//! impl QueryParams for Page {
+//!     fn extract(params: &HashMap<String, String>) -> Result<Self, QueryExtractError> {
+//!         Ok(Self {
+//!             number: torus_http::query::required(params, "page")?,
+//!             sort_by: torus_http::query::optional(params, "sort")?,
+//!         })
+//!     }
+//! }
+//!
+//! let req = HttpRequest::from_str("GET /list?page=3 HTTP/1.1\r\n\r\n").unwrap();
+//! let page: Page = req.query_params().unwrap();
+//! assert_eq!(page.number, 3);
+//! assert_eq!(page.sort_by, None);
+//! ```
+use std::{collections::HashMap, fmt::Display, hash::BuildHasher, str::FromStr};
+
+#[cfg(feature = "derive")]
+pub use torus_http_macros::QueryParams;
+
+/// Types that can be built from a request's query parameters
+pub trait QueryParams: Sized {
+    /// # Errors
+    ///
+    /// - a required parameter is missing
+    /// - a parameter doesn't parse into its field's type
+    fn extract(params: &HashMap<String, String>) -> Result<Self, QueryExtractError>;
+}
+
+/// Why [`QueryParams::extract`] failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryExtractError {
+    /// A required parameter wasn't there
+    Missing { name: String },
+    /// A parameter was there but didn't parse
+    Invalid { name: String, value: String },
+}
+
+impl Display for QueryExtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryExtractError::Missing { name } => write!(f, "missing query parameter {name:?}"),
+            QueryExtractError::Invalid { name, value } => {
+                write!(f, "invalid value {value:?} for query parameter {name:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryExtractError {}
+
+/// Parses the parameter `name`, which has to be there
+///
+/// # Errors
+///
+/// - the parameter is missing
+/// - it doesn't parse as `T`
+pub fn required<T: FromStr, S: BuildHasher>(
+    params: &HashMap<String, String, S>,
+    name: &str,
+) -> Result<T, QueryExtractError> {
+    optional(params, name)?.ok_or_else(|| QueryExtractError::Missing { name: name.into() })
+}
+
+/// Parses the parameter `name` if it is there
+///
+/// # Errors
+///
+/// - the parameter doesn't parse as `T`
+pub fn optional<T: FromStr, S: BuildHasher>(
+    params: &HashMap<String, String, S>,
+    name: &str,
+) -> Result<Option<T>, QueryExtractError> {
+    params
+        .get(name)
+        .map(|value| {
+            value.parse().map_err(|_| QueryExtractError::Invalid {
+                name: name.into(),
+                value: value.clone(),
+            })
+        })
+        .transpose()
+}
@@ -6,95 +6,880 @@
 //! ```rust
 //! use torus_http::response::{HttpResponse, Response};
 //! let response = HttpResponse::new().set_body("hey there").insert_header("Cool-Header", "so cool");
-//! let response = "hello".to_response();
+//! let response = "hello".into_response();
 //! ```
 
-use std::collections::HashMap;
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::HashMap,
+    hash::BuildHasher,
+    io::{self, Write},
+    sync::Arc,
+};
 
-use crate::status::{HttpStatus, ServerErrorResponse};
+use crate::{
+    cookie::Cookie,
+    error::HttpError,
+    parser::{self, ChunkParseError},
+    request,
+    status::{ClientErrorResponse, CustomStatus, HttpStatus, ServerErrorResponse},
+    timing::ServerTiming,
+    upgrade::{Upgrade, Upgraded},
+};
+
+#[cfg(feature = "derive")]
+pub use torus_http_macros::Response;
 
 /// Trait that allows things to be sent back from the server
+///
+/// Responses are consumed, so owned bodies move into the [`HttpResponse`] without a copy.
+/// Before 0.3 this was `fn to_response(&self)`, implementations only need the rename and to
+/// take `self`:
+///
+/// ```rust
+/// use torus_http::{prelude::*, response::Response};
+///
+/// struct Report(Vec<u8>);
+///
+/// impl Response for Report {
+///     fn into_response(self) -> HttpResponse {
+///         HttpResponse::new().set_body(self.0).insert_header("Content-Type", "text/csv")
+///     }
+/// }
+///
+/// assert_eq!(Report(Vec::new()).status_code(), HttpStatus::OK);
+/// let res = Report(b"a,b\n1,2\n".to_vec()).into_response();
+/// assert_eq!(res.body_str(), Some("a,b\n1,2\n"));
+/// ```
 pub trait Response {
-    fn to_response(&self) -> HttpResponse;
+    fn into_response(self) -> HttpResponse;
+
+    /// Callers that only have a reference can still get at the response by cloning
+    #[deprecated(
+        since = "0.3.0",
+        note = "responses are consumed now, use `into_response`"
+    )]
+    fn to_response(&self) -> HttpResponse
+    where
+        Self: Clone,
+    {
+        self.clone().into_response()
+    }
+
+    /// The status this would be sent with, without building the response
+    ///
+    /// Defaults to `200 OK`, types answering with anything else have to override it
+    ///
+    /// ```rust
+    /// use torus_http::{prelude::*, response::Response};
+    /// assert_eq!("hello".status_code(), HttpStatus::OK);
+    /// assert_eq!(
+    ///     HttpResponse::new().set_status(HttpStatus::NOT_FOUND).status_code(),
+    ///     HttpStatus::NOT_FOUND
+    /// );
+    ///
+    /// let boxed: Box<dyn Response> = Box::new(None::<&str>);
+    /// assert_eq!(boxed.status_code(), HttpStatus::INTERNAL_SERVER_ERROR);
+    /// ```
+    fn status_code(&self) -> HttpStatus {
+        HttpStatus::OK
+    }
 }
 
 impl Response for &str {
-    fn to_response(&self) -> HttpResponse {
-        HttpResponse::new_body((*self).to_string(), HttpStatus::default())
+    fn into_response(self) -> HttpResponse {
+        HttpResponse::new_body(self.to_owned(), HttpStatus::default())
+    }
+
+    fn status_code(&self) -> HttpStatus {
+        HttpStatus::default()
     }
 }
 
 impl Response for String {
-    fn to_response(&self) -> HttpResponse {
-        HttpResponse::new_body(self.clone(), HttpStatus::default())
+    fn into_response(self) -> HttpResponse {
+        HttpResponse::new_body(self, HttpStatus::default())
+    }
+
+    fn status_code(&self) -> HttpStatus {
+        HttpStatus::default()
     }
 }
 
 impl<S: Response> Response for Option<S> {
-    fn to_response(&self) -> HttpResponse {
+    fn into_response(self) -> HttpResponse {
         match self {
-            Some(e) => e.to_response(),
+            Some(e) => e.into_response(),
             None => HttpResponse::new().set_status(HttpStatus::ServerError(
                 ServerErrorResponse::InternalServerError,
             )),
         }
     }
+
+    fn status_code(&self) -> HttpStatus {
+        match self {
+            Some(e) => e.status_code(),
+            None => HttpStatus::INTERNAL_SERVER_ERROR,
+        }
+    }
 }
 
+/// Errors are sent as `500 Internal Server Error` unless they come with an error status of their
+/// own, like an [`IoErrorResponse`] for a missing file
 impl<S: Response, E: Response> Response for Result<S, E> {
-    fn to_response(&self) -> HttpResponse {
+    fn into_response(self) -> HttpResponse {
+        match self {
+            Ok(s) => s.into_response().set_status(HttpStatus::default()),
+            Err(e) => {
+                let response = e.into_response();
+                if response.status.code() >= 400 {
+                    response
+                } else {
+                    response.set_status(ServerErrorResponse::InternalServerError.into())
+                }
+            }
+        }
+    }
+
+    fn status_code(&self) -> HttpStatus {
         match self {
-            Ok(s) => s.to_response().set_status(HttpStatus::default()),
-            Err(e) => e
-                .to_response()
-                .set_status(ServerErrorResponse::InternalServerError.into()),
+            Ok(_) => HttpStatus::default(),
+            Err(e) => Some(e.status_code())
+                .filter(|status| status.code() >= 400)
+                .unwrap_or(HttpStatus::INTERNAL_SERVER_ERROR),
+        }
+    }
+}
+
+/// An [`io::Error`] as a response, with a status fitting its kind and its message as the body
+///
+/// Meant as the error type of handlers doing io, `?` converts into it:
+///
+/// ```rust
+/// use std::{fs, io};
+/// use torus_http::{prelude::*, response::{IntoResponse, IoErrorResponse, Response}, testing::TestClient};
+///
+/// fn read_note(_req: HttpRequest) -> Result<String, IoErrorResponse> {
+///     Ok(fs::read_to_string("/definitely/not/here.txt")?)
+/// }
+///
+/// let client = TestClient::new(HttpServer::new().get("/note", read_note));
+/// let res = client.get("/note").send();
+/// assert_eq!(res.status, HttpStatus::NOT_FOUND);
+/// assert!(res.body_str().unwrap().contains("No such file"));
+///
+/// let denied = IntoResponse::into_response(io::Error::new(io::ErrorKind::PermissionDenied, "not yours"));
+/// assert_eq!(denied.status(), HttpStatus::FORBIDDEN);
+/// assert_eq!(Response::into_response(denied).body_str(), Some("not yours"));
+/// ```
+#[derive(Debug)]
+pub struct IoErrorResponse(pub io::Error);
+
+impl IoErrorResponse {
+    /// `404` for missing files, `403` for missing permissions, `400` for bad input, `409` if
+    /// something already exists, `504` for timeouts and `500` for everything else
+    #[must_use]
+    pub fn status(&self) -> HttpStatus {
+        match self.0.kind() {
+            io::ErrorKind::NotFound => HttpStatus::NOT_FOUND,
+            io::ErrorKind::PermissionDenied => HttpStatus::FORBIDDEN,
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => HttpStatus::BAD_REQUEST,
+            io::ErrorKind::AlreadyExists => ClientErrorResponse::Conflict.into(),
+            io::ErrorKind::TimedOut => HttpStatus::GATEWAY_TIMEOUT,
+            _ => HttpStatus::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl Response for IoErrorResponse {
+    fn into_response(self) -> HttpResponse {
+        HttpResponse::new_body(self.0.to_string(), self.status())
+            .insert_header("Content-Type", "text/plain; charset=utf-8")
+    }
+
+    fn status_code(&self) -> HttpStatus {
+        self.status()
+    }
+}
+
+impl From<io::Error> for IoErrorResponse {
+    fn from(err: io::Error) -> Self {
+        Self(err)
+    }
+}
+
+impl std::fmt::Display for IoErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for IoErrorResponse {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Turns an [`io::Error`] into an [`IoErrorResponse`], for `map_err(IntoResponse::into_response)`
+/// and the like
+pub trait IntoResponse {
+    fn into_response(self) -> IoErrorResponse;
+}
+
+impl IntoResponse for io::Error {
+    fn into_response(self) -> IoErrorResponse {
+        IoErrorResponse(self)
+    }
+}
+
+/// Types that render to html, e.g. templates of an engine like askama or minijinja
+///
+/// Everything implementing it is a [`Response`] sent as `text/html; charset=utf-8`. A failed
+/// render takes the same way as the error of a [`fallible`](crate::server::fallible) handler,
+/// so it can be mapped with [`HttpServer::map_error`](crate::server::HttpServer::map_error) and
+/// otherwise becomes a `500 Internal Server Error`
+///
+/// # Example:
+///
+/// ```rust
+/// use torus_http::{prelude::*, testing::TestClient};
+///
+/// struct Profile {
+///     name: String,
+/// }
+///
+/// impl Render for Profile {
+///     fn render(&self) -> Result<String, RenderError> {
+///         if self.name.contains('<') {
+///             return Err(RenderError::new("names can't contain markup"));
+///         }
+///         Ok(format!("<h1>{}</h1>", self.name))
+///     }
+/// }
+///
+/// let client = TestClient::new(
+///     HttpServer::new().get("/users/:name", |req: HttpRequest| Profile {
+///         name: req.param("name").unwrap().to_owned(),
+///     }),
+/// );
+///
+/// let res = client.get("/users/ferris").send();
+/// assert_eq!(res.body_str(), Some("<h1>ferris</h1>"));
+/// assert_eq!(res.header("Content-Type"), Some("text/html; charset=utf-8"));
+///
+/// let res = client.get("/users/<script>").send();
+/// assert_eq!(res.status, HttpStatus::INTERNAL_SERVER_ERROR);
+/// assert_eq!(res.body_str(), Some("internal server error"));
+///
+/// // or show what went wrong
+/// let client = TestClient::new(
+///     HttpServer::new()
+///         .map_error(|err: &RenderError| {
+///             HttpResponse::new_body(err.to_string(), HttpStatus::BAD_REQUEST)
+///         })
+///         .get("/users/:name", |req: HttpRequest| Profile {
+///             name: req.param("name").unwrap().to_owned(),
+///         }),
+/// );
+/// let res = client.get("/users/<script>").send();
+/// assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+/// assert_eq!(res.body_str(), Some("names can't contain markup"));
+/// ```
+pub trait Render {
+    /// # Errors
+    ///
+    /// - whatever keeps the template from rendering
+    fn render(&self) -> Result<String, RenderError>;
+}
+
+impl<T: Render> Response for T {
+    fn into_response(self) -> HttpResponse {
+        match self.render() {
+            Ok(html) => HttpResponse::new()
+                .set_body(html)
+                .insert_header("Content-Type", "text/html; charset=utf-8"),
+            Err(err) => {
+                let mut res = HttpResponse::new().set_status(HttpStatus::INTERNAL_SERVER_ERROR);
+                res.failure = Some(Failure(Arc::new(err)));
+                res
+            }
+        }
+    }
+
+    /// `200 OK` without rendering, a template that fails to render is only noticed by
+    /// [`Response::into_response`]
+    fn status_code(&self) -> HttpStatus {
+        HttpStatus::default()
+    }
+}
+
+/// Why a [`Render`] implementation failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderError {
+    pub message: String,
+}
+
+impl RenderError {
+    #[must_use]
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
         }
     }
 }
 
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// Sends the `1xx` responses, usually [`HttpResponse::early_hints`], before the final one
+///
+/// ```rust
+/// use torus_http::{prelude::*, testing::TestClient};
+///
+/// let client = TestClient::new(HttpServer::new().get("/", |_| {
+///     let hints = HttpResponse::early_hints(&["</style.css>; rel=preload; as=style"]);
+///     (vec![hints], "<link rel=stylesheet href=/style.css>")
+/// }));
+///
+/// let res = client.get("/").send();
+/// assert_eq!(res.status, HttpStatus::OK);
+/// assert_eq!(res.interim()[0].status, HttpStatus::EARLY_HINTS);
+/// ```
+impl<R: Response> Response for (Vec<HttpResponse>, R) {
+    fn into_response(self) -> HttpResponse {
+        let (interim, response) = self;
+        interim
+            .into_iter()
+            .fold(response.into_response(), HttpResponse::with_interim)
+    }
+
+    fn status_code(&self) -> HttpStatus {
+        self.1.status_code()
+    }
+}
+
 impl Response for HttpResponse {
-    // TODO: make this not need to clone, can't just take ownership since dyn
-    fn to_response(&self) -> HttpResponse {
-        self.clone()
+    fn into_response(self) -> HttpResponse {
+        self
+    }
+
+    fn status_code(&self) -> HttpStatus {
+        self.status.clone()
     }
 }
 
 /// Struct that contains all the information that will be sent to the client
-#[derive(Eq, PartialEq, Clone, Debug, Default)]
+#[derive(Eq, PartialEq, Clone, Debug)]
 pub struct HttpResponse {
     pub headers: HashMap<String, String>,
     pub status: HttpStatus,
-    pub body: Option<String>,
+    /// Raw body bytes, see [`HttpResponse::body_str`] for text bodies. Borrowed when set with
+    /// [`HttpResponse::set_static_body`]
+    pub body: Option<Cow<'static, [u8]>>,
+    /// Takes over the connection once this response has been sent, see [`HttpResponse::on_upgrade`]
+    pub(crate) upgrade: Option<Upgrade>,
+    /// The error this response was made from, see [`HttpResponse::error`]
+    pub(crate) error: Option<Arc<HttpError>>,
+    /// The error of a [`fallible`](crate::server::fallible) handler, waiting to be turned into
+    /// a response by the server
+    pub(crate) failure: Option<Failure>,
+    /// `Set-Cookie` headers on top of the one in `headers`, see [`HttpResponse::add_cookie`]
+    pub(crate) cookies: Vec<String>,
+    /// `1xx` responses sent ahead of this one, see [`HttpResponse::with_interim`]
+    pub(crate) interim: Vec<HttpResponse>,
+    /// `Link` headers on top of the one in `headers`, see [`HttpResponse::early_hints`]
+    pub(crate) links: Vec<String>,
+}
+
+/// Any error a handler failed with
+#[derive(Clone)]
+pub(crate) struct Failure(pub(crate) Arc<dyn Any + Send + Sync>);
+
+impl PartialEq for Failure {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Failure {}
+
+impl std::fmt::Debug for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Failure(..)")
+    }
+}
+
+/// `(status, headers, body)`, see [`HttpResponse::with_headers`]
+impl From<(HttpStatus, Vec<(String, String)>, Option<String>)> for HttpResponse {
+    fn from((status, headers, body): (HttpStatus, Vec<(String, String)>, Option<String>)) -> Self {
+        Self::with_headers(status, body, headers)
+    }
+}
+
+/// `(status, body, headers)` with the headers already in a map, as a proxy would have them
+///
+/// `Content-Length` is derived from the body unless the map brings its own, like
+/// [`HttpResponse::with_headers`]
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use torus_http::prelude::*;
+///
+/// let headers = HashMap::from([
+///     ("Content-Type".to_owned(), "application/json".to_owned()),
+///     ("X-Upstream".to_owned(), "billing".to_owned()),
+/// ]);
+/// let response = HttpResponse::from((HttpStatus::NOT_FOUND, "{}".to_owned(), headers));
+/// assert_eq!(response.status, HttpStatus::NOT_FOUND);
+/// assert_eq!(response.body_str(), Some("{}"));
+/// assert_eq!(response.headers.len(), 3);
+/// assert_eq!(response.headers["Content-Type"], "application/json");
+/// assert_eq!(response.headers["X-Upstream"], "billing");
+/// assert_eq!(response.headers["Content-Length"], "2");
+///
+/// let headers = HashMap::from([("Content-Length".to_owned(), "10".to_owned())]);
+/// let head: HttpResponse = (HttpStatus::OK, String::new(), headers).into();
+/// assert_eq!(head.headers["Content-Length"], "10");
+/// ```
+impl<S: BuildHasher> From<(HttpStatus, String, HashMap<String, String, S>)> for HttpResponse {
+    fn from((status, body, headers): (HttpStatus, String, HashMap<String, String, S>)) -> Self {
+        Self::with_headers(status, Some(body), headers)
+    }
+}
+
+/// Separate builder for [`HttpResponse`], nothing is final until [`HttpResponseBuilder::build`]
+#[derive(Debug, Clone, Default)]
+pub struct HttpResponseBuilder {
+    status: HttpStatus,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+}
+
+impl HttpResponseBuilder {
+    #[must_use]
+    pub fn status(mut self, status: HttpStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    #[must_use]
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    #[must_use]
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body.replace(body.into());
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> HttpResponse {
+        let mut response = HttpResponse::new().set_status(self.status);
+        if let Some(body) = self.body {
+            response = response.set_body(body);
+        }
+        self.headers
+            .into_iter()
+            .fold(response, |response, (k, v)| response.insert_header(k, v))
+    }
+}
+
+/// `200 OK` with an empty body, `Content-Length: 0` included so clients don't wait for a body
+///
+/// ```rust
+/// use torus_http::response::HttpResponse;
+/// let response = HttpResponse::default();
+/// assert_eq!(response.headers.get("Content-Length").map(String::as_str), Some("0"));
+/// assert_eq!(response, HttpResponse::new());
+/// ```
+impl Default for HttpResponse {
+    fn default() -> Self {
+        Self {
+            headers: HashMap::from([("Content-Length".into(), "0".into())]),
+            status: HttpStatus::default(),
+            body: None,
+            upgrade: None,
+            error: None,
+            failure: None,
+            cookies: Vec::new(),
+            interim: Vec::new(),
+            links: Vec::new(),
+        }
+    }
 }
 
 impl HttpResponse {
     #[must_use]
     pub fn new_body(body: String, status: HttpStatus) -> Self {
         let mut headers: HashMap<String, String> = HashMap::new();
-        headers.insert("Content-Length".into(), body.chars().count().to_string());
+        headers.insert("Content-Length".into(), body.len().to_string());
         Self {
             headers,
             status,
-            body: Some(body),
+            body: Some(Cow::Owned(body.into_bytes())),
+            upgrade: None,
+            error: None,
+            failure: None,
+            cookies: Vec::new(),
+            interim: Vec::new(),
+            links: Vec::new(),
         }
     }
 
+    /// Sets a header, replacing an earlier value
+    ///
+    /// Line breaks would let a value smuggle in headers of its own, so they are dropped when the
+    /// response is sent. Use [`HttpResponse::try_insert_header`] to find out about them instead
+    ///
+    /// ```rust
+    /// use torus_http::{prelude::*, testing::TestClient};
+    ///
+    /// let client = TestClient::new(HttpServer::new().get("/", |req: HttpRequest| {
+    ///     // echoing client input into headers is asking for trouble
+    ///     let name = req.query.unwrap_or_default().remove("name").unwrap_or_default();
+    ///     HttpResponse::new().insert_header("X-Name", name.replace("%0D%0A", "\r\n"))
+    /// }));
+    ///
+    /// let res = client.get("/?name=bob%0D%0AX-Injected:%20evil").send();
+    /// let sent = String::from_utf8(res.into_bytes()).unwrap();
+    /// assert!(sent.contains("X-Name: bobX-Injected:%20evil\r\n"));
+    /// assert!(!sent.contains("\r\nX-Injected"));
+    /// ```
     #[must_use]
     pub fn insert_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.headers.insert(key.into(), value.into());
         self
     }
 
+    /// Adds a `Set-Cookie` header, unlike [`HttpResponse::insert_header`] this keeps the ones
+    /// added before
+    ///
+    /// ```rust
+    /// use torus_http::{cookie::Cookie, prelude::*};
+    /// let res = HttpResponse::new()
+    ///     .add_cookie(&Cookie::new("a", "1"))
+    ///     .add_cookie(&Cookie::new("b", "2").http_only(true));
+    /// assert_eq!(res.set_cookies().collect::<Vec<_>>(), ["a=1", "b=2; HttpOnly"]);
+    /// assert!(res.into_bytes().ends_with(b"Set-Cookie: a=1\r\nSet-Cookie: b=2; HttpOnly\r\n\r\n"));
+    /// ```
     #[must_use]
-    pub fn set_body(mut self, body: impl Into<String>) -> Self {
+    pub fn add_cookie(mut self, cookie: &Cookie) -> Self {
+        self.cookies.push(cookie.to_string());
+        self
+    }
+
+    /// Every `Set-Cookie` header, whether it was inserted as a header or added with
+    /// [`HttpResponse::add_cookie`]
+    pub fn set_cookies(&self) -> impl Iterator<Item = &str> {
+        self.header("Set-Cookie")
+            .into_iter()
+            .chain(self.cookies.iter().map(String::as_str))
+    }
+
+    /// Every `Link` header, whether it was inserted as a header or given to
+    /// [`HttpResponse::early_hints`]
+    pub fn links(&self) -> impl Iterator<Item = &str> {
+        self.header("Link")
+            .into_iter()
+            .chain(self.links.iter().map(String::as_str))
+    }
+
+    /// A `103 Early Hints` response, letting the client start loading `links` while the final
+    /// response is still being put together
+    ///
+    /// Every link is sent as a `Link` header of its own. Send it with
+    /// [`HttpResponse::with_interim`] or by returning `(vec![hints], response)` from a handler
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    /// let hints = HttpResponse::early_hints(&[
+    ///     "</style.css>; rel=preload; as=style",
+    ///     "</app.js>; rel=preload; as=script",
+    /// ]);
+    /// assert_eq!(hints.links().count(), 2);
+    /// assert_eq!(
+    ///     hints.into_bytes(),
+    ///     b"HTTP/1.1 103 Early Hints\r\n\
+    ///       Link: </style.css>; rel=preload; as=style\r\n\
+    ///       Link: </app.js>; rel=preload; as=script\r\n\r\n"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn early_hints(links: &[&str]) -> Self {
+        Self {
+            headers: HashMap::new(),
+            status: HttpStatus::EARLY_HINTS,
+            links: links.iter().map(|&link| link.to_owned()).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// `204 No Content`, for `DELETE`s, `PUT`s and the like that have nothing to say
+    ///
+    /// A `204` never has a body, so none is sent even if one is set later on, and neither is
+    /// `Content-Length`
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    /// assert_eq!(HttpResponse::no_content().into_bytes(), b"HTTP/1.1 204 No Content\r\n\r\n");
+    ///
+    /// let res = HttpResponse::no_content().set_body("ignored");
+    /// assert_eq!(res.into_bytes(), b"HTTP/1.1 204 No Content\r\n\r\n");
+    ///
+    /// // however the handler spells it
+    /// use torus_http::testing::TestClient;
+    /// let client = TestClient::new(HttpServer::new().delete("/", |_| {
+    ///     HttpResponse::no_content().insert_header("content-length", "7")
+    /// }));
+    /// assert_eq!(client.delete("/").send().header("Content-Length"), None);
+    /// ```
+    #[must_use]
+    pub fn no_content() -> Self {
+        Self {
+            headers: HashMap::new(),
+            status: HttpStatus::NO_CONTENT,
+            ..Self::default()
+        }
+    }
+
+    /// `201 Created`, `location` points at what was created
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    /// let res = HttpResponse::created("/users/7");
+    /// assert_eq!(res.status, HttpStatus::CREATED);
+    /// assert_eq!(res.header("Location"), Some("/users/7"));
+    ///
+    /// let raw = String::from_utf8(res.into_bytes()).unwrap();
+    /// assert!(raw.starts_with("HTTP/1.1 201 Created\r\n"));
+    /// assert!(raw.contains("\r\nLocation: /users/7\r\n"));
+    /// assert!(raw.contains("\r\nContent-Length: 0\r\n"));
+    /// assert!(raw.ends_with("\r\n\r\n"));
+    /// ```
+    #[must_use]
+    pub fn created(location: &str) -> Self {
+        Self::new()
+            .set_status(HttpStatus::CREATED)
+            .insert_header("Location", location)
+    }
+
+    /// Sends `interim` ahead of this response, `1xx` responses are the only ones that make sense
+    /// here
+    ///
+    /// Http/1.0 has no `1xx` responses, so clients speaking it only get the final one
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .get("/", |_| {
+    ///         HttpResponse::new()
+    ///             .set_body("page")
+    ///             .with_interim(HttpResponse::early_hints(&[
+    ///                 "</style.css>; rel=preload",
+    ///                 "</app.js>; rel=preload",
+    ///             ]))
+    ///     })
+    ///     .bind(("127.0.0.1", 0))
+    ///     .unwrap();
+    /// let port = server.port();
+    /// thread::spawn(move || server.listen());
+    ///
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    /// stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with(
+    ///     "HTTP/1.1 103 Early Hints\r\n\
+    ///      Link: </style.css>; rel=preload\r\n\
+    ///      Link: </app.js>; rel=preload\r\n\r\n\
+    ///      HTTP/1.1 200 OK\r\n"
+    /// ));
+    /// assert!(response.ends_with("\r\n\r\npage"));
+    ///
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    /// stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(!response.contains("Early Hints"));
+    /// ```
+    #[must_use]
+    pub fn with_interim(mut self, interim: HttpResponse) -> Self {
+        self.interim.push(interim);
+        self
+    }
+
+    /// The responses sent ahead of this one, see [`HttpResponse::with_interim`]
+    #[must_use]
+    pub fn interim(&self) -> &[HttpResponse] {
+        &self.interim
+    }
+
+    /// Adds the metrics of `timing` to the `Server-Timing` header, after any already there
+    ///
+    /// ```rust
+    /// use torus_http::{prelude::*, timing::ServerTiming};
+    /// let res = HttpResponse::new()
+    ///     .with_server_timing(ServerTiming::new().metric("db", 12.5))
+    ///     .with_server_timing(ServerTiming::new().metric("render", 3.1));
+    /// assert_eq!(res.headers["Server-Timing"], "db;dur=12.5, render;dur=3.1");
+    /// ```
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)] // built for this response, no need to keep it around
+    pub fn with_server_timing(mut self, timing: ServerTiming) -> Self {
+        if timing.is_empty() {
+            return self;
+        }
+        let existing = self
+            .headers
+            .keys()
+            .find(|k| k.eq_ignore_ascii_case("Server-Timing"))
+            .cloned()
+            .and_then(|k| self.headers.remove(&k));
+        let value = match existing {
+            Some(existing) => format!("{existing}, {timing}"),
+            None => timing.to_string(),
+        };
+        self.insert_header("Server-Timing", value)
+    }
+
+    /// Like [`HttpResponse::insert_header`], but refuses names and values containing line breaks
+    ///
+    /// # Errors
+    ///
+    /// - `key` or `value` contains a `\r` or `\n`
+    ///
+    /// ```rust
+    /// use torus_http::response::HttpResponse;
+    /// let res = HttpResponse::new().try_insert_header("X-Name", "bob\r\nX-Injected: evil");
+    /// assert!(res.is_err());
+    /// let res = HttpResponse::new().try_insert_header("X-Name", "bob").unwrap();
+    /// assert_eq!(res.headers["X-Name"], "bob");
+    /// ```
+    pub fn try_insert_header(
+        self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<Self, HeaderInjectionError> {
+        let (key, value) = (key.into(), value.into());
+        if has_line_break(&key) || has_line_break(&value) {
+            return Err(HeaderInjectionError { name: key });
+        }
+        Ok(self.insert_header(key, value))
+    }
+
+    /// Sets the body and its `Content-Length`, anything from text to raw bytes goes
+    ///
+    /// ```rust
+    /// use torus_http::response::HttpResponse;
+    /// let png = HttpResponse::new().set_body(&b"\x89PNG\r\n"[..]);
+    /// assert_eq!(png.headers["Content-Length"], "6");
+    /// assert_eq!(png.body_str(), None);
+    /// ```
+    #[must_use]
+    pub fn set_body(mut self, body: impl Into<Vec<u8>>) -> Self {
         let body = body.into();
-        let body_len = body.chars().count();
-        self.body.replace(body);
+        let body_len = body.len();
+        self.body.replace(Cow::Owned(body));
         self.headers
             .insert("Content-Length".into(), body_len.to_string());
         self
     }
 
+    /// [`HttpResponse::set_body`] without copying, for data that lives as long as the program
+    ///
+    /// ```rust
+    /// use std::borrow::Cow;
+    /// use torus_http::response::HttpResponse;
+    /// static LOGO: &[u8] = b"\x89PNG\r\n";
+    /// let png = HttpResponse::new().set_static_body(LOGO);
+    /// assert!(matches!(png.body, Some(Cow::Borrowed(body)) if body.as_ptr() == LOGO.as_ptr()));
+    /// assert_eq!(png.headers["Content-Length"], "6");
+    /// ```
+    #[must_use]
+    pub fn set_static_body(mut self, body: &'static [u8]) -> Self {
+        self.body.replace(Cow::Borrowed(body));
+        self.headers
+            .insert("Content-Length".into(), body.len().to_string());
+        self
+    }
+
+    /// Serializes `value` as the body, setting `Content-Length` and
+    /// `Content-Type: application/json`, for when a response is built up step by step
+    ///
+    /// # Errors
+    ///
+    /// - `value` can't be serialized, the response is left untouched then
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use torus_http::{prelude::*, status::SuccessResponse};
+    ///
+    /// #[derive(Serialize)]
+    /// struct User {
+    ///     id: u32,
+    /// }
+    ///
+    /// let mut res = HttpResponse::new().set_status(SuccessResponse::Created.into());
+    /// res.set_body_json(&User { id: 7 }).unwrap();
+    /// assert_eq!(res.body_str(), Some(r#"{"id":7}"#));
+    /// assert_eq!(res.headers["Content-Type"], "application/json");
+    /// assert_eq!(res.headers["Content-Length"], "8");
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn set_body_json<T: serde::Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), serde_json::Error> {
+        let json = serde_json::to_vec(value)?;
+        self.headers
+            .insert("Content-Length".into(), json.len().to_string());
+        self.headers
+            .insert("Content-Type".into(), "application/json".into());
+        self.body = Some(Cow::Owned(json));
+        Ok(())
+    }
+
+    /// [`HttpResponse::set_body_json`] for chaining
+    ///
+    /// # Errors
+    ///
+    /// - `value` can't be serialized
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use torus_http::prelude::*;
+    ///
+    /// let res = HttpResponse::new()
+    ///     .set_status(HttpStatus::NOT_FOUND)
+    ///     .with_json_body(&HashMap::from([("error", "no such user")]))
+    ///     .unwrap();
+    /// assert_eq!(res.status, HttpStatus::NOT_FOUND);
+    /// assert_eq!(res.body_str(), Some(r#"{"error":"no such user"}"#));
+    ///
+    /// // maps need string keys in json
+    /// let res = HttpResponse::new().with_json_body(&HashMap::from([((1, 2), "pair")]));
+    /// assert!(res.is_err());
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn with_json_body<T: serde::Serialize + ?Sized>(
+        mut self,
+        value: &T,
+    ) -> Result<Self, serde_json::Error> {
+        self.set_body_json(value)?;
+        Ok(self)
+    }
+
     #[must_use]
     pub fn set_status(mut self, status: HttpStatus) -> Self {
         self.status = status;
@@ -103,30 +888,359 @@ impl HttpResponse {
 
     #[must_use]
     pub fn new() -> Self {
-        Self {
+        Self::default()
+    }
+
+    /// The body as text, `None` if there is none or it isn't valid utf-8
+    #[must_use]
+    pub fn body_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.body.as_deref()?).ok()
+    }
+
+    /// The [`HttpError`] this response was made from, including its message if that was hidden
+    /// from the client
+    #[must_use]
+    pub fn error(&self) -> Option<&HttpError> {
+        self.error.as_deref()
+    }
+
+    /// Case insensitive header lookup, like [`HttpRequest::header`](crate::request::HttpRequest::header)
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    /// let response = HttpResponse::new().insert_header("Content-Type", "text/html");
+    /// assert_eq!(response.header("content-type"), Some("text/html"));
+    /// assert_eq!(response.header("Location"), None);
+    /// ```
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        if let Some(value) = self.headers.get(name) {
+            return Some(value);
+        }
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Build a whole response in one go, handy when the headers already come as a collection
+    ///
+    /// `Content-Length` is derived from the body unless `headers` brings its own
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    /// let response = HttpResponse::with_headers(
+    ///     HttpStatus::NOT_FOUND,
+    ///     Some("gone".into()),
+    ///     [("Content-Type", "text/plain"), ("X-Reason", "deleted")],
+    /// );
+    /// assert_eq!(response.status, HttpStatus::NOT_FOUND);
+    /// assert_eq!(response.headers["X-Reason"], "deleted");
+    /// assert_eq!(response.headers["Content-Length"], "4");
+    /// ```
+    #[must_use]
+    pub fn with_headers<K: Into<String>, V: Into<String>>(
+        status: HttpStatus,
+        body: Option<String>,
+        headers: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        let mut response = Self::new().set_status(status);
+        if let Some(body) = body {
+            response = response.set_body(body);
+        }
+        headers
+            .into_iter()
+            .fold(response, |response, (k, v)| response.insert_header(k, v))
+    }
+
+    /// Start building a response step by step with a [`HttpResponseBuilder`]
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    /// let response = HttpResponse::builder()
+    ///     .status(HttpStatus::BAD_REQUEST)
+    ///     .header("Content-Type", "text/plain")
+    ///     .body("nope")
+    ///     .build();
+    /// assert_eq!(response.status, HttpStatus::BAD_REQUEST);
+    /// assert_eq!(response.body_str(), Some("nope"));
+    /// ```
+    #[must_use]
+    pub fn builder() -> HttpResponseBuilder {
+        HttpResponseBuilder::default()
+    }
+
+    /// Hand the raw connection to `f` once this response has been written, the server stops
+    /// speaking http on it afterwards
+    ///
+    /// Meant for `101 Switching Protocols` responses, see the [`upgrade`](crate::upgrade) module
+    #[must_use]
+    pub fn on_upgrade(mut self, f: impl FnOnce(Upgraded) + Send + 'static) -> Self {
+        self.upgrade.replace(Upgrade::new(f));
+        self
+    }
+
+    /// Serialize the response straight into `writer`
+    ///
+    /// Every piece is its own write, so hand it something buffered like a `Vec` or a
+    /// `BufWriter` rather than a bare socket
+    ///
+    /// # Errors
+    ///
+    /// - writing to `writer` failed
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    /// let res = HttpResponse::new().set_body("hi");
+    /// let mut out = Vec::new();
+    /// res.write_to(&mut out).unwrap();
+    /// assert_eq!(out, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi");
+    /// assert_eq!(res.into_bytes(), out);
+    /// ```
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for interim in &self.interim {
+            interim.write_to(writer)?;
+        }
+        write!(writer, "HTTP/1.1 {}\r\n", self.status)?;
+        let bodyless = self.forbids_body();
+        for (k, v) in &self.headers {
+            if bodyless && k.eq_ignore_ascii_case("Content-Length") {
+                continue;
+            }
+            write_header_part(writer, k)?;
+            writer.write_all(b": ")?;
+            write_header_part(writer, v)?;
+            writer.write_all(b"\r\n")?;
+        }
+        for cookie in &self.cookies {
+            writer.write_all(b"Set-Cookie: ")?;
+            write_header_part(writer, cookie)?;
+            writer.write_all(b"\r\n")?;
+        }
+        for link in &self.links {
+            writer.write_all(b"Link: ")?;
+            write_header_part(writer, link)?;
+            writer.write_all(b"\r\n")?;
+        }
+        writer.write_all(b"\r\n")?;
+        if let Some(body) = self.body.as_ref().filter(|_| !bodyless) {
+            writer.write_all(body)?;
+        }
+        Ok(())
+    }
+
+    /// `1xx`, `204` and `304` responses must not have a body, nor a `Content-Length` announcing
+    /// one
+    pub(crate) fn forbids_body(&self) -> bool {
+        self.status.code() < 200
+            || self.status == HttpStatus::NO_CONTENT
+            || self.status == HttpStatus::NOT_MODIFIED
+    }
+
+    /// Parses a response as it came over the wire, the counterpart of
+    /// [`HttpResponse::write_to`]
+    ///
+    /// Chunked bodies are decoded, leaving a `Content-Length` in place of the
+    /// `Transfer-Encoding`. A body is cut off at `Content-Length`, and a missing body is fine
+    /// since responses to `HEAD` requests don't have one. Repeated headers are joined with `, `,
+    /// except `Set-Cookie` which ends up in [`HttpResponse::set_cookies`]
+    ///
+    /// # Errors
+    ///
+    /// - the head isn't complete
+    /// - the status line isn't `HTTP/<version> <code> <reason>`, or torus doesn't know the code
+    /// - a header line has no `:` or isn't utf-8
+    /// - `Content-Length` isn't a number or there are fewer bytes than it says
+    /// - the chunked body is broken
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    ///
+    /// let res = HttpResponse::from_bytes(b"HTTP/1.1 404 Not Found\r\nContent-Length: 4\r\n\r\nnope").unwrap();
+    /// assert_eq!(res.status, HttpStatus::NOT_FOUND);
+    /// assert_eq!(res.body_str(), Some("nope"));
+    ///
+    /// let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\n\r\n\
+    ///             5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+    /// let res = HttpResponse::from_bytes(raw).unwrap();
+    /// assert_eq!(res.body_str(), Some("hello world"));
+    /// assert_eq!(res.header("Content-Length"), Some("11"));
+    /// assert_eq!(res.header("Transfer-Encoding"), None);
+    /// assert_eq!(res.set_cookies().collect::<Vec<_>>(), ["a=1", "b=2"]);
+    ///
+    /// // what goes out comes back in
+    /// let sent = HttpResponse::new().set_status(HttpStatus::FORBIDDEN).set_body("no");
+    /// assert_eq!(HttpResponse::from_bytes(&sent.clone().into_bytes()).unwrap(), sent);
+    ///
+    /// assert!(HttpResponse::from_bytes(b"HTTP/1.1 200 OK\r\n").is_err());
+    /// assert!(HttpResponse::from_bytes(b"HTTP/1.1 2000 Odd\r\n\r\n").is_err());
+    /// assert!(HttpResponse::from_bytes(b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nshort").is_err());
+    /// ```
+    pub fn from_bytes(raw: &[u8]) -> Result<Self, ResponseParseError> {
+        let head_len = request::head_len(raw).ok_or(ResponseParseError::IncompleteHead)?;
+        let head =
+            std::str::from_utf8(&raw[..head_len]).map_err(|_| ResponseParseError::InvalidHeader)?;
+        let mut lines = head.lines();
+
+        let status_line = lines.next().unwrap_or_default();
+        let mut parts = status_line.splitn(3, ' ');
+        let code: u16 = match (parts.next(), parts.next()) {
+            (Some(version), Some(code)) if version.starts_with("HTTP/") => code
+                .parse()
+                .map_err(|_| ResponseParseError::InvalidStatusLine)?,
+            _ => return Err(ResponseParseError::InvalidStatusLine),
+        };
+        let status = match HttpStatus::from_code(code) {
+            Some(status) => status,
+            None => CustomStatus::new(code, parts.next().unwrap_or_default())
+                .ok_or(ResponseParseError::UnknownStatus(code))?
+                .into(),
+        };
+
+        let mut res = HttpResponse {
             headers: HashMap::new(),
-            status: HttpStatus::default(),
-            body: None,
+            ..HttpResponse::new().set_status(status)
+        };
+        for line in lines.take_while(|line| !line.is_empty()) {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or(ResponseParseError::InvalidHeader)?;
+            let (name, value) = (name.trim(), value.trim());
+            if name.eq_ignore_ascii_case("Set-Cookie") {
+                res.cookies.push(value.to_owned());
+            } else if let Some(existing) = res.headers.get_mut(name) {
+                existing.push_str(", ");
+                existing.push_str(value);
+            } else {
+                res.headers.insert(name.to_owned(), value.to_owned());
+            }
         }
+
+        let rest = &raw[head_len..];
+        let chunked = res
+            .header("Transfer-Encoding")
+            .is_some_and(parser::ends_in_chunked);
+        let body = if chunked && !rest.is_empty() {
+            res.headers
+                .retain(|name, _| !name.eq_ignore_ascii_case("Transfer-Encoding"));
+            let body = parser::dechunk(rest)?;
+            res.headers
+                .insert("Content-Length".into(), body.len().to_string());
+            body
+        } else if let Some(len) = res.header("Content-Length") {
+            let len: usize = len
+                .parse()
+                .map_err(|_| ResponseParseError::InvalidContentLength)?;
+            match rest.get(..len) {
+                Some(body) => body.to_vec(),
+                None if rest.is_empty() => Vec::new(),
+                None => return Err(ResponseParseError::IncompleteBody),
+            }
+        } else {
+            rest.to_vec()
+        };
+        res.body = (!body.is_empty()).then_some(Cow::Owned(body));
+        Ok(res)
     }
 
-    pub(crate) fn into_bytes(self) -> Vec<u8> {
-        self.into_string().into_bytes()
+    /// The response as it goes over the wire
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        let body_len = self.body.as_deref().map_or(0, <[u8]>::len);
+        let mut bytes = Vec::with_capacity(body_len + 256);
+        // writing into a `Vec` can't fail
+        _ = self.write_to(&mut bytes);
+        bytes
+    }
+}
+
+/// A header name or value contained a line break, see [`HttpResponse::try_insert_header`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderInjectionError {
+    /// The offending header
+    pub name: String,
+}
+
+impl std::fmt::Display for HeaderInjectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line break in header {:?}", self.name)
     }
+}
 
-    pub(crate) fn into_string(self) -> String {
-        use std::fmt::Write;
+impl std::error::Error for HeaderInjectionError {}
 
-        let headers = self.headers.iter().fold(String::new(), |mut acc, (k, v)| {
-            acc.write_fmt(core::format_args!("{k}: {v}\r\n"))
-                .expect("if this fails fuck clippy");
-            acc
-        });
+/// Reasons [`HttpResponse::from_bytes`] can reject a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseParseError {
+    /// The empty line ending the head is missing
+    IncompleteHead,
+    /// The first line isn't `HTTP/<version> <code> <reason>`
+    InvalidStatusLine,
+    /// A status code that isn't three digits
+    UnknownStatus(u16),
+    /// A header line without a `:`, or one that isn't utf-8
+    InvalidHeader,
+    InvalidContentLength,
+    /// There are fewer bytes than `Content-Length` promised
+    IncompleteBody,
+    Chunked(ChunkParseError),
+}
 
-        format!(
-            "HTTP/1.1 {}\r\n{headers}\r\n{}",
-            self.status,
-            self.body.unwrap_or_default()
-        )
+impl std::fmt::Display for ResponseParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResponseParseError::IncompleteHead => write!(f, "response head ended early"),
+            ResponseParseError::InvalidStatusLine => write!(f, "invalid status line"),
+            ResponseParseError::UnknownStatus(code) => write!(f, "unknown status code {code}"),
+            ResponseParseError::InvalidHeader => write!(f, "invalid header line"),
+            ResponseParseError::InvalidContentLength => write!(f, "invalid Content-Length header"),
+            ResponseParseError::IncompleteBody => write!(f, "response body ended early"),
+            ResponseParseError::Chunked(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ResponseParseError {}
+
+impl From<ChunkParseError> for ResponseParseError {
+    fn from(err: ChunkParseError) -> Self {
+        Self::Chunked(err)
+    }
+}
+
+fn has_line_break(s: &str) -> bool {
+    s.contains(['\r', '\n'])
+}
+
+/// Writes a header name or value, leaving out line breaks so it can't end the header early
+fn write_header_part(writer: &mut impl Write, part: &str) -> std::io::Result<()> {
+    if has_line_break(part) {
+        let cleaned: Vec<u8> = part.bytes().filter(|&b| b != b'\r' && b != b'\n').collect();
+        writer.write_all(&cleaned)
+    } else {
+        writer.write_all(part.as_bytes())
+    }
+}
+
+/// Support code for `#[derive(Response)]`, not part of the public api, the `derive` feature
+/// turns on `json` for it
+#[cfg(feature = "json")]
+#[doc(hidden)]
+pub mod __private {
+    use super::HttpResponse;
+    use crate::status::HttpStatus;
+
+    pub fn json_response<T: serde::Serialize + ?Sized>(
+        value: &T,
+        status: HttpStatus,
+        content_type: &str,
+    ) -> HttpResponse {
+        match HttpResponse::new().set_status(status).with_json_body(value) {
+            Ok(res) => res.insert_header("Content-Type", content_type),
+            Err(_) => HttpResponse::new_body(
+                "failed serializing response".into(),
+                HttpStatus::INTERNAL_SERVER_ERROR,
+            ),
+        }
     }
 }
@@ -1,31 +1,283 @@
 //! The actual http server on which you define your routes
 use std::{
+    any::{Any, TypeId},
     collections::HashMap,
-    io::{Read, Write},
-    net::{TcpListener, ToSocketAddrs},
-    str::{FromStr, Utf8Error, from_utf8},
-    sync::Arc,
+    fmt::Write as _,
+    io::{ErrorKind, Read, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    str::{Utf8Error, from_utf8},
+    sync::{Arc, Mutex, OnceLock, PoisonError, mpsc},
+    time::{Duration, Instant, SystemTime},
 };
 
-use crate::{method::HttpMethod, request::HttpRequest, response::Response};
+use crate::{
+    extensions::Extensions,
+    files::{EmbeddedFile, ServeDir},
+    logger::{Logger, PanickedRequest, StderrLogger},
+    method::HttpMethod,
+    metrics::{self, Metrics},
+    middleware::{Middleware, MiddlewareError, MiddlewareResult, Next, timeout},
+    parser::{self, ChunkParseError},
+    pattern::{CompiledPattern, InvalidPattern, PathParams},
+    request::{self, ConnectionInfo, HttpRequest, RequestParseError},
+    response::{Failure, HttpResponse, Response},
+    status::{ClientErrorResponse, HttpStatus},
+    time::format_http_date,
+    upgrade::{Upgrade, Upgraded},
+};
 
 /// A generic trait to allow many different types of handlers to be passed into our http server
 pub trait HandlerFn: Send + Sync {
-    fn call(&self, req: HttpRequest) -> Box<dyn Response>;
+    fn call(&self, req: HttpRequest) -> HttpResponse;
 }
 
 impl<F, T> HandlerFn for F
 where
     F: Fn(HttpRequest) -> T + Send + Sync,
-    T: Response + 'static,
+    T: Response,
+{
+    fn call(&self, req: HttpRequest) -> HttpResponse {
+        self(req).into_response()
+    }
+}
+
+/// Adapts a handler returning `Result<R, E>` for any error type, so it can use `?` freely
+///
+/// Errors are turned into responses by the function registered for their type with
+/// [`HttpServer::map_error`], or become a plain `500 Internal Server Error`. Error types that
+/// are responses themselves, like [`HttpError`](crate::error::HttpError), don't need this
+///
+/// # Example usage:
+///
+/// ```rust
+/// use std::{fs, io};
+/// use torus_http::{prelude::*, server::fallible, testing::TestClient};
+///
+/// #[derive(Debug)]
+/// enum AppError {
+///     Io(io::Error),
+///     NotAllowed,
+/// }
+///
+/// impl From<io::Error> for AppError {
+///     fn from(err: io::Error) -> Self {
+///         AppError::Io(err)
+///     }
+/// }
+///
+/// fn read_config(_req: HttpRequest) -> Result<String, AppError> {
+///     Ok(fs::read_to_string("/definitely/not/here.toml")?)
+/// }
+///
+/// fn admin(_req: HttpRequest) -> Result<&'static str, AppError> {
+///     Err(AppError::NotAllowed)
+/// }
+///
+/// let client = TestClient::new(
+///     HttpServer::new()
+///         .get("/config", fallible(read_config))
+///         .get("/admin", fallible(admin))
+///         .get("/raw", fallible(|_| -> Result<&'static str, io::Error> { Err(io::Error::other("oops")) })),
+/// );
+/// // nothing registered for `AppError` or `io::Error` yet
+/// assert_eq!(client.get("/config").send().status, HttpStatus::INTERNAL_SERVER_ERROR);
+/// assert_eq!(client.get("/raw").send().body_str(), Some("internal server error"));
+///
+/// let client = TestClient::new(
+///     HttpServer::new()
+///         .map_error(|err: &AppError| match err {
+///             AppError::Io(err) => HttpResponse::new_body(err.to_string(), HttpStatus::INTERNAL_SERVER_ERROR),
+///             AppError::NotAllowed => HttpResponse::new_body("no".into(), HttpStatus::FORBIDDEN),
+///         })
+///         .get("/config", fallible(read_config))
+///         .get("/admin", fallible(admin)),
+/// );
+/// let res = client.get("/config").send();
+/// assert_eq!(res.status, HttpStatus::INTERNAL_SERVER_ERROR);
+/// assert!(res.body_str().unwrap().contains("No such file"));
+/// assert_eq!(client.get("/admin").send().status, HttpStatus::FORBIDDEN);
+/// ```
+pub fn fallible<F, R, E>(handler: F) -> impl Fn(HttpRequest) -> HttpResponse + Send + Sync + 'static
+where
+    F: Fn(HttpRequest) -> Result<R, E> + Send + Sync + 'static,
+    R: Response,
+    E: Send + Sync + 'static,
 {
-    fn call(&self, req: HttpRequest) -> Box<dyn Response> {
-        Box::new(self(req))
+    move |req| match handler(req) {
+        Ok(res) => res.into_response(),
+        Err(err) => {
+            let mut res = HttpResponse::new().set_status(HttpStatus::INTERNAL_SERVER_ERROR);
+            res.failure = Some(Failure(Arc::new(err)));
+            res
+        }
+    }
+}
+
+pub type MiddleWareFn = Box<dyn Fn(HttpRequest) -> HttpRequest + Send + Sync>;
+/// Whatever was handed to [`HttpServer::set_state`]
+type State = Option<Arc<dyn Any + Send + Sync>>;
+/// Middleware as the server stores it, stateless middleware just ignore the state
+type StoredMiddleware = Box<dyn Fn(&State, HttpRequest) -> MiddlewareResult + Send + Sync>;
+pub type ResponseMiddlewareFn =
+    Box<dyn Fn(&HttpRequest, HttpResponse) -> HttpResponse + Send + Sync>;
+type MiddlewareErrorFn = Box<dyn Fn(&MiddlewareError) -> HttpResponse + Send + Sync>;
+/// Turns the error of a [`fallible`] handler into a response if it has the right type
+type ErrorMapperFn = Box<dyn Fn(&(dyn Any + Send + Sync)) -> Option<HttpResponse> + Send + Sync>;
+type StartFn = Box<dyn FnOnce(SocketAddr) + Send>;
+pub type Handler = Arc<dyn HandlerFn + Send + Sync>;
+/// Middleware that only runs for a single route, see [`HttpServer::route_with_middleware`]
+pub type RouteMiddlewareFn = Arc<dyn Fn(HttpRequest) -> MiddlewareResult + Send + Sync>;
+
+/// A registered handler plus what we know about it
+struct Route {
+    handler: Handler,
+    /// Shown by `trace_routes`, defaults to the handler's type name
+    name: &'static str,
+    /// Whether `name` was given rather than made up, only those show up in `print_routes`
+    named: bool,
+    /// Position in registration order
+    index: usize,
+    /// Runs after the global middleware, only for this route
+    middleware: Vec<RouteMiddlewareFn>,
+}
+
+/// A route that hasn't been registered yet, see [`HttpServer::register`]
+///
+/// With the `derive` feature the `#[get(...)]`, `#[post(...)]`, ... attributes generate these for
+/// you and `routes!` collects them
+pub struct RouteDef {
+    pub path: String,
+    pub method: HttpMethod,
+    /// Shown by [`HttpServer::trace_routes`]
+    pub name: &'static str,
+    pub handler: Handler,
+}
+
+impl RouteDef {
+    pub fn new<F: HandlerFn + 'static>(
+        path: impl Into<String>,
+        method: HttpMethod,
+        name: &'static str,
+        f: F,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            method,
+            name,
+            handler: Arc::new(f),
+        }
+    }
+}
+
+impl std::fmt::Debug for RouteDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RouteDef")
+            .field("path", &self.path)
+            .field("method", &self.method)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "derive")]
+pub use torus_http_macros::{delete, get, head, options, patch, post, put, routes};
+
+/// Several methods on one path, see [`HttpServer::at`]
+///
+/// Nothing is registered until [`RouteBuilder::build`] hands back the server
+#[must_use = "the routes are only registered by `build`"]
+pub struct RouteBuilder {
+    server: HttpServer,
+    path: String,
+    routes: Vec<(HttpMethod, Handler, &'static str)>,
+    middleware: Vec<RouteMiddlewareFn>,
+}
+
+impl RouteBuilder {
+    /// Handle `method` with `f`, replacing an earlier handler for the same method
+    pub fn route<F: HandlerFn + 'static>(mut self, method: HttpMethod, f: F) -> Self {
+        self.routes.retain(|(other, _, _)| *other != method);
+        self.routes
+            .push((method, Arc::new(f), std::any::type_name::<F>()));
+        self
+    }
+
+    pub fn get<F: HandlerFn + 'static>(self, f: F) -> Self {
+        self.route(HttpMethod::Get, f)
+    }
+
+    pub fn post<F: HandlerFn + 'static>(self, f: F) -> Self {
+        self.route(HttpMethod::Post, f)
+    }
+
+    pub fn put<F: HandlerFn + 'static>(self, f: F) -> Self {
+        self.route(HttpMethod::Put, f)
+    }
+
+    pub fn patch<F: HandlerFn + 'static>(self, f: F) -> Self {
+        self.route(HttpMethod::Patch, f)
+    }
+
+    pub fn delete<F: HandlerFn + 'static>(self, f: F) -> Self {
+        self.route(HttpMethod::Delete, f)
+    }
+
+    pub fn head<F: HandlerFn + 'static>(self, f: F) -> Self {
+        self.route(HttpMethod::Head, f)
+    }
+
+    pub fn options<F: HandlerFn + 'static>(self, f: F) -> Self {
+        self.route(HttpMethod::Options, f)
+    }
+
+    /// Middleware for every method on this path, whether it was added before or after, see
+    /// [`HttpServer::route_with_middleware`]
+    pub fn with_middleware<R: Into<MiddlewareResult>>(
+        mut self,
+        middleware: impl Fn(HttpRequest) -> R + Send + Sync + 'static,
+    ) -> Self {
+        self.middleware.extend(route_middleware([middleware]));
+        self
+    }
+
+    /// Registers the routes and hands back the server
+    #[must_use]
+    pub fn build(self) -> HttpServer {
+        let Self {
+            server,
+            path,
+            routes,
+            middleware,
+        } = self;
+        routes
+            .into_iter()
+            .fold(server, |server, (method, handler, name)| {
+                server.insert_route(
+                    path.clone(),
+                    method,
+                    handler,
+                    name,
+                    false,
+                    middleware.clone(),
+                )
+            })
+    }
+}
+
+impl std::fmt::Debug for RouteBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let methods: Vec<&str> = self
+            .routes
+            .iter()
+            .map(|(method, _, _)| method.as_str())
+            .collect();
+        f.debug_struct("RouteBuilder")
+            .field("path", &self.path)
+            .field("methods", &methods)
+            .finish_non_exhaustive()
     }
 }
 
-pub type MiddleWareFn = fn(HttpRequest) -> HttpRequest;
-pub type Handler = Box<dyn HandlerFn + Send + Sync>;
 /// The struct to initialise your http server and finally listen on some port
 ///
 /// # Example usage:
@@ -34,27 +286,197 @@ pub type Handler = Box<dyn HandlerFn + Send + Sync>;
 /// use torus_http::server::HttpServer;
 /// HttpServer::new().listen(("127.0.0.1", 8080)); // no_op http server listening on port 8080
 /// ```
-#[derive(Default)]
+#[allow(clippy::struct_excessive_bools)] // unrelated switches, not states in disguise
 pub struct HttpServer {
-    handlers: HashMap<(String, HttpMethod), Handler>,
-    middle_ware: Option<MiddleWareFn>,
-    state: Option<Box<dyn Send + Sync>>,
+    /// Keyed by path first so a lookup only needs to borrow from the request
+    handlers: HashMap<String, HashMap<HttpMethod, Route>>,
+    /// Routes with placeholders, tried in registration order after `handlers`
+    patterns: Vec<(CompiledPattern, HashMap<HttpMethod, Route>)>,
+    /// `(name, priority, middleware)`, kept sorted by priority
+    middle_ware: Vec<(String, i32, StoredMiddleware)>,
+    state: State,
+    logger: Option<Arc<dyn Logger>>,
+    proxy_protocol: bool,
+    proxy_protocol_fallback: bool,
+    https_redirect: Option<u16>,
+    response_middleware: Vec<ResponseMiddlewareFn>,
+    around_middleware: Vec<Box<dyn Middleware>>,
+    max_url_length: usize,
+    handler_timeout: Option<Duration>,
+    cycle_timeout: Option<Duration>,
+    /// Started by [`BoundServer::listen`] when there is a `cycle_timeout`
+    watchdog: Option<mpsc::Sender<Watched>>,
+    default_content_type: Option<String>,
+    middleware_error: Option<MiddlewareErrorFn>,
+    error_mappers: Vec<ErrorMapperFn>,
+    error_format: ErrorFormat,
+    date_header: bool,
+    /// Added to every response that doesn't have them yet
+    default_headers: Vec<(String, String)>,
+    metrics: Option<Metrics>,
+    /// What [`HttpServer::metrics_endpoint`] renders, swapped by a later
+    /// [`HttpServer::with_metrics`]
+    served_metrics: Option<Arc<Mutex<Metrics>>>,
+    /// Found while registering routes, see [`HttpServer::validate`]
+    route_errors: Vec<RouteValidationError>,
+    /// The state types state middleware were registered for, checked by
+    /// [`HttpServer::validate`]
+    state_types: Vec<(TypeId, &'static str)>,
+    skip_validation: bool,
+    /// `None` serves every connection on a thread of its own, see [`HttpServer::workers`]
+    workers: Option<usize>,
+    /// Taken and called once when serving starts, the mutex only keeps the server `Sync`
+    on_start: Mutex<Vec<StartFn>>,
+    #[cfg(feature = "tracing")]
+    slow_request_threshold: Option<Duration>,
+}
+
+/// How the responses torus makes up itself, like the `404` for unknown routes, are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// A short message as `text/plain`
+    #[default]
+    Text,
+    /// `{"status": 404, "error": "not found"}` as `application/json`
+    Json,
+}
+
+impl ErrorFormat {
+    /// The format of the server `req` came in on, for middleware answering in its place
+    #[must_use]
+    pub fn of(req: &HttpRequest) -> Self {
+        req.extensions.get().copied().unwrap_or_default()
+    }
+
+    /// A response with `message` in this format
+    #[must_use]
+    pub fn response(self, status: HttpStatus, message: &str) -> HttpResponse {
+        match self {
+            ErrorFormat::Text => HttpResponse::new_body(message.into(), status)
+                .insert_header("Content-Type", "text/plain; charset=utf-8"),
+            ErrorFormat::Json => {
+                let message = message.replace('\\', "\\\\").replace('"', "\\\"");
+                let code = status.code();
+                HttpResponse::new_body(
+                    format!("{{\"status\": {code}, \"error\": \"{message}\"}}"),
+                    status,
+                )
+                .insert_header("Content-Type", "application/json")
+            }
+        }
+    }
+}
+
+impl Default for HttpServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Bulk registration, e.g. for modules that define their routes as a
+/// `fn routes() -> Vec<(String, HttpMethod, Handler)>`
+///
+/// The routes show up as `<shared>` in [`HttpServer::trace_routes`], use [`RouteDef`]s to give
+/// them names
+///
+/// # Example usage:
+///
+/// ```rust
+/// use torus_http::{prelude::*, testing::TestClient};
+///
+/// mod numbers {
+///     use std::sync::Arc;
+///     use torus_http::{prelude::*, server::Handler};
+///
+///     pub fn routes() -> Vec<(String, HttpMethod, Handler)> {
+///         (0..10)
+///             .map(|n| {
+///                 let handler: Handler = Arc::new(move |_| format!("number {n}"));
+///                 (format!("/numbers/{n}"), HttpMethod::Get, handler)
+///             })
+///             .collect()
+///     }
+/// }
+///
+/// let mut server = HttpServer::new();
+/// server.extend(numbers::routes());
+/// let client = TestClient::new(server);
+/// for n in 0..10 {
+///     let res = client.get(format!("/numbers/{n}")).send();
+///     assert_eq!(res.body_str(), Some(format!("number {n}").as_str()));
+/// }
+/// assert_eq!(client.get("/numbers/10").send().status, HttpStatus::NOT_FOUND);
+/// ```
+impl Extend<(String, HttpMethod, Handler)> for HttpServer {
+    fn extend<I: IntoIterator<Item = (String, HttpMethod, Handler)>>(&mut self, routes: I) {
+        let server = std::mem::take(self);
+        *self = routes
+            .into_iter()
+            .fold(server, |server, (path, method, handler)| {
+                server.insert_route(path, method, handler, "<shared>", false, Vec::new())
+            });
+    }
+}
+
+/// Like [`HttpServer::register`], for when the server is behind a `&mut`
+impl Extend<RouteDef> for HttpServer {
+    fn extend<I: IntoIterator<Item = RouteDef>>(&mut self, routes: I) {
+        let server = std::mem::take(self);
+        *self = server.register(routes);
+    }
 }
 
+// connections are served by threads sharing an `Arc<HttpServer>`, so anything added to the
+// server has to be shareable across threads
+#[cfg(test)]
+static_assertions::assert_impl_all!(HttpServer: Send, Sync);
+#[cfg(test)]
+static_assertions::assert_impl_all!(BoundServer: Send, Sync);
+
 impl HttpServer {
     /// Initialise an http server on an address
     #[must_use]
     pub fn new() -> Self {
         Self {
             handlers: HashMap::new(),
-            middle_ware: None,
+            patterns: Vec::new(),
+            middle_ware: Vec::new(),
             state: None,
+            logger: None,
+            proxy_protocol: false,
+            proxy_protocol_fallback: false,
+            https_redirect: None,
+            response_middleware: Vec::new(),
+            around_middleware: Vec::new(),
+            max_url_length: DEFAULT_MAX_URL_LENGTH,
+            handler_timeout: None,
+            cycle_timeout: None,
+            watchdog: None,
+            default_content_type: None,
+            middleware_error: None,
+            error_mappers: Vec::new(),
+            error_format: ErrorFormat::Text,
+            date_header: true,
+            default_headers: vec![(
+                "Server".into(),
+                concat!("torus-http/", env!("CARGO_PKG_VERSION")).into(),
+            )],
+            metrics: None,
+            served_metrics: None,
+            route_errors: Vec::new(),
+            state_types: Vec::new(),
+            skip_validation: false,
+            workers: None,
+            on_start: Mutex::default(),
+            #[cfg(feature = "tracing")]
+            slow_request_threshold: None,
         }
     }
 
-    /// Initialises middleware or replaces if there was already some added
+    /// Adds an unnamed middleware with the default priority of `0`
     ///
-    /// subject to change
+    /// Middleware run one after the other, each getting the request the previous one returned.
+    /// Middleware with the same priority run in the order they were registered in.
     ///
     /// # Example usage:
     ///
@@ -65,177 +487,2305 @@ impl HttpServer {
     ///     req
     /// });
     /// ```
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .add_middleware(|mut req| {
+    ///         req.headers.append("X-Trail", "first");
+    ///         req
+    ///     })
+    ///     .add_middleware(|mut req| {
+    ///         req.headers.append("X-Trail", "second");
+    ///         req
+    ///     })
+    ///     .get("/", |req: HttpRequest| req.headers.get_all("X-Trail").join(","));
+    ///
+    /// let request = HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+    /// assert_eq!(server.handle(request).body_str(), Some("first,second"));
+    /// ```
+    ///
+    /// Middleware can be closures capturing whatever they need
+    ///
+    /// ```rust
+    /// use std::{str::FromStr, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
+    /// use torus_http::prelude::*;
+    ///
+    /// let seen = Arc::new(AtomicUsize::new(0));
+    /// let counter = seen.clone();
+    /// let server = HttpServer::new()
+    ///     .add_middleware(move |req| {
+    ///         counter.fetch_add(1, Ordering::SeqCst);
+    ///         req
+    ///     })
+    ///     .get("/", |_| "hi");
+    ///
+    /// for _ in 0..3 {
+    ///     _ = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// }
+    /// assert_eq!(seen.load(Ordering::SeqCst), 3);
+    /// ```
     #[must_use]
-    pub fn add_middleware(mut self, f: fn(req: HttpRequest) -> HttpRequest) -> Self {
-        self.middle_ware.replace(f);
-        self
+    pub fn add_middleware<R: Into<MiddlewareResult>>(
+        self,
+        f: impl Fn(HttpRequest) -> R + Send + Sync + 'static,
+    ) -> Self {
+        self.add_middleware_with_priority("", 0, f)
     }
 
-    /// Register a custom route
+    /// Adds a named middleware, lower priorities run first
     ///
     /// # Example usage:
     ///
     /// ```rust
-    /// use torus_http::server::HttpServer;
-    /// use torus_http::method::HttpMethod;
-    /// HttpServer::new().route("/some_path", HttpMethod::other("custom"), |_| {"hi"});
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// fn visit(mut req: HttpRequest, name: &str) -> HttpRequest {
+    ///     req.headers.append("X-Visited", name);
+    ///     req
+    /// }
+    ///
+    /// let server = HttpServer::new()
+    ///     .add_middleware_with_priority("rate-limit", 0, |req| visit(req, "rate-limit "))
+    ///     .add_middleware_with_priority("auth", -100, |req| visit(req, "auth "))
+    ///     .get("/", |req: HttpRequest| req.headers.get_all("X-Visited").concat());
+    ///
+    /// let request = HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+    /// assert_eq!(server.handle(request).body_str(), Some("auth rate-limit "));
     /// ```
     #[must_use]
-    pub fn route<F: HandlerFn + 'static>(
-        mut self,
-        path: impl Into<String>,
-        method: HttpMethod,
-        f: F,
+    pub fn add_middleware_with_priority<R: Into<MiddlewareResult>>(
+        self,
+        name: impl Into<String>,
+        priority: i32,
+        f: impl Fn(HttpRequest) -> R + Send + Sync + 'static,
     ) -> Self {
-        self.handlers.insert((path.into(), method), Box::new(f));
-        self
+        self.insert_middleware(name.into(), priority, Box::new(move |_, req| f(req).into()))
     }
 
-    /// Register a **GET** method
+    /// Adds a middleware that also gets the state set with [`HttpServer::set_state`], it runs
+    /// with the default priority of `0` alongside the stateless ones
+    ///
+    /// [`HttpServer::validate`] reports it when no state of type `S` was set, so the server
+    /// doesn't start. Called anyway, e.g. through [`HttpServer::handle`], the middleware fails and
+    /// the request gets a `500 Internal Server Error`
     ///
     /// # Example usage:
     ///
     /// ```rust
-    /// use torus_http::server::HttpServer;
-    /// use torus_http::request::HttpRequest;
-    /// use torus_http::response::Response;
-    /// fn home_method(_req: HttpRequest) -> impl Response {
-    ///     "hello, world"
-    /// }
-    /// HttpServer::new().get("/home", home_method);
-    /// ```
+    /// use std::{str::FromStr, sync::Mutex};
+    /// use torus_http::prelude::*;
     ///
-    /// ## Note:
+    /// let server = HttpServer::new()
+    ///     .set_state(Mutex::new(0_u64))
+    ///     .add_state_middleware(|visits: std::sync::Arc<Mutex<u64>>, mut req| {
+    ///         let mut visits = visits.lock().unwrap();
+    ///         *visits += 1;
+    ///         req.headers.insert("X-Visits", visits.to_string());
+    ///         req
+    ///     })
+    ///     .get("/", |req: HttpRequest| req.headers["X-Visits"].clone());
+    ///
+    /// assert!(server.validate().is_ok());
+    /// let request = || HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+    /// assert_eq!(server.handle(request()).body_str(), Some("1"));
+    /// assert_eq!(server.handle(request()).body_str(), Some("2"));
     ///
-    /// I drop the body for get requests as that is apparently standard
+    /// // state of another type
+    /// let server = HttpServer::new()
+    ///     .set_state(0_u32)
+    ///     .add_state_middleware(|_: std::sync::Arc<Mutex<u64>>, req| req)
+    ///     .get("/", |_| "unreachable");
+    /// assert!(server.validate().is_err());
+    /// assert_eq!(server.handle(request()).status, HttpStatus::INTERNAL_SERVER_ERROR);
+    /// ```
     #[must_use]
-    pub fn get<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
-        self.route(path, HttpMethod::Get, f)
+    pub fn add_state_middleware<S: Send + Sync + 'static, R: Into<MiddlewareResult>>(
+        mut self,
+        f: impl Fn(Arc<S>, HttpRequest) -> R + Send + Sync + 'static,
+    ) -> Self {
+        let name = std::any::type_name::<S>();
+        self.state_types.push((TypeId::of::<S>(), name));
+        let shim = move |state: &State, req| match state
+            .clone()
+            .and_then(|state| state.downcast::<S>().ok())
+        {
+            Some(state) => f(state, req).into(),
+            None => MiddlewareResult::Fail(RouteValidationError::MissingState(name).into()),
+        };
+        self.insert_middleware(String::new(), 0, Box::new(shim))
     }
 
-    /// Register a **POST** method
+    fn insert_middleware(mut self, name: String, priority: i32, f: StoredMiddleware) -> Self {
+        let index = self
+            .middle_ware
+            .partition_point(|(_, other, _)| *other <= priority);
+        self.middle_ware.insert(index, (name, priority, f));
+        self
+    }
+
+    /// Adds an unnamed middleware at `index` of the request middleware list, so it runs after
+    /// the `index` middleware registered before it
+    ///
+    /// It takes over the priority of the middleware it is put in front of (or of the last one
+    /// when appending), so later registrations keep sorting around it as usual
+    ///
+    /// # Panics
+    ///
+    /// If `index` is larger than the number of request middleware
     ///
     /// # Example usage:
     ///
     /// ```rust
-    /// use torus_http::server::HttpServer;
-    /// use torus_http::request::HttpRequest;
-    /// use torus_http::response::Response;
-    /// fn my_post(_req: HttpRequest) -> impl Response {
-    ///     // ... Super complex DB activity
-    ///     "I'll keep you posted"
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// fn visit(mut req: HttpRequest, name: &str) -> HttpRequest {
+    ///     req.headers.append("X-Visited", name);
+    ///     req
     /// }
-    /// HttpServer::new().post("/drop/prod/db", my_post);
+    ///
+    /// let server = HttpServer::new()
+    ///     .add_middleware(|req| visit(req, "a "))
+    ///     .add_middleware(|req| visit(req, "c "))
+    ///     .insert_middleware_at(1, |req| visit(req, "b "))
+    ///     .insert_middleware_at(0, |req| visit(req, "first "))
+    ///     .get("/", |req: HttpRequest| req.headers.get_all("X-Visited").concat());
+    ///
+    /// let request = HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+    /// assert_eq!(server.handle(request).body_str(), Some("first a b c "));
     /// ```
     #[must_use]
-    pub fn post<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
-        self.route(path, HttpMethod::Post, f)
+    pub fn insert_middleware_at<R: Into<MiddlewareResult>>(
+        mut self,
+        index: usize,
+        f: impl Fn(HttpRequest) -> R + Send + Sync + 'static,
+    ) -> Self {
+        let len = self.middle_ware.len();
+        assert!(
+            index <= len,
+            "middleware index {index} out of bounds, there are {len}"
+        );
+        let priority = self
+            .middle_ware
+            .get(index)
+            .or(self.middle_ware.last())
+            .map_or(0, |(_, priority, _)| *priority);
+        self.middle_ware.insert(
+            index,
+            (
+                String::new(),
+                priority,
+                Box::new(move |_, req| f(req).into()),
+            ),
+        );
+        self
     }
 
-    /// Register a **DELETE** method
+    /// Removes every middleware registered under `name`, returns whether there was any
+    pub fn remove_middleware(&mut self, name: &str) -> bool {
+        let len = self.middle_ware.len();
+        self.middle_ware.retain(|(other, _, _)| other != name);
+        self.middle_ware.len() != len
+    }
+
+    /// Register a custom route
+    ///
+    /// `path` may contain placeholders like `/users/:id`, see the [`pattern`](crate::pattern)
+    /// module. This goes for every other way of registering a route as well
+    ///
+    /// Paths that aren't valid patterns, e.g. `/files/**/edit`, are left out and keep the server
+    /// from starting, see [`HttpServer::validate`]
+    ///
+    /// A request goes to the route with exactly its path first, then to the first pattern
+    /// matching it in registration order. A path that is known but not for the request's method
+    /// is answered with `405 Method Not Allowed` and an `Allow` header
     ///
     /// # Example usage:
     ///
     /// ```rust
     /// use torus_http::server::HttpServer;
-    /// use torus_http::request::HttpRequest;
-    /// use torus_http::response::Response;
-    /// fn my_delete(_req: HttpRequest) -> impl Response {
-    ///     // delete browser history ...
-    ///     "Yeah I don't use the internet bro trust me..."
-    /// }
-    /// HttpServer::new().delete("/homework", my_delete);
+    /// use torus_http::method::HttpMethod;
+    /// HttpServer::new().route("/some_path", HttpMethod::other("custom"), |_| {"hi"});
+    /// ```
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .get("/users/:id", |req: HttpRequest| format!("user {}", req.param("id").unwrap()))
+    ///     .get("/users/:name/posts", |_| "posts")
+    ///     .get("/users/me", |_| "me")
+    ///     .get("/:section/me", |_| "section")
+    ///     .delete("/users/:id", |_| "deleted");
+    /// let get = |path: &str| {
+    ///     let request = HttpRequest::from_str(&format!("GET {path} HTTP/1.1\r\n\r\n")).unwrap();
+    ///     server.handle(request)
+    /// };
+    ///
+    /// // exact paths win over patterns, even ones registered earlier
+    /// assert_eq!(get("/users/me").body_str(), Some("me"));
+    /// // params match a single segment, other segments have to match as written
+    /// assert_eq!(get("/users/7").body_str(), Some("user 7"));
+    /// assert_eq!(get("/users/7/posts").body_str(), Some("posts"));
+    /// assert_eq!(get("/users/7/likes").status, HttpStatus::NOT_FOUND);
+    /// // between patterns the first registered one wins
+    /// assert_eq!(get("/admin/me").body_str(), Some("section"));
+    ///
+    /// // the path is there, just not for this method
+    /// let request = HttpRequest::from_str("POST /users/7 HTTP/1.1\r\n\r\n").unwrap();
+    /// let res = server.handle(request);
+    /// assert_eq!(res.status, HttpStatus::METHOD_NOT_ALLOWED);
+    /// assert_eq!(res.headers["Allow"], "DELETE, GET");
     /// ```
     #[must_use]
-    pub fn delete<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
-        self.route(path, HttpMethod::Delete, f)
+    pub fn route<F: HandlerFn + 'static>(
+        self,
+        path: impl Into<String>,
+        method: HttpMethod,
+        f: F,
+    ) -> Self {
+        self.insert_route(
+            path.into(),
+            method,
+            Arc::new(f),
+            std::any::type_name::<F>(),
+            false,
+            Vec::new(),
+        )
     }
 
-    /// Register an **UPDATE** method
+    /// Register a route with middleware that only applies to it, running after the global
+    /// middleware in the given order
+    ///
+    /// Functions and closures that don't capture anything can be mixed in one list, closures
+    /// that do are easier added one by one with [`RouteBuilder::with_middleware`]
     ///
     /// # Example usage:
     ///
     /// ```rust
-    /// use torus_http::server::HttpServer;
-    /// use torus_http::request::HttpRequest;
-    /// use torus_http::response::Response;
-    /// fn im_getting_tired_of_writing_these(_req: HttpRequest) -> impl Response {
-    ///     // just read the others like .get() and .post() bro
-    ///     "Yeah I don't use the internet bro trust me..."
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// fn small_bodies_only(req: HttpRequest) -> MiddlewareResult {
+    ///     if req.body.as_ref().is_some_and(|body| body.len() > 8) {
+    ///         return HttpResponse::new_body("too large".into(), HttpStatus::BAD_REQUEST).into();
+    ///     }
+    ///     req.into()
     /// }
-    /// HttpServer::new().delete("/homework", im_getting_tired_of_writing_these);
+    ///
+    /// let server = HttpServer::new()
+    ///     .route_with_middleware("/upload", HttpMethod::Post, vec![small_bodies_only], |_| "stored")
+    ///     .post("/echo", |req: HttpRequest| req.body_str().unwrap_or_default().to_owned());
+    ///
+    /// let upload = HttpRequest::from_str("POST /upload HTTP/1.1\r\n\r\nway too much data").unwrap();
+    /// assert_eq!(server.handle(upload).status, HttpStatus::BAD_REQUEST);
+    ///
+    /// // other routes are left alone
+    /// let echo = HttpRequest::from_str("POST /echo HTTP/1.1\r\n\r\nway too much data").unwrap();
+    /// assert_eq!(server.handle(echo).body_str(), Some("way too much data"));
+    /// ```
+    ///
+    /// Closures work as well, including ones capturing their configuration:
+    ///
+    /// ```rust
+    /// use torus_http::{prelude::*, testing::TestClient};
+    ///
+    /// let token = String::from("let-me-in");
+    /// let client = TestClient::new(
+    ///     HttpServer::new()
+    ///         .delete_with_middleware(
+    ///             "/posts/:id",
+    ///             [move |req: HttpRequest| {
+    ///                 if req.header("Authorization") == Some(token.as_str()) {
+    ///                     Ok(req)
+    ///                 } else {
+    ///                     Err(HttpResponse::new().set_status(HttpStatus::UNAUTHORIZED))
+    ///                 }
+    ///             }],
+    ///             |_| "deleted",
+    ///         )
+    ///         .put_with_middleware(
+    ///             "/posts/:id",
+    ///             vec![|mut req: HttpRequest| {
+    ///                 req.headers.insert("X-Checked", "yes");
+    ///                 req
+    ///             }],
+    ///             |req: HttpRequest| req.headers["X-Checked"].clone(),
+    ///         ),
+    /// );
+    ///
+    /// assert_eq!(client.delete("/posts/1").send().status, HttpStatus::UNAUTHORIZED);
+    /// let res = client.delete("/posts/1").header("Authorization", "let-me-in").send();
+    /// assert_eq!(res.body_str(), Some("deleted"));
+    /// assert_eq!(client.put("/posts/1").send().body_str(), Some("yes"));
     /// ```
     #[must_use]
-    pub fn update<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
-        self.route(path, HttpMethod::Update, f)
+    pub fn route_with_middleware<F, M, R>(
+        self,
+        path: impl Into<String>,
+        method: HttpMethod,
+        middleware: impl IntoIterator<Item = M>,
+        f: F,
+    ) -> Self
+    where
+        F: HandlerFn + 'static,
+        M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+        R: Into<MiddlewareResult>,
+    {
+        let name = std::any::type_name::<F>();
+        let middleware = route_middleware(middleware);
+        self.insert_route(path.into(), method, Arc::new(f), name, false, middleware)
     }
 
-    /// Register a **PUT** method
+    /// Start registering several methods on `path` at once, finished by
+    /// [`RouteBuilder::build`]
     ///
     /// # Example usage:
     ///
     /// ```rust
-    /// use torus_http::request::HttpRequest;
-    /// use torus_http::server::HttpServer;
-    /// use torus_http::response::Response;
-    /// fn im_getting_tired_of_writing_these(_req: HttpRequest) -> impl Response {
-    ///     "WHY THE HECK DID I ADD SO MANY OF THESE THINGS"
+    /// use torus_http::{prelude::*, testing::TestClient};
+    ///
+    /// fn logged_in(req: HttpRequest) -> MiddlewareResult {
+    ///     match req.header("Authorization") {
+    ///         Some(_) => req.into(),
+    ///         None => HttpResponse::new().set_status(HttpStatus::UNAUTHORIZED).into(),
+    ///     }
     /// }
-    /// HttpServer::new().delete("/us-east1", im_getting_tired_of_writing_these);
+    ///
+    /// let client = TestClient::new(
+    ///     HttpServer::new()
+    ///         .at("/users")
+    ///         .get(|_| "alice, bob")
+    ///         .post(|req: HttpRequest| format!("created {}", req.body_str().unwrap_or_default()))
+    ///         .with_middleware(logged_in)
+    ///         .build()
+    ///         .get("/", |_| "public"),
+    /// );
+    ///
+    /// let res = client.get("/users").header("Authorization", "yes").send();
+    /// assert_eq!(res.body_str(), Some("alice, bob"));
+    /// let res = client.post("/users").header("Authorization", "yes").body("carol").send();
+    /// assert_eq!(res.body_str(), Some("created carol"));
+    ///
+    /// assert_eq!(client.post("/users").send().status, HttpStatus::UNAUTHORIZED);
+    /// assert_eq!(client.get("/").send().body_str(), Some("public"));
     /// ```
-    #[must_use]
-    pub fn put<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
-        self.route(path, HttpMethod::Put, f)
-    }
-
-    /// like `.post()` but patch
-    #[must_use]
-    pub fn patch<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
-        self.route(path, HttpMethod::Patch, f)
+    pub fn at(self, path: impl Into<String>) -> RouteBuilder {
+        RouteBuilder {
+            server: self,
+            path: path.into(),
+            routes: Vec::new(),
+            middleware: Vec::new(),
+        }
     }
 
-    /// I just took this one from hoppscotch I never heard of the head method before
-    /// read `.post()` and stuff for documentation
+    /// Serve the files in a directory under `prefix`, for `GET` and `HEAD` requests, see the
+    /// [`files`](crate::files) module
     #[must_use]
-    pub fn head<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
-        self.route(path, HttpMethod::Head, f)
+    pub fn serve_dir(self, prefix: &str, dir: ServeDir) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        let dir = dir.prefix(prefix);
+        let pattern = format!("{prefix}/**");
+        self.insert_route(
+            pattern.clone(),
+            HttpMethod::Get,
+            Arc::new(dir.clone()),
+            "serve_dir",
+            true,
+            Vec::new(),
+        )
+        .insert_route(
+            pattern,
+            HttpMethod::Head,
+            Arc::new(dir),
+            "serve_dir",
+            true,
+            Vec::new(),
+        )
     }
 
-    /// Shoutout to chatgpt for this one:
-    /// Register an **OPTIONS** method
+    /// Serve `bytes`, usually from `include_bytes!`, at `path` for `GET` and `HEAD` requests,
+    /// see [`EmbeddedFile`]
     ///
-    /// This attaches a handler to the given path that responds to http `OPTIONS`
-    /// requests. Typically used for capability discovery, CORS preflight checks,
-    /// or politely telling browsers what they are allowed to do.
+    /// ```rust
+    /// use torus_http::{prelude::*, testing::TestClient};
     ///
-    /// # Example usage:
+    /// let client = TestClient::new(HttpServer::new().embedded(
+    ///     "/LICENSE.txt",
+    ///     include_bytes!("../LICENSE.txt"),
+    ///     "text/plain; charset=utf-8",
+    /// ));
     ///
-    /// ```rust
-    /// use torus_http::server::HttpServer;
-    /// use torus_http::request::HttpRequest;
-    /// use torus_http::response::Response;
-    /// fn options_method(_req: HttpRequest) -> impl Response {
-    ///     ""
-    /// }
+    /// let res = client.get("/LICENSE.txt").send();
+    /// assert_eq!(res.body.as_deref(), Some(&include_bytes!("../LICENSE.txt")[..]));
     ///
-    /// HttpServer::new().options("/home", options_method);
+    /// let res = client.head("/LICENSE.txt").send();
+    /// assert_eq!(res.body, None);
+    /// assert_eq!(
+    ///     res.header("Content-Length"),
+    ///     Some(include_bytes!("../LICENSE.txt").len().to_string().as_str())
+    /// );
     /// ```
-    ///
+    #[must_use]
+    pub fn embedded(
+        self,
+        path: impl Into<String>,
+        bytes: &'static [u8],
+        content_type: &'static str,
+    ) -> Self {
+        let path = path.into();
+        let file: Handler = Arc::new(EmbeddedFile::new(bytes, content_type));
+        self.insert_route(
+            path.clone(),
+            HttpMethod::Get,
+            file.clone(),
+            "embedded",
+            true,
+            Vec::new(),
+        )
+        .insert_route(path, HttpMethod::Head, file, "embedded", true, Vec::new())
+    }
+
+    /// Register a **GET** method with middleware that only applies to it, see
+    /// [`HttpServer::route_with_middleware`]
+    #[must_use]
+    pub fn get_with_middleware<F, M, R>(
+        self,
+        path: impl Into<String>,
+        middleware: impl IntoIterator<Item = M>,
+        f: F,
+    ) -> Self
+    where
+        F: HandlerFn + 'static,
+        M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+        R: Into<MiddlewareResult>,
+    {
+        self.route_with_middleware(path, HttpMethod::Get, middleware, f)
+    }
+
+    /// Register a **POST** method with middleware that only applies to it, see
+    /// [`HttpServer::route_with_middleware`]
+    #[must_use]
+    pub fn post_with_middleware<F, M, R>(
+        self,
+        path: impl Into<String>,
+        middleware: impl IntoIterator<Item = M>,
+        f: F,
+    ) -> Self
+    where
+        F: HandlerFn + 'static,
+        M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+        R: Into<MiddlewareResult>,
+    {
+        self.route_with_middleware(path, HttpMethod::Post, middleware, f)
+    }
+
+    /// Register a **PUT** method with middleware that only applies to it, see
+    /// [`HttpServer::route_with_middleware`]
+    #[must_use]
+    pub fn put_with_middleware<F, M, R>(
+        self,
+        path: impl Into<String>,
+        middleware: impl IntoIterator<Item = M>,
+        f: F,
+    ) -> Self
+    where
+        F: HandlerFn + 'static,
+        M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+        R: Into<MiddlewareResult>,
+    {
+        self.route_with_middleware(path, HttpMethod::Put, middleware, f)
+    }
+
+    /// Register a **PATCH** method with middleware that only applies to it, see
+    /// [`HttpServer::route_with_middleware`]
+    #[must_use]
+    pub fn patch_with_middleware<F, M, R>(
+        self,
+        path: impl Into<String>,
+        middleware: impl IntoIterator<Item = M>,
+        f: F,
+    ) -> Self
+    where
+        F: HandlerFn + 'static,
+        M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+        R: Into<MiddlewareResult>,
+    {
+        self.route_with_middleware(path, HttpMethod::Patch, middleware, f)
+    }
+
+    /// Register a **DELETE** method with middleware that only applies to it, see
+    /// [`HttpServer::route_with_middleware`]
+    #[must_use]
+    pub fn delete_with_middleware<F, M, R>(
+        self,
+        path: impl Into<String>,
+        middleware: impl IntoIterator<Item = M>,
+        f: F,
+    ) -> Self
+    where
+        F: HandlerFn + 'static,
+        M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+        R: Into<MiddlewareResult>,
+    {
+        self.route_with_middleware(path, HttpMethod::Delete, middleware, f)
+    }
+
+    /// Register a **HEAD** method with middleware that only applies to it, see
+    /// [`HttpServer::route_with_middleware`]
+    #[must_use]
+    pub fn head_with_middleware<F, M, R>(
+        self,
+        path: impl Into<String>,
+        middleware: impl IntoIterator<Item = M>,
+        f: F,
+    ) -> Self
+    where
+        F: HandlerFn + 'static,
+        M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+        R: Into<MiddlewareResult>,
+    {
+        self.route_with_middleware(path, HttpMethod::Head, middleware, f)
+    }
+
+    /// Register a **OPTIONS** method with middleware that only applies to it, see
+    /// [`HttpServer::route_with_middleware`]
+    #[must_use]
+    pub fn options_with_middleware<F, M, R>(
+        self,
+        path: impl Into<String>,
+        middleware: impl IntoIterator<Item = M>,
+        f: F,
+    ) -> Self
+    where
+        F: HandlerFn + 'static,
+        M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+        R: Into<MiddlewareResult>,
+    {
+        self.route_with_middleware(path, HttpMethod::Options, middleware, f)
+    }
+
+    /// Register a custom route under a name that shows up in [`HttpServer::trace_routes`]
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::server::HttpServer;
+    /// use torus_http::method::HttpMethod;
+    /// HttpServer::new().route_named("/", HttpMethod::Get, "index", |_| "hi");
+    /// ```
+    #[must_use]
+    pub fn route_named<F: HandlerFn + 'static>(
+        self,
+        path: impl Into<String>,
+        method: HttpMethod,
+        name: &'static str,
+        f: F,
+    ) -> Self {
+        self.insert_route(path.into(), method, Arc::new(f), name, true, Vec::new())
+    }
+
+    /// Register a handler you already hold an `Arc` to, so one handler can serve several routes
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{str::FromStr, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
+    /// use torus_http::prelude::*;
+    /// use torus_http::server::Handler;
+    ///
+    /// let hits = Arc::new(AtomicUsize::new(0));
+    /// let counter = hits.clone();
+    /// let handler: Handler = Arc::new(move |_| counter.fetch_add(1, Ordering::SeqCst).to_string());
+    ///
+    /// let server = HttpServer::new()
+    ///     .route_shared("/a", HttpMethod::Get, handler.clone())
+    ///     .route_shared("/b", HttpMethod::Get, handler.clone())
+    ///     .route_shared("/c", HttpMethod::Post, handler.clone());
+    ///
+    /// for raw in ["GET /a HTTP/1.1\r\n\r\n", "GET /b HTTP/1.1\r\n\r\n", "POST /c HTTP/1.1\r\n\r\n"] {
+    ///     _ = server.handle(HttpRequest::from_str(raw).unwrap());
+    /// }
+    /// assert_eq!(hits.load(Ordering::SeqCst), 3);
+    /// assert_eq!(Arc::strong_count(&handler), 4);
+    /// ```
+    #[must_use]
+    pub fn route_shared(self, path: impl Into<String>, method: HttpMethod, f: Handler) -> Self {
+        self.insert_route(path.into(), method, f, "<shared>", false, Vec::new())
+    }
+
+    /// Register routes defined somewhere else, usually with the route attributes and `routes!`
+    /// from the `derive` feature
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::{prelude::*, server::RouteDef, testing::TestClient};
+    ///
+    /// fn users() -> Vec<RouteDef> {
+    ///     vec![
+    ///         RouteDef::new("/users", HttpMethod::Get, "list_users", |_| "all of them"),
+    ///         RouteDef::new("/users/:id", HttpMethod::Get, "show_user", |req: HttpRequest| {
+    ///             format!("user {}", req.param("id").unwrap())
+    ///         }),
+    ///     ]
+    /// }
+    ///
+    /// let client = TestClient::new(HttpServer::new().register(users()));
+    /// assert_eq!(client.get("/users").send().body_str(), Some("all of them"));
+    /// assert_eq!(client.get("/users/7").send().body_str(), Some("user 7"));
+    /// ```
+    #[must_use]
+    pub fn register(self, routes: impl IntoIterator<Item = RouteDef>) -> Self {
+        routes.into_iter().fold(self, |server, route| {
+            server.insert_route(
+                route.path,
+                route.method,
+                route.handler,
+                route.name,
+                true,
+                Vec::new(),
+            )
+        })
+    }
+
+    fn insert_route(
+        mut self,
+        path: String,
+        method: HttpMethod,
+        handler: Handler,
+        name: &'static str,
+        named: bool,
+        middleware: Vec<RouteMiddlewareFn>,
+    ) -> Self {
+        if path.is_empty() {
+            self.route_errors.push(RouteValidationError::EmptyPath);
+        } else if !path.starts_with('/') && path != "*" {
+            self.route_errors
+                .push(RouteValidationError::MissingLeadingSlash(path.clone()));
+        }
+        let pattern: CompiledPattern = match path.parse() {
+            Ok(pattern) => pattern,
+            Err(InvalidPattern { pattern, reason }) => {
+                self.route_errors
+                    .push(RouteValidationError::InvalidPattern(pattern, reason.into()));
+                return self;
+            }
+        };
+        let index = self.routes().map(|(_, methods)| methods.len()).sum();
+        let route = Route {
+            handler,
+            name,
+            named,
+            index,
+            middleware,
+        };
+        let methods = if pattern.is_literal() {
+            self.handlers.entry(path.clone()).or_default()
+        } else if let Some(i) = self.patterns.iter().position(|(p, _)| *p == pattern) {
+            &mut self.patterns[i].1
+        } else {
+            self.patterns.push((pattern, HashMap::new()));
+            &mut self.patterns.last_mut().expect("just pushed").1
+        };
+        // the later route wins, unless validation stops the server from starting at all
+        if methods.insert(method.clone(), route).is_some() {
+            self.route_errors
+                .push(RouteValidationError::DuplicateRoute(path, method));
+        }
+        self
+    }
+
+    /// Problems with the registered routes, [`HttpServer::bind`] refuses to start with any
+    ///
+    /// Routes are checked as they are registered: paths have to start with `/` (or be `*`), be
+    /// valid patterns, and every method can only be registered once per path. Routes with an
+    /// invalid pattern are left out, for the others the last registration wins. State middleware
+    /// need [`HttpServer::set_state`] to have been called with their type
+    ///
+    /// # Errors
+    ///
+    /// Every problem found, in registration order
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::{prelude::*, server::{RouteValidationError, ServerError}};
+    ///
+    /// let server = HttpServer::new()
+    ///     .get("/", |_| "home")
+    ///     .get("", |_| "empty")
+    ///     .get("users", |_| "no slash")
+    ///     .get("/files/**/edit", |_| "bad pattern")
+    ///     .post("/users/:id", |_| "first")
+    ///     .post("/users/:id", |_| "second");
+    ///
+    /// assert_eq!(
+    ///     server.validate(),
+    ///     Err(vec![
+    ///         RouteValidationError::EmptyPath,
+    ///         RouteValidationError::MissingLeadingSlash("users".into()),
+    ///         RouteValidationError::InvalidPattern(
+    ///             "/files/**/edit".into(),
+    ///             "`**` has to be the last segment".into(),
+    ///         ),
+    ///         RouteValidationError::DuplicateRoute("/users/:id".into(), HttpMethod::Post),
+    ///     ])
+    /// );
+    /// assert!(matches!(
+    ///     server.bind(("127.0.0.1", 0)),
+    ///     Err(ServerError::InvalidRoutes(errors)) if errors.len() == 4
+    /// ));
+    ///
+    /// let server = HttpServer::new().get("users", |_| "no slash").skip_validation();
+    /// assert!(server.bind(("127.0.0.1", 0)).is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<RouteValidationError>> {
+        let state_type = self.state.as_deref().map(Any::type_id);
+        let mut errors = self.route_errors.clone();
+        for (wanted, name) in &self.state_types {
+            let error = RouteValidationError::MissingState(name);
+            if state_type != Some(*wanted) && !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Start even if [`HttpServer::validate`] finds problems with the routes
+    #[must_use]
+    pub fn skip_validation(mut self) -> Self {
+        self.skip_validation = true;
+        self
+    }
+
+    pub(crate) fn skips_validation(&self) -> bool {
+        self.skip_validation
+    }
+
+    /// Every route by path, literal ones first
+    fn routes(&self) -> impl Iterator<Item = (&str, &HashMap<HttpMethod, Route>)> {
+        self.handlers
+            .iter()
+            .map(|(path, methods)| (path.as_str(), methods))
+            .chain(
+                self.patterns
+                    .iter()
+                    .map(|(pattern, methods)| (pattern.as_str(), methods)),
+            )
+    }
+
+    /// Print all middleware and routes to `stderr` for debugging, routes in registration order
+    ///
+    /// ```text
+    /// [mw]   auth (priority -100)
+    /// GET    /                      → my_app::hello_world
+    /// POST   /users                 → create_user
+    /// ```
+    pub fn trace_routes(&self) {
+        for (name, priority, _) in &self.middle_ware {
+            let name = if name.is_empty() { "<anonymous>" } else { name };
+            eprintln!("[mw]   {name} (priority {priority})");
+        }
+
+        let mut routes: Vec<_> = self
+            .routes()
+            .flat_map(|(path, methods)| {
+                methods
+                    .iter()
+                    .map(move |(method, route)| (route, method, path))
+            })
+            .collect();
+        routes.sort_by_key(|(route, _, _)| route.index);
+        for (route, method, path) in routes {
+            eprintln!("{:<6} {path:<22} → {}", method.as_str(), route.name);
+        }
+    }
+
+    /// The route table as [`HttpServer::print_routes`] prints it
+    ///
+    /// One line per route sorted by path and then method, with a column for the names if any
+    /// route was registered with one through [`HttpServer::route_named`] or
+    /// [`HttpServer::register`]
+    ///
+    /// ```rust
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .post("/users", |_| "created")
+    ///     .get("/users/:id", |_| "user")
+    ///     .get("/users", |_| "users");
+    /// assert_eq!(
+    ///     server.route_table(),
+    ///     "GET   /users\n\
+    ///      POST  /users\n\
+    ///      GET   /users/:id\n"
+    /// );
+    ///
+    /// let server = server
+    ///     .route_named("/", HttpMethod::Get, "index", |_| "hi")
+    ///     .route_named("/health", HttpMethod::other("PROBE"), "health_check", |_| "ok");
+    /// assert_eq!(
+    ///     server.route_table(),
+    ///     "GET    /           index\n\
+    ///      PROBE  /health     health_check\n\
+    ///      GET    /users      -\n\
+    ///      POST   /users      -\n\
+    ///      GET    /users/:id  -\n"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn route_table(&self) -> String {
+        let mut routes: Vec<_> = self
+            .routes()
+            .flat_map(|(path, methods)| {
+                methods
+                    .iter()
+                    .map(move |(method, route)| (path, method.as_str(), route))
+            })
+            .collect();
+        routes.sort_by_key(|&(path, method, _)| (path, method));
+
+        let method_width = routes.iter().map(|(_, method, _)| method.len()).max();
+        let path_width = routes.iter().map(|(path, _, _)| path.len()).max();
+        let named = routes.iter().any(|(_, _, route)| route.named);
+        let mut table = String::new();
+        for (path, method, route) in routes {
+            let method_width = method_width.unwrap_or_default();
+            _ = if named {
+                let name = if route.named { route.name } else { "-" };
+                let path_width = path_width.unwrap_or_default();
+                writeln!(
+                    table,
+                    "{method:<method_width$}  {path:<path_width$}  {name}"
+                )
+            } else {
+                writeln!(table, "{method:<method_width$}  {path}")
+            };
+        }
+        table
+    }
+
+    /// Print the [route table](HttpServer::route_table) to `stdout`, e.g. at startup to catch a
+    /// `/user` that should have been `/users`
+    ///
+    /// ```text
+    /// GET   /users
+    /// POST  /users
+    /// GET   /users/:id
+    /// ```
+    pub fn print_routes(&self) {
+        print!("{}", self.route_table());
+    }
+
+    /// [`HttpServer::listen`], printing the bound address and the
+    /// [route table](HttpServer::print_routes) first
+    ///
+    /// # Errors
+    ///
+    /// - see [`HttpServer::listen`]
+    pub fn listen_verbose(self, address: impl ToSocketAddrs) -> Result<(), ServerError> {
+        let bound = self.bind(address)?;
+        println!("listening on http://{}", bound.local_addr());
+        bound.server.print_routes();
+        bound.listen()
+    }
+
+    /// Register a **GET** method
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::server::HttpServer;
+    /// use torus_http::request::HttpRequest;
+    /// use torus_http::response::Response;
+    /// fn home_method(_req: HttpRequest) -> impl Response {
+    ///     "hello, world"
+    /// }
+    /// HttpServer::new().get("/home", home_method);
+    /// ```
+    ///
     /// ## Note:
     ///
-    /// `OPTIONS` requests are generally expected to return headers describing
-    /// allowed methods and behaviors. A response body is usually unnecessary and
-    /// often ignored, but nothing is stopping you from adding one if you enjoy
-    /// disappointing strict HTTP purists.
+    /// I drop the body for get requests as that is apparently standard, see
+    /// [`HttpMethod::typically_has_body`]
     #[must_use]
-    pub fn options<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
-        self.route(path, HttpMethod::Options, f)
+    pub fn get<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Get, f)
+    }
+
+    /// Register a **POST** method
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::server::HttpServer;
+    /// use torus_http::request::HttpRequest;
+    /// use torus_http::response::Response;
+    /// fn my_post(_req: HttpRequest) -> impl Response {
+    ///     // ... Super complex DB activity
+    ///     "I'll keep you posted"
+    /// }
+    /// HttpServer::new().post("/drop/prod/db", my_post);
+    /// ```
+    #[must_use]
+    pub fn post<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Post, f)
+    }
+
+    /// Register a **DELETE** method
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::server::HttpServer;
+    /// use torus_http::request::HttpRequest;
+    /// use torus_http::response::Response;
+    /// fn my_delete(_req: HttpRequest) -> impl Response {
+    ///     // delete browser history ...
+    ///     "Yeah I don't use the internet bro trust me..."
+    /// }
+    /// HttpServer::new().delete("/homework", my_delete);
+    /// ```
+    #[must_use]
+    pub fn delete<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Delete, f)
+    }
+
+    /// Register a custom **UPDATE** method
+    ///
+    /// `UPDATE` isn't an http method, so this is just a route for `HttpMethod::other("UPDATE")`
+    /// matching requests that send exactly `UPDATE`
+    ///
+    /// # Migrating:
+    ///
+    /// Use `.patch()` or `.put()` where you can, otherwise register the custom method yourself:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new().route("/homework", HttpMethod::other("UPDATE"), |_| "updated");
+    ///
+    /// let res = server.handle(HttpRequest::from_str("UPDATE /homework HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.body_str(), Some("updated"));
+    /// // custom methods are case sensitive
+    /// let res = server.handle(HttpRequest::from_str("update /homework HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::METHOD_NOT_ALLOWED);
+    /// ```
+    #[deprecated(
+        since = "0.2.10",
+        note = "UPDATE isn't an http method, use `.patch()`/`.put()` or `.route(path, HttpMethod::other(\"UPDATE\"), f)`; will be removed in the next breaking release"
+    )]
+    #[must_use]
+    pub fn update<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::other("UPDATE"), f)
+    }
+
+    /// Register a **PUT** method
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::request::HttpRequest;
+    /// use torus_http::server::HttpServer;
+    /// use torus_http::response::Response;
+    /// fn im_getting_tired_of_writing_these(_req: HttpRequest) -> impl Response {
+    ///     "WHY THE HECK DID I ADD SO MANY OF THESE THINGS"
+    /// }
+    /// HttpServer::new().delete("/us-east1", im_getting_tired_of_writing_these);
+    /// ```
+    #[must_use]
+    pub fn put<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Put, f)
+    }
+
+    /// like `.post()` but patch
+    #[must_use]
+    pub fn patch<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Patch, f)
+    }
+
+    /// I just took this one from hoppscotch I never heard of the head method before
+    /// read `.post()` and stuff for documentation
+    #[must_use]
+    pub fn head<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Head, f)
+    }
+
+    /// Shoutout to chatgpt for this one:
+    /// Register an **OPTIONS** method
+    ///
+    /// This attaches a handler to the given path that responds to http `OPTIONS`
+    /// requests. Typically used for capability discovery, CORS preflight checks,
+    /// or politely telling browsers what they are allowed to do.
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::server::HttpServer;
+    /// use torus_http::request::HttpRequest;
+    /// use torus_http::response::Response;
+    /// fn options_method(_req: HttpRequest) -> impl Response {
+    ///     ""
+    /// }
+    ///
+    /// HttpServer::new().options("/home", options_method);
+    /// ```
+    ///
+    /// ## Note:
+    ///
+    /// `OPTIONS` requests are generally expected to return headers describing
+    /// allowed methods and behaviors. A response body is usually unnecessary and
+    /// often ignored, but nothing is stopping you from adding one if you enjoy
+    /// disappointing strict HTTP purists.
+    #[must_use]
+    pub fn options<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Options, f)
+    }
+
+    /// Register a **TRACE** method
+    ///
+    /// Nothing answers `TRACE` unless you register it, echoing the request back is up to you
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new().trace("/", |req: HttpRequest| {
+    ///     format!("{} {} HTTP/1.1", req.method, req.path)
+    /// });
+    ///
+    /// let res = server.handle(HttpRequest::from_str("TRACE / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.body_str(), Some("TRACE / HTTP/1.1"));
+    /// ```
+    #[must_use]
+    pub fn trace<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Trace, f)
+    }
+
+    /// Register a **CONNECT** method, `path` being the authority the client wants a tunnel to,
+    /// e.g. `example.com:443`
+    ///
+    /// Without a matching route `CONNECT` gets `501 Not Implemented` rather than a 404
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new().connect("example.com:443", |_| "tunnel established");
+    ///
+    /// let req = HttpRequest::from_str("CONNECT example.com:443 HTTP/1.1\r\n\r\n").unwrap();
+    /// assert_eq!(req.method, HttpMethod::Connect);
+    /// assert_eq!(server.handle(req).status, HttpStatus::OK);
+    ///
+    /// let req = HttpRequest::from_str("CONNECT other.com:443 HTTP/1.1\r\n\r\n").unwrap();
+    /// assert_eq!(server.handle(req).status, HttpStatus::NOT_IMPLEMENTED);
+    /// ```
+    #[must_use]
+    pub fn connect<F: HandlerFn + 'static>(self, path: impl Into<String>, f: F) -> Self {
+        self.route(path, HttpMethod::Connect, f)
+    }
+
+    /// Share some application state with the state middleware, see
+    /// [`HttpServer::add_state_middleware`]
+    #[must_use]
+    pub fn set_state<T: Send + Sync + 'static>(mut self, state: T) -> Self {
+        self.state.replace(Arc::new(state));
+        self
+    }
+
+    /// Plug in a logger that gets told about every handled request and every failed connection
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::{logger::StderrLogger, server::HttpServer};
+    /// HttpServer::new().with_logger(StderrLogger);
+    /// ```
+    #[must_use]
+    pub fn with_logger(mut self, logger: impl Logger + 'static) -> Self {
+        self.logger.replace(Arc::new(logger));
+        self
+    }
+
+    /// Shorthand for `.with_logger(StderrLogger)`
+    #[must_use]
+    pub fn with_logging(self) -> Self {
+        self.with_logger(StderrLogger)
+    }
+
+    /// Count requests and serve the counts at `path` in the Prometheus text format, see the
+    /// [`metrics`] module
+    #[must_use]
+    pub fn metrics_endpoint(mut self, path: impl Into<String>) -> Self {
+        let metrics = self.metrics.get_or_insert_default().clone();
+        let served = Arc::clone(
+            self.served_metrics
+                .get_or_insert_with(|| Arc::new(Mutex::new(metrics))),
+        );
+        self.get(path, move |_| {
+            let rendered = served
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .render();
+            HttpResponse::new_body(rendered, HttpStatus::OK)
+                .insert_header("Content-Type", metrics::CONTENT_TYPE)
+        })
+    }
+
+    /// Count requests into `metrics`, to pick the histogram buckets or render them somewhere
+    /// else
+    ///
+    /// [`HttpServer::metrics_endpoint`] serves `metrics` too, whichever of the two comes first
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use torus_http::{metrics::Metrics, prelude::*, testing::TestClient};
+    ///
+    /// let metrics = Metrics::new().buckets([0.1, 1.0]);
+    /// let client = TestClient::new(
+    ///     HttpServer::new()
+    ///         .with_metrics(metrics.clone())
+    ///         .get("/", |_| "hi"),
+    /// );
+    /// client.get("/").send();
+    /// client.get("/nope").send();
+    ///
+    /// let rendered = metrics.render();
+    /// assert!(rendered.contains("torus_http_requests_total{method=\"GET\",route=\"/\",status=\"200\"} 1\n"));
+    /// assert!(rendered.contains("torus_http_requests_total{method=\"GET\",route=\"unmatched\",status=\"404\"} 1\n"));
+    /// assert!(rendered.contains("torus_http_request_duration_seconds_bucket{method=\"GET\",route=\"/\",le=\"1\"} 1\n"));
+    ///
+    /// // registered after the endpoint, still the instance it serves
+    /// let metrics = Metrics::new();
+    /// let client = TestClient::new(
+    ///     HttpServer::new()
+    ///         .metrics_endpoint("/metrics")
+    ///         .with_metrics(metrics.clone())
+    ///         .get("/", |_| "hi"),
+    /// );
+    /// client.get("/").send();
+    ///
+    /// let scraped = client.get("/metrics").send();
+    /// assert!(scraped.body_str().unwrap().contains("route=\"/\",status=\"200\"} 1\n"));
+    /// assert!(metrics.render().contains("route=\"/metrics\",status=\"200\"} 1\n"));
+    /// ```
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        if let Some(served) = &self.served_metrics {
+            *served.lock().unwrap_or_else(PoisonError::into_inner) = metrics.clone();
+        }
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Emit a `tracing` warning for every request that takes longer than `threshold`
+    ///
+    /// With the `tracing` feature every request is handled inside a `request` span with the
+    /// fields `method`, `path`, `peer`, the matched `route` and the response `status`. The span
+    /// is entered before any middleware runs, so their events end up in it too. Requests that
+    /// don't parse and panicking handlers are reported as events as well
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{io, sync::{Arc, Mutex}, thread, time::Duration};
+    /// use torus_http::{prelude::*, testing::TestClient};
+    /// use tracing_subscriber::fmt::format::FmtSpan;
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Captured(Arc<Mutex<Vec<u8>>>);
+    ///
+    /// impl io::Write for Captured {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         self.0.lock().unwrap().write(buf)
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let client = TestClient::new(
+    ///     HttpServer::new()
+    ///         .with_slow_request_threshold(Duration::from_millis(10))
+    ///         .get("/users/:id", |_| {
+    ///             tracing::info!("loading user");
+    ///             thread::sleep(Duration::from_millis(20));
+    ///             "alice"
+    ///         })
+    ///         .get("/boom", |_| -> &str { panic!("oops") }),
+    /// );
+    ///
+    /// let captured = Captured::default();
+    /// let writer = captured.clone();
+    /// let subscriber = tracing_subscriber::fmt()
+    ///     .with_writer(move || writer.clone())
+    ///     .with_span_events(FmtSpan::CLOSE)
+    ///     .with_ansi(false)
+    ///     .finish();
+    /// tracing::subscriber::with_default(subscriber, || {
+    ///     client.get("/users/7").send();
+    ///     client.get("/boom").send();
+    /// });
+    ///
+    /// let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    /// let line = |message: &str| output.lines().find(|line| line.contains(message)).unwrap();
+    /// // the handler's own events happen inside the span
+    /// assert!(line("loading user").contains(" request{method=GET path=/users/7 route=/users/:id}:"));
+    /// let finished = " request{method=GET path=/users/7 route=/users/:id status=200}:";
+    /// assert!(line("slow request").contains(finished));
+    /// assert!(line("close").contains(finished));
+    /// assert!(line("handler panicked").contains(" request{method=GET path=/boom route=/boom}:"));
+    /// assert!(line("handler panicked").contains("panic=\"oops\""));
+    /// ```
+    #[cfg(feature = "tracing")]
+    #[must_use]
+    pub fn with_slow_request_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_request_threshold = Some(threshold);
+        self
+    }
+
+    /// Expect every connection to start with a PROXY protocol v1 line, like load balancers such
+    /// as `HAProxy` send, and use the client address advertised in it as
+    /// [`HttpRequest::peer_addr`]
+    ///
+    /// Connections with a missing or malformed PROXY line get closed, unless
+    /// [`HttpServer::proxy_protocol_fallback`] is enabled
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .accept_proxy_protocol(true)
+    ///     .get("/", |req: HttpRequest| req.client_ip().unwrap().to_string())
+    ///     .bind(("127.0.0.1", 0))
+    ///     .unwrap();
+    /// let port = server.port();
+    /// thread::spawn(move || server.listen());
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    ///
+    /// stream.write_all(b"PROXY TCP4 203.0.113.7 10.0.0.1 56324 80\r\n").unwrap();
+    /// stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.ends_with("\r\n\r\n203.0.113.7"));
+    /// ```
+    #[must_use]
+    pub fn accept_proxy_protocol(mut self, enabled: bool) -> Self {
+        self.proxy_protocol = enabled;
+        self
+    }
+
+    /// With PROXY protocol enabled, treat connections without a valid PROXY line as plain http
+    /// coming straight from the socket's peer instead of closing them
+    #[must_use]
+    pub fn proxy_protocol_fallback(mut self, enabled: bool) -> Self {
+        self.proxy_protocol_fallback = enabled;
+        self
+    }
+
+    /// Requests whose target (path plus query) is longer than `limit` bytes get a
+    /// `414 URI Too Long` without being parsed any further, defaults to 8192 like nginx
+    ///
+    /// The target is checked while the request line is still coming in, so the rest of the
+    /// request is never read. Response middleware and the logger get a stand-in request with
+    /// just the method and the first `limit` bytes of the target. The request line counts on
+    /// top of the usual limit for the head, so limits past it work too
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .with_max_url_length(8192)
+    ///     .get("/", |_| "hi")
+    ///     .bind(("127.0.0.1", 0))
+    ///     .unwrap();
+    /// let port = server.port();
+    /// thread::spawn(move || server.listen());
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    ///
+    /// let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(10_000));
+    /// stream.write_all(long.as_bytes()).unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 414"));
+    /// ```
+    #[must_use]
+    pub fn with_max_url_length(mut self, limit: usize) -> Self {
+        self.max_url_length = limit;
+        self
+    }
+
+    /// Serve connections on a pool of `n` threads, at least one, instead of a thread each
+    ///
+    /// A worker keeps a connection for as long as it is open, keep-alive and upgraded
+    /// connections included, so once all of them are busy new connections queue up until one
+    /// closes. Idle keep-alive connections hold on to their worker as well, so a few clients
+    /// that never send anything stall the pool until they time out. Up to
+    /// [`POOL_QUEUE_PER_WORKER`] connections per worker wait in the queue, any more are answered
+    /// with `503 Service Unavailable` right away
+    ///
+    /// # Example usage:
+    ///
+    /// ```no_run
+    /// use torus_http::prelude::*;
+    /// HttpServer::new()
+    ///     .workers(8)
+    ///     .get("/", |_| "hi")
+    ///     .listen(("127.0.0.1", 8080));
+    /// ```
+    #[must_use]
+    pub fn workers(mut self, n: usize) -> Self {
+        self.workers = Some(n.max(1));
+        self
+    }
+
+    /// [`HttpServer::workers`] with one thread per available core, or four if that can't be
+    /// told
+    #[must_use]
+    pub fn workers_auto(mut self) -> Self {
+        self.workers = Some(std::thread::available_parallelism().map_or(4, std::num::NonZero::get));
+        self
+    }
+
+    /// Serve every connection on a thread of its own, which is the default
+    #[must_use]
+    pub fn thread_per_connection(mut self) -> Self {
+        self.workers = None;
+        self
+    }
+
+    /// Call `f` with the bound address once the server is ready to accept connections, e.g. to
+    /// print where it ended up when listening on port `0`
+    ///
+    /// Several callbacks run in the order they were added. They run on the listening thread
+    /// after the workers have started, right before the first connection is accepted
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, sync::mpsc, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let (started, addresses) = mpsc::channel();
+    /// let also_started = started.clone();
+    /// thread::spawn(move || {
+    ///     HttpServer::new()
+    ///         .get("/", |_| "hi")
+    ///         .on_start(move |addr| started.send(("first", addr)).unwrap())
+    ///         .on_start(move |addr| also_started.send(("second", addr)).unwrap())
+    ///         .listen(("127.0.0.1", 0))
+    /// });
+    ///
+    /// let (first, addr) = addresses.recv().unwrap();
+    /// assert_eq!(first, "first");
+    /// assert_ne!(addr.port(), 0);
+    /// assert_eq!(addresses.recv().unwrap(), ("second", addr));
+    ///
+    /// for _ in 0..2 {
+    ///     let mut stream = TcpStream::connect(addr).unwrap();
+    ///     stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    ///     let mut response = String::new();
+    ///     stream.read_to_string(&mut response).unwrap();
+    ///     assert!(response.ends_with("hi"));
+    /// }
+    /// // once per server, not per connection
+    /// assert!(addresses.try_recv().is_err());
+    /// ```
+    #[must_use]
+    pub fn on_start(self, f: impl FnOnce(SocketAddr) + Send + 'static) -> Self {
+        self.on_start
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(f));
+        self
+    }
+
+    /// Answer with `504 Gateway Timeout` when any handler takes longer than `limit`, see the
+    /// [`timeout`] module for the caveats
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{str::FromStr, thread, time::Duration};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .with_handler_timeout(Duration::from_millis(50))
+    ///     .get("/slow", |_| {
+    ///         thread::sleep(Duration::from_secs(2));
+    ///         "finally"
+    ///     });
+    ///
+    /// let res = server.handle(HttpRequest::from_str("GET /slow HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::GATEWAY_TIMEOUT);
+    /// ```
+    #[must_use]
+    pub fn with_handler_timeout(mut self, limit: Duration) -> Self {
+        self.handler_timeout = Some(limit);
+        self
+    }
+
+    /// Bound the whole cycle of a request on a connection, from its first byte arriving through
+    /// middleware and handler to writing the response
+    ///
+    /// A request that isn't answered within `limit` gets a `503 Service Unavailable` through the
+    /// response middleware and logger, and the connection is closed. This covers clients that
+    /// send their request slowly as well as slow handlers. Handlers can't be interrupted, one
+    /// watchdog thread for the whole server answers for them when they take too long, and what
+    /// they return in the end is thrown away. [`HttpServer::handle`] calls made directly aren't
+    /// bounded
+    ///
+    /// # Example usage:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use torus_http::prelude::*;
+    /// HttpServer::new()
+    ///     .with_cycle_timeout(Duration::from_secs(30))
+    ///     .get("/", |_| "hi")
+    ///     .listen(("127.0.0.1", 8080));
+    /// ```
+    #[must_use]
+    pub fn with_cycle_timeout(mut self, limit: Duration) -> Self {
+        self.cycle_timeout = Some(limit);
+        self
+    }
+
+    /// `Content-Type` for responses that have a body but didn't set one, plain strings returned
+    /// from handlers for example
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .with_default_content_type("text/html; charset=utf-8")
+    ///     .get("/", |_| "<h1>hello</h1>")
+    ///     .get("/api", |_| {
+    ///         HttpResponse::new()
+    ///             .set_body("{}")
+    ///             .insert_header("Content-Type", "application/json")
+    ///     });
+    ///
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.headers["Content-Type"], "text/html; charset=utf-8");
+    ///
+    /// let res = server.handle(HttpRequest::from_str("GET /api HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.headers["Content-Type"], "application/json");
+    /// ```
+    #[must_use]
+    pub fn with_default_content_type(mut self, mime: &str) -> Self {
+        self.default_content_type = Some(mime.to_owned());
+        self
+    }
+
+    /// Turn errors of middleware returning [`MiddlewareResult::Fail`] into responses, without
+    /// this they become a `500 Internal Server Error`
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{fmt, str::FromStr};
+    /// use torus_http::{middleware::MiddlewareError, prelude::*};
+    ///
+    /// #[derive(Debug)]
+    /// struct BadToken;
+    ///
+    /// impl fmt::Display for BadToken {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         f.write_str("bad token")
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for BadToken {}
+    ///
+    /// fn check_token(req: HttpRequest) -> Result<HttpRequest, MiddlewareError> {
+    ///     let token = req.header("X-Token").ok_or(BadToken)?;
+    ///     let _id: u32 = token.parse()?;
+    ///     Ok(req)
+    /// }
+    ///
+    /// let server = HttpServer::new()
+    ///     .add_middleware(check_token)
+    ///     .on_middleware_error(|err: &MiddlewareError| {
+    ///         if err.downcast_ref::<BadToken>().is_some() {
+    ///             HttpResponse::new_body(err.to_string(), HttpStatus::UNAUTHORIZED)
+    ///         } else {
+    ///             HttpResponse::new_body(err.to_string(), HttpStatus::BAD_REQUEST)
+    ///         }
+    ///     })
+    ///     .get("/", |_| "welcome");
+    ///
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::UNAUTHORIZED);
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\nX-Token: abc\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\nX-Token: 42\r\n\r\n").unwrap());
+    /// assert_eq!(res.body_str(), Some("welcome"));
+    /// ```
+    #[must_use]
+    pub fn on_middleware_error(
+        mut self,
+        f: impl Fn(&MiddlewareError) -> HttpResponse + Send + Sync + 'static,
+    ) -> Self {
+        self.middleware_error = Some(Box::new(f));
+        self
+    }
+
+    /// Turn errors of type `E` returned by [`fallible`] handlers into responses, see there for an
+    /// example. Errors of types without a mapper become `500 Internal Server Error`
+    #[must_use]
+    pub fn map_error<E: 'static>(
+        mut self,
+        map: impl Fn(&E) -> HttpResponse + Send + Sync + 'static,
+    ) -> Self {
+        self.error_mappers
+            .push(Box::new(move |err| err.downcast_ref::<E>().map(&map)));
+        self
+    }
+
+    /// Format of the responses torus makes up itself, like the `404` for unknown routes, the
+    /// `500` for panicking handlers or the `414` for overlong urls
+    ///
+    /// The bundled middleware answer in this format too, others can find it with
+    /// [`ErrorFormat::of`]
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::{middleware::basic_auth::BasicAuth, prelude::*, server::ErrorFormat};
+    ///
+    /// let server = HttpServer::new().get("/panic", |_| -> &str { panic!("oops") });
+    /// let res = server.handle(HttpRequest::from_str("GET /nope HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.headers["Content-Type"], "text/plain; charset=utf-8");
+    /// let res = server.handle(HttpRequest::from_str("GET /panic HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.headers["Content-Type"], "text/plain; charset=utf-8");
+    ///
+    /// let server = HttpServer::new()
+    ///     .with_error_format(ErrorFormat::Json)
+    ///     .with_https_redirect(443)
+    ///     .get("/panic", |_| -> &str { panic!("oops") });
+    /// let res = server.handle(HttpRequest::from_str("GET /nope HTTP/1.1\r\nX-Forwarded-Proto: https\r\n\r\n").unwrap());
+    /// assert_eq!(res.headers["Content-Type"], "application/json");
+    /// assert_eq!(res.body_str(), Some(r#"{"status": 404, "error": "no method found"}"#));
+    /// let res = server.handle(HttpRequest::from_str("GET /panic HTTP/1.1\r\nX-Forwarded-Proto: https\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::INTERNAL_SERVER_ERROR);
+    /// assert_eq!(res.headers["Content-Type"], "application/json");
+    /// // no Host header to redirect to
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+    /// assert_eq!(res.headers["Content-Type"], "application/json");
+    ///
+    /// // middleware answering for the server
+    /// let server = HttpServer::new()
+    ///     .with_error_format(ErrorFormat::Json)
+    ///     .add_around_middleware(BasicAuth::with_credentials("admin", "admin", "hunter2"))
+    ///     .get("/", |_| "hi");
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::UNAUTHORIZED);
+    /// assert_eq!(res.body_str(), Some(r#"{"status": 401, "error": "unauthorized"}"#));
+    /// ```
+    #[must_use]
+    pub fn with_error_format(mut self, format: ErrorFormat) -> Self {
+        self.error_format = format;
+        self
+    }
+
+    /// Whether responses get a `Date` header with the time they are sent at, on by default
+    ///
+    /// Handlers setting their own `Date` keep it
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new().get("/", |_| "hi").bind(("127.0.0.1", 0)).unwrap();
+    /// let port = server.port();
+    /// thread::spawn(move || server.listen());
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    ///
+    /// stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// let date = response
+    ///     .lines()
+    ///     .find_map(|line| line.strip_prefix("Date: "))
+    ///     .unwrap();
+    /// // e.g. "Wed, 15 Nov 2023 08:12:31 GMT"
+    /// assert_eq!(date.len(), 29);
+    /// assert_eq!(&date[3..5], ", ");
+    /// assert!(date.ends_with(" GMT"));
+    /// ```
+    #[must_use]
+    pub fn with_date_header(mut self, enable: bool) -> Self {
+        self.date_header = enable;
+        self
+    }
+
+    /// Add `name: value` to every response that doesn't set `name` itself, setting the same
+    /// header again replaces the earlier value
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .with_default_header("X-Powered-By", "coffee")
+    ///     .get("/", |_| "hi")
+    ///     .get("/tea", |_| HttpResponse::new().insert_header("X-Powered-By", "tea"));
+    ///
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.headers["X-Powered-By"], "coffee");
+    /// let res = server.handle(HttpRequest::from_str("GET /tea HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.headers["X-Powered-By"], "tea");
+    /// // unknown routes get them as well
+    /// let res = server.handle(HttpRequest::from_str("GET /nope HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.headers["X-Powered-By"], "coffee");
+    /// ```
+    #[must_use]
+    pub fn with_default_header(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        let name = name.into();
+        self.default_headers
+            .retain(|(other, _)| !other.eq_ignore_ascii_case(&name));
+        self.default_headers.push((name, value.into()));
+        self
+    }
+
+    /// The `Server` header sent with every response, `torus-http/<version>` by default. An empty
+    /// value leaves the header out, as does [`HttpServer::hide_server_header`]
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let request = || HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap();
+    ///
+    /// let res = HttpServer::new().get("/", |_| "hi").handle(request());
+    /// assert!(res.headers["Server"].starts_with("torus-http/"));
+    ///
+    /// let res = HttpServer::new().with_server_header("nginx").get("/", |_| "hi").handle(request());
+    /// assert_eq!(res.headers["Server"], "nginx");
+    ///
+    /// let res = HttpServer::new().hide_server_header().get("/", |_| "hi").handle(request());
+    /// assert!(!res.headers.contains_key("Server"));
+    /// ```
+    #[must_use]
+    pub fn with_server_header(mut self, value: &str) -> Self {
+        if value.is_empty() {
+            self.default_headers
+                .retain(|(name, _)| !name.eq_ignore_ascii_case("Server"));
+            self
+        } else {
+            self.with_default_header("Server", value)
+        }
+    }
+
+    /// Don't tell clients which server software they are talking to
+    #[must_use]
+    pub fn hide_server_header(self) -> Self {
+        self.with_server_header("")
+    }
+
+    /// Answer every request that didn't come in over https with a `301 Moved Permanently` to its
+    /// https equivalent, before any middleware runs, around and response middleware included
+    ///
+    /// The redirect is built from the `Host` header, requests without one get a `400 Bad Request`
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new().with_https_redirect(8443).get("/login", |_| "hi");
+    ///
+    /// let request = HttpRequest::from_str("GET /login HTTP/1.1\r\nHost: example.com:8080\r\n\r\n");
+    /// let response = server.handle(request.unwrap());
+    /// assert_eq!(response.status, HttpStatus::MOVED_PERMANENTLY);
+    /// assert_eq!(response.headers["Location"], "https://example.com:8443/login");
+    ///
+    /// // the query goes along as it was sent, order, repeats and flags included
+    /// let request = HttpRequest::from_str("GET /login?b=2&a=1&a=3&debug HTTP/1.1\r\nHost: example.com\r\n\r\n");
+    /// let response = server.handle(request.unwrap());
+    /// assert_eq!(response.headers["Location"], "https://example.com:8443/login?b=2&a=1&a=3&debug");
+    ///
+    /// let request = HttpRequest::from_str("GET /login HTTP/1.1\r\n\r\n");
+    /// assert_eq!(server.handle(request.unwrap()).status, HttpStatus::BAD_REQUEST);
+    ///
+    /// let request = HttpRequest::from_str("GET /login HTTP/1.1\r\nX-Forwarded-Proto: https\r\n\r\n");
+    /// assert_eq!(server.handle(request.unwrap()).body_str(), Some("hi"));
+    ///
+    /// // an around middleware like a rate limiter never sees the requests about to be redirected
+    /// let server = HttpServer::new()
+    ///     .with_https_redirect(443)
+    ///     .add_around_middleware(|_req, _next: Next| {
+    ///         HttpResponse::new().set_status(HttpStatus::TOO_MANY_REQUESTS)
+    ///     });
+    /// let request = HttpRequest::from_str("GET / HTTP/1.1\r\nHost: example.com\r\n\r\n");
+    /// assert_eq!(server.handle(request.unwrap()).status, HttpStatus::MOVED_PERMANENTLY);
+    /// ```
+    #[must_use]
+    pub fn with_https_redirect(mut self, https_port: u16) -> Self {
+        self.https_redirect.replace(https_port);
+        self
+    }
+
+    /// Adds a middleware that gets to touch every outgoing response
+    ///
+    /// Response middleware run in registration order after the handler, and also for responses
+    /// coming from short-circuiting middleware or torus itself (like the 404 for unknown routes,
+    /// or the 400 for a request that doesn't parse). They see the request as it came in, before
+    /// any request middleware touched it. The only responses they don't see are the `503` a full
+    /// worker pool answers with, which goes out before a request was read, and the redirect of
+    /// [`HttpServer::with_https_redirect`].
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::{prelude::*, testing::TestClient};
+    ///
+    /// let server = HttpServer::new()
+    ///     .add_response_middleware(|_req, res| res.insert_header("X-Frame-Options", "DENY"))
+    ///     .get("/", |_| "hi");
+    ///
+    /// let found = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(found.headers["X-Frame-Options"], "DENY");
+    ///
+    /// let not_found = server.handle(HttpRequest::from_str("GET /nope HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(not_found.status, HttpStatus::NOT_FOUND);
+    /// assert_eq!(not_found.headers["X-Frame-Options"], "DENY");
+    ///
+    /// let client = TestClient::new(server.with_max_url_length(16));
+    /// let too_long = client.get("/a-rather-long-path").send();
+    /// assert_eq!(too_long.status.code(), 414);
+    /// assert_eq!(too_long.header("X-Frame-Options"), Some("DENY"));
+    /// ```
+    #[must_use]
+    pub fn add_response_middleware(
+        mut self,
+        f: impl Fn(&HttpRequest, HttpResponse) -> HttpResponse + Send + Sync + 'static,
+    ) -> Self {
+        self.response_middleware.push(Box::new(f));
+        self
+    }
+
+    /// Adds a middleware that wraps around everything else (request middleware, the handler and
+    /// response middleware), see [`Middleware`] for an example
+    ///
+    /// The first registered one is the outermost
+    #[must_use]
+    pub fn add_around_middleware(mut self, f: impl Middleware + 'static) -> Self {
+        self.around_middleware.push(Box::new(f));
+        self
+    }
+
+    /// Adds an around middleware that only runs for requests `predicate` accepts, others skip it
+    /// entirely, shorthand for registering [`Middleware::when`]
+    ///
+    /// It takes its place among the around middleware like any other
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{str::FromStr, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
+    /// use torus_http::{middleware::basic_auth::BasicAuth, prelude::*};
+    ///
+    /// let asked = Arc::new(AtomicUsize::new(0));
+    /// let counter = asked.clone();
+    /// let server = HttpServer::new()
+    ///     .add_middleware_when(
+    ///         move |req: &HttpRequest| {
+    ///             counter.fetch_add(1, Ordering::SeqCst);
+    ///             req.path.starts_with("/admin")
+    ///         },
+    ///         BasicAuth::with_credentials("admin", "root", "hunter2"),
+    ///     )
+    ///     .get("/admin/x", |_| "secret")
+    ///     .get("/public/x", |_| "hello");
+    ///
+    /// let res = server.handle(HttpRequest::from_str("GET /admin/x HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::UNAUTHORIZED);
+    /// let res = server.handle(HttpRequest::from_str("GET /public/x HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.body_str(), Some("hello"));
+    /// assert_eq!(asked.load(Ordering::SeqCst), 2);
+    /// ```
+    #[must_use]
+    pub fn add_middleware_when(
+        self,
+        predicate: impl Fn(&HttpRequest) -> bool + Send + Sync + 'static,
+        f: impl Middleware + 'static,
+    ) -> Self {
+        self.add_around_middleware(f.when(predicate))
+    }
+
+    /// Run a parsed request through the middleware and the matching handler without going
+    /// over the network, which is handy for testing
+    ///
+    /// A panicking middleware or handler is answered with a `500 Internal Server Error`, the
+    /// connection stays usable
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .add_middleware(|req: HttpRequest| {
+    ///         assert!(req.path != "/boom", "middleware exploded");
+    ///         req
+    ///     })
+    ///     .get("/boom", |_| "unreachable")
+    ///     .get("/", |_| "still here");
+    ///
+    /// let res = server.handle(HttpRequest::from_str("GET /boom HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.status, HttpStatus::INTERNAL_SERVER_ERROR);
+    /// let res = server.handle(HttpRequest::from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+    /// assert_eq!(res.body_str(), Some("still here"));
+    /// ```
+    #[must_use]
+    pub fn handle(&self, mut request: HttpRequest) -> HttpResponse {
+        if self.error_format != ErrorFormat::default() {
+            request.extensions.insert(self.error_format);
+        }
+        let measured = self.metrics.as_ref().map(|metrics| {
+            let route = MatchedRoute::default();
+            request.extensions.insert(route.clone());
+            (metrics, request.method.clone(), route, Instant::now())
+        });
+        #[cfg(feature = "tracing")]
+        let (request, span, start) = start_span(request);
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        // the handler takes ownership of the request, the logger wants to know what panicked
+        let panicked_on = self.logger.as_ref().map(|_| PanickedRequest::new(&request));
+        let run = || match self.https_redirect {
+            Some(https_port) if !request.is_https() => self.https_redirect(&request, https_port),
+            _ => Next::new(&self.around_middleware, &|request| {
+                self.handle_inner(request)
+            })
+            .run(request),
+        };
+        // nothing the panicking code touched is looked at again, only its absence of a response
+        let response =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(run)).unwrap_or_else(|payload| {
+                self.report_panic(panicked_on.as_ref(), payload.as_ref());
+                self.error_response(HttpStatus::INTERNAL_SERVER_ERROR, "internal server error")
+            });
+        #[cfg(feature = "tracing")]
+        self.finish_span(&span, &response, start);
+        if let Some((metrics, method, MatchedRoute(route), start)) = measured {
+            let method = match route.get() {
+                // anything goes for the method of a request no route wanted
+                None if method.is_other() => "OTHER",
+                _ => method.as_str(),
+            };
+            let route = route.get().map_or("unmatched", String::as_str);
+            metrics.record(method, route, response.status.code(), start.elapsed());
+        }
+        self.with_default_headers(response)
+    }
+
+    /// Reports a panic [`HttpServer::handle`] caught to the logger and as a `tracing` event
+    fn report_panic(&self, request: Option<&PanickedRequest>, payload: &(dyn Any + Send)) {
+        let message = panic_message(payload);
+        #[cfg(feature = "tracing")]
+        tracing::error!(panic = message, "handler panicked");
+        if let (Some(logger), Some(request)) = (&self.logger, request) {
+            logger.log_panic(request, message);
+        }
+    }
+
+    /// Records the status on the request's span and warns about slow requests
+    #[cfg(feature = "tracing")]
+    fn finish_span(&self, span: &tracing::Span, response: &HttpResponse, start: Instant) {
+        span.record("status", response.status.code());
+        let elapsed = start.elapsed();
+        if self
+            .slow_request_threshold
+            .is_some_and(|threshold| elapsed > threshold)
+        {
+            tracing::warn!(elapsed_ms = elapsed.as_secs_f64() * 1000.0, "slow request");
+        }
+    }
+
+    fn with_default_headers(&self, mut response: HttpResponse) -> HttpResponse {
+        for (name, value) in &self.default_headers {
+            if !response
+                .headers
+                .keys()
+                .any(|k| k.eq_ignore_ascii_case(name))
+            {
+                response = response.insert_header(name.as_str(), value.as_str());
+            }
+        }
+        response
+    }
+
+    /// Everything the around middleware wrap
+    fn handle_inner(&self, request: HttpRequest) -> HttpResponse {
+        if self.response_middleware.is_empty() {
+            return self.with_content_type(self.respond(request));
+        }
+        let original = request.clone();
+        let response = self.with_content_type(self.respond(request));
+        self.response_middleware
+            .iter()
+            .fold(response, |response, f| f(&original, response))
+    }
+
+    /// Default answer to `OPTIONS *`, listing every method any route accepts
+    fn server_options(&self) -> HttpResponse {
+        let mut custom: Vec<&str> = self
+            .routes()
+            .flat_map(|(_, methods)| methods.keys())
+            .filter(|method| method.is_other())
+            .map(HttpMethod::as_str)
+            .collect();
+        custom.sort_unstable();
+        custom.dedup();
+        let mut allow: Vec<&str> = HttpMethod::all_standard()
+            .filter(|method| {
+                **method == HttpMethod::Options
+                    || self
+                        .routes()
+                        .any(|(_, methods)| methods.contains_key(method))
+            })
+            .map(HttpMethod::as_str)
+            .collect();
+        allow.extend(custom);
+        HttpResponse::new()
+            .set_status(HttpStatus::OK)
+            .insert_header("Allow", allow.join(", "))
+    }
+
+    /// The methods the routes for `path` take, `None` if there are none
+    fn allowed_methods(&self, path: &str) -> Option<String> {
+        let mut allow: Vec<&str> = self
+            .handlers
+            .get(path)
+            .into_iter()
+            .chain(
+                self.patterns
+                    .iter()
+                    .filter(|(pattern, _)| pattern.matches(path).is_some())
+                    .map(|(_, methods)| methods),
+            )
+            .flat_map(HashMap::keys)
+            .map(HttpMethod::as_str)
+            .collect();
+        allow.sort_unstable();
+        allow.dedup();
+        (!allow.is_empty()).then(|| allow.join(", "))
+    }
+
+    fn middleware_error_response(&self, err: &MiddlewareError) -> HttpResponse {
+        match &self.middleware_error {
+            Some(map) => map(err),
+            None => self.error_response(HttpStatus::INTERNAL_SERVER_ERROR, "internal server error"),
+        }
+    }
+
+    fn failure_response(&self, failure: &Failure) -> HttpResponse {
+        self.error_mappers
+            .iter()
+            .find_map(|map| map(failure.0.as_ref()))
+            .unwrap_or_else(|| {
+                self.error_response(HttpStatus::INTERNAL_SERVER_ERROR, "internal server error")
+            })
+    }
+
+    fn error_response(&self, status: HttpStatus, message: &str) -> HttpResponse {
+        self.error_format.response(status, message)
+    }
+
+    /// Redirect a plain http request to the same path over https
+    fn https_redirect(&self, request: &HttpRequest, https_port: u16) -> HttpResponse {
+        let Some(host) = request.header("Host") else {
+            return self.error_response(HttpStatus::BAD_REQUEST, "missing Host header");
+        };
+        // drop any port the plain http listener was reached on, keeping ipv6 literals intact
+        let host = match host.rsplit_once(':') {
+            Some((name, port)) if !port.contains(']') => name,
+            _ => host,
+        };
+        let mut location = match https_port {
+            443 => format!("https://{host}{}", request.path),
+            port => format!("https://{host}:{port}{}", request.path),
+        };
+        if let Some(query) = &request.raw_query {
+            location.push('?');
+            location.push_str(query);
+        }
+        HttpResponse::new()
+            .set_status(HttpStatus::MOVED_PERMANENTLY)
+            .insert_header("Location", location)
+    }
+
+    /// Stamps the response with the current time if enabled and it isn't dated yet
+    fn with_date(&self, response: HttpResponse) -> HttpResponse {
+        if !self.date_header
+            || response
+                .headers
+                .keys()
+                .any(|k| k.eq_ignore_ascii_case("Date"))
+        {
+            return response;
+        }
+        response.insert_header("Date", format_http_date(SystemTime::now()))
+    }
+
+    /// The `414 URI Too Long` for a request whose target is longer than
+    /// [`HttpServer::with_max_url_length`] allows, along with the stand-in request it was made
+    /// for, ready to be written
+    pub(crate) fn reject_long_target(
+        &self,
+        raw: &[u8],
+        peer_addr: Option<SocketAddr>,
+    ) -> (HttpRequest, HttpResponse) {
+        let request = self.stand_in_request(raw, peer_addr);
+        let response = self.answer_early(
+            &request,
+            ClientErrorResponse::URITooLong.into(),
+            "uri too long",
+        );
+        (request, response)
+    }
+
+    /// The `400 Bad Request` for the request at the start of `raw` that couldn't be read or
+    /// parsed, `None` if `err` is about the connection rather than the request
+    ///
+    /// The error still goes to the logger, the client only learns its request was bad
+    pub(crate) fn reject_malformed(
+        &self,
+        raw: &[u8],
+        peer_addr: Option<SocketAddr>,
+        err: &ServerError,
+    ) -> Option<HttpResponse> {
+        match err {
+            // our own complaints about the request, like a broken `Content-Length`
+            ServerError::IoError(io) if io.kind() == ErrorKind::InvalidData => {}
+            ServerError::IoError(_) | ServerError::InvalidRoutes(_) => return None,
+            ServerError::Utf8Conversion(_)
+            | ServerError::RequestParse(_)
+            | ServerError::ChunkParse(_) => {}
+        }
+        if let Some(logger) = &self.logger {
+            logger.log_error(err);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::warn!(error = ?err, "malformed request");
+        let request = self.stand_in_request(raw, peer_addr);
+        Some(self.answer_early(&request, HttpStatus::BAD_REQUEST, "Bad Request"))
+    }
+
+    /// Reads the request in `raw` the way a connection would and answers it, the response is
+    /// ready to be written. Anything after the first request is ignored
+    pub(crate) fn respond_to_raw(&self, raw: &[u8]) -> HttpResponse {
+        let mut buf = Vec::new();
+        let incoming = read_request(
+            &mut Replay(raw),
+            &mut buf,
+            &mut Instant::now(),
+            self.max_url_length,
+            None,
+        );
+        let request = match incoming {
+            Ok(Incoming::Request(len)) => parse_request(&buf[..len]).map(|r| (len, r)),
+            Ok(Incoming::TargetTooLong) => return self.reject_long_target(&buf, None).1,
+            // a connection would wait for the rest, there is no rest coming here
+            Ok(Incoming::Closed | Incoming::TimedOut) => {
+                Err(invalid_data("request ends early").into())
+            }
+            Err(ServerError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                Err(invalid_data("request ends early").into())
+            }
+            Err(err) => Err(err),
+        };
+        match request {
+            Ok((len, request)) => {
+                let keep_alive = wants_keep_alive(&buf[..len], &request);
+                let response = for_version(self.handle(request), is_http_1_0(&buf[..len]));
+                self.frame_response(response, keep_alive)
+            }
+            Err(err) => self.reject_malformed(&buf, None, &err).unwrap_or_else(|| {
+                let request = self.stand_in_request(&buf, None);
+                self.answer_early(&request, HttpStatus::BAD_REQUEST, "Bad Request")
+            }),
+        }
+    }
+
+    /// Something to hand the response middleware and logger for a request that wasn't read in
+    /// full, with the method and (at most [`HttpServer::with_max_url_length`] of the) target
+    /// from its request line
+    fn stand_in_request(&self, raw: &[u8], peer_addr: Option<SocketAddr>) -> HttpRequest {
+        let line = raw.split(|&b| b == b'\n').next().unwrap_or_default();
+        let mut parts = line.split(|&b| b == b' ');
+        let method = String::from_utf8_lossy(parts.next().unwrap_or_default())
+            .parse()
+            .unwrap_or(HttpMethod::Get);
+        let target = parts.next().unwrap_or_default();
+        HttpRequest {
+            method,
+            path: String::from_utf8_lossy(&target[..target.len().min(self.max_url_length)])
+                .into_owned(),
+            headers: request::Headers::new(),
+            body: None,
+            query: None,
+            raw_query: None,
+            peer_addr,
+            extensions: Extensions::default(),
+        }
+    }
+
+    /// An error response for `request` that didn't get to a handler, through the response
+    /// middleware and ready to be written on a connection that is closed after it
+    fn answer_early(
+        &self,
+        request: &HttpRequest,
+        status: HttpStatus,
+        message: &str,
+    ) -> HttpResponse {
+        let response = self.respond_without_handler(request, self.error_response(status, message));
+        self.frame_response(response, false)
+    }
+
+    /// Runs a response the server made up for `request` instead of a handler through the
+    /// response middleware, like a handler's response
+    fn respond_without_handler(
+        &self,
+        request: &HttpRequest,
+        response: HttpResponse,
+    ) -> HttpResponse {
+        let response = self
+            .response_middleware
+            .iter()
+            .fold(response, |response, f| f(request, response));
+        self.with_default_headers(response)
+    }
+
+    /// Adds the headers that depend on the connection rather than the handler to a response of
+    /// [`HttpServer::handle`], after this it is ready to be written
+    pub(crate) fn frame_response(
+        &self,
+        mut response: HttpResponse,
+        keep_alive: bool,
+    ) -> HttpResponse {
+        if response.forbids_body() {
+            response
+                .headers
+                .retain(|k, _| !k.eq_ignore_ascii_case("Content-Length"));
+        } else if response.header("Content-Length").is_none() {
+            let len = response.body.as_deref().map_or(0, <[u8]>::len);
+            response = response.insert_header("Content-Length", len.to_string());
+        }
+        if !keep_alive {
+            response = response.insert_header("Connection", "close");
+        }
+        self.with_date(response)
     }
 
-    #[must_use]
-    pub fn set_state<T: Send + Sync + 'static>(mut self, state: T) -> Self {
-        self.state.replace(Box::new(state));
-        self
+    /// Fills in the default content type if there is one and the response needs it
+    fn with_content_type(&self, response: HttpResponse) -> HttpResponse {
+        match &self.default_content_type {
+            Some(mime)
+                if response.body.is_some()
+                    && !response
+                        .headers
+                        .keys()
+                        .any(|k| k.eq_ignore_ascii_case("Content-Type")) =>
+            {
+                response.insert_header("Content-Type", mime.as_str())
+            }
+            _ => response,
+        }
+    }
+
+    fn respond(&self, mut request: HttpRequest) -> HttpResponse {
+        for (_, _, middle_ware) in &self.middle_ware {
+            request = match middle_ware(&self.state, request) {
+                MiddlewareResult::Continue(request) => request,
+                MiddlewareResult::Respond(response) => return response,
+                MiddlewareResult::Fail(err) => return self.middleware_error_response(&err),
+            };
+        }
+        let route = self
+            .handlers
+            .get_key_value(&request.path)
+            .and_then(|(path, methods)| Some((path.as_str(), methods.get(&request.method)?)))
+            .or_else(|| {
+                self.patterns.iter().find_map(|(pattern, methods)| {
+                    let route = methods.get(&request.method)?;
+                    let params = pattern.matches(&request.path)?;
+                    request.extensions.insert(PathParams(params));
+                    Some((pattern.as_str(), route))
+                })
+            });
+        if let Some((pattern, route)) = route {
+            record_route(&request, pattern);
+            for middle_ware in &route.middleware {
+                request = match middle_ware(request) {
+                    MiddlewareResult::Continue(request) => request,
+                    MiddlewareResult::Respond(response) => return response,
+                    MiddlewareResult::Fail(err) => return self.middleware_error_response(&err),
+                };
+            }
+            let response = match self.handler_timeout {
+                Some(limit) => timeout::run_with_deadline(&route.handler, request, limit)
+                    .unwrap_or_else(|(status, message)| self.error_response(status, message)),
+                None => route.handler.call(request),
+            };
+            match &response.failure {
+                Some(failure) => self.failure_response(failure),
+                None => response,
+            }
+        } else if request.is_server_options() {
+            self.server_options()
+        } else if request.method == HttpMethod::Connect {
+            self.error_response(HttpStatus::NOT_IMPLEMENTED, "CONNECT is not supported")
+        } else if let Some(allow) = self.allowed_methods(&request.path) {
+            self.error_response(HttpStatus::METHOD_NOT_ALLOWED, "method not allowed")
+                .insert_header("Allow", allow)
+        } else {
+            self.error_response(HttpStatus::NOT_FOUND, "no method found")
+        }
     }
 
     /// Start your http server
@@ -243,57 +2793,1043 @@ impl HttpServer {
     /// # Errors
     ///
     /// - Failed binding listener to address
+    /// - The routes didn't pass [`HttpServer::validate`]
     /// - Failed reading the stream to the buffer
     /// - Failed getting the stream
-    /// - Failed parsing the request
     /// - Failed flushing to the stream
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .get("/big", |_| "x".repeat(256 * 1024))
+    ///     .bind(("127.0.0.1", 0))
+    ///     .unwrap();
+    /// let port = server.port();
+    /// thread::spawn(move || server.listen());
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    ///
+    /// stream.write_all(b"GET /big HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    /// let mut response = Vec::new();
+    /// stream.read_to_end(&mut response).unwrap();
+    /// assert!(response.starts_with(b"HTTP/1.1 200"));
+    /// assert!(response.ends_with(&[b'x'; 256 * 1024]));
+    /// ```
+    ///
+    /// Requests that don't parse are answered with `400 Bad Request` before the connection is
+    /// closed:
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new().get("/", |_| "hi").bind(("127.0.0.1", 0)).unwrap();
+    /// let port = server.port();
+    /// thread::spawn(|| server.listen());
+    ///
+    /// for garbage in [
+    ///     &b"GARBAGE\r\n\r\n"[..],
+    ///     b"GET / HTTP/1.1\r\nContent-Length: lots\r\n\r\n",
+    ///     // chunked has to come last, or the end of the body is anyone's guess
+    ///     b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\nContent-Length: 3\r\n\r\nabc",
+    ///     b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffffffff\r\n",
+    /// ] {
+    ///     let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    ///     stream.write_all(garbage).unwrap();
+    ///     let mut response = String::new();
+    ///     stream.read_to_string(&mut response).unwrap();
+    ///     assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{response}");
+    ///     assert!(response.contains("Connection: close\r\n"));
+    ///     assert!(response.ends_with("\r\n\r\nBad Request"));
+    /// }
+    /// ```
+    ///
+    /// Clients that send `Expect: 100-continue` are told to go ahead before the body is read:
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new()
+    ///     .post("/upload", |req: HttpRequest| format!("got {} bytes", req.body.map_or(0, |b| b.len())))
+    ///     .bind(("127.0.0.1", 0))
+    ///     .unwrap();
+    /// let port = server.port();
+    /// thread::spawn(move || server.listen());
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    ///
+    /// stream
+    ///     .write_all(b"POST /upload HTTP/1.1\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\n")
+    ///     .unwrap();
+    /// let mut buf = [0; 256];
+    /// let n = stream.read(&mut buf).unwrap();
+    /// assert_eq!(&buf[..n], b"HTTP/1.1 100 Continue\r\n\r\n");
+    ///
+    /// stream.write_all(b"hello").unwrap();
+    /// let n = stream.read(&mut buf).unwrap();
+    /// assert!(buf[..n].starts_with(b"HTTP/1.1 200"));
+    /// assert!(buf[..n].ends_with(b"got 5 bytes"));
+    ///
+    /// // http/1.0 has no 100 Continue, the body has to follow anyway
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    /// stream
+    ///     .write_all(b"POST /upload HTTP/1.0\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\nhello")
+    ///     .unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200"));
+    /// assert!(response.ends_with("got 5 bytes"));
+    /// ```
     pub fn listen(self, address: impl ToSocketAddrs) -> Result<(), ServerError> {
+        self.bind(address)?.listen()
+    }
+
+    /// Bind to `address` without serving yet, so the actual address is known before the first
+    /// connection, handy with port `0` to let the os pick a free port
+    ///
+    /// # Errors
+    ///
+    /// - Failed binding listener to address
+    /// - The routes didn't pass [`HttpServer::validate`]
+    ///
+    /// # Example usage:
+    ///
+    /// ```rust
+    /// use std::{io::{Read, Write}, net::TcpStream, thread};
+    /// use torus_http::prelude::*;
+    ///
+    /// let server = HttpServer::new().get("/", |_| "hi").bind(("127.0.0.1", 0)).unwrap();
+    /// let port = server.port();
+    /// assert_ne!(port, 0);
+    /// assert_eq!(server.local_addr().port(), port);
+    /// thread::spawn(|| server.listen());
+    ///
+    /// let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    /// stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200"));
+    /// ```
+    pub fn bind(self, address: impl ToSocketAddrs) -> Result<BoundServer, ServerError> {
+        if !self.skip_validation {
+            self.validate().map_err(ServerError::InvalidRoutes)?;
+        }
         let listener = TcpListener::bind(address)?;
-        let handlers = Arc::new(self.handlers);
-        let middle_ware = Arc::new(self.middle_ware);
+        let local_addr = listener.local_addr()?;
+        Ok(BoundServer {
+            server: self,
+            listener,
+            local_addr,
+        })
+    }
+}
+
+/// An [`HttpServer`] that is bound to an address but not accepting connections yet, see
+/// [`HttpServer::bind`]
+pub struct BoundServer {
+    server: HttpServer,
+    listener: TcpListener,
+    local_addr: SocketAddr,
+}
+
+impl BoundServer {
+    /// The address the listener ended up on
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The port the listener ended up on, never `0`
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.local_addr.port()
+    }
+
+    /// Start serving, like [`HttpServer::listen`]
+    ///
+    /// With [`HttpServer::workers`] the worker threads are started here and live as long as
+    /// the listener
+    ///
+    /// # Errors
+    ///
+    /// - Failed starting the worker threads
+    /// - Failed getting the stream
+    pub fn listen(self) -> Result<(), ServerError> {
+        let Self {
+            mut server,
+            listener,
+            local_addr,
+        } = self;
+        if server.cycle_timeout.is_some() {
+            let (sender, receiver) = mpsc::channel();
+            std::thread::Builder::new()
+                .name("torus-watchdog".to_owned())
+                .spawn(move || watch(&receiver))?;
+            server.watchdog = Some(sender);
+        }
+        let on_start = std::mem::take(
+            &mut *server
+                .on_start
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        let server = Arc::new(server);
+        let pool = match server.workers {
+            Some(workers) => Some(start_workers(&server, workers)?),
+            None => None,
+        };
+        for f in on_start {
+            f(local_addr);
+        }
 
         for stream in listener.incoming() {
             let stream = stream?;
-            let middle_ware = middle_ware.clone();
-            let handlers = handlers.clone();
-
-            let job = move || _ = handle_connection(&handlers, &middle_ware, stream);
-            std::thread::spawn(job);
+            let Some(sender) = &pool else {
+                let server = server.clone();
+                std::thread::spawn(move || serve_connection(&server, stream));
+                continue;
+            };
+            // the workers only stop when the sender is gone
+            if let Err(mpsc::TrySendError::Full(stream)) = sender.try_send(stream) {
+                reject_busy(&server, stream);
+            }
         }
         Ok(())
     }
 }
 
-fn handle_connection(
-    // server: &Arc<HttpServer<T>>,
-    handlers: &Arc<HashMap<(String, HttpMethod), Handler>>,
-    middle_ware: &Arc<Option<MiddleWareFn>>,
-    mut stream: std::net::TcpStream,
-) -> Result<(), ServerError> {
-    let mut buf = [0; 4096 * 4];
-    let n = stream.read(&mut buf)?;
-    let request = {
-        let request = HttpRequest::from_str(from_utf8(&buf[..n])?)?;
-        if let Some(middle_ware) = **middle_ware {
-            middle_ware(request)
-        } else {
-            request
+/// How many connections may wait for each worker of [`HttpServer::workers`] before new ones are
+/// turned away
+pub const POOL_QUEUE_PER_WORKER: usize = 64;
+
+/// Starts `workers` threads serving the connections sent to the returned queue
+fn start_workers(
+    server: &Arc<HttpServer>,
+    workers: usize,
+) -> std::io::Result<mpsc::SyncSender<TcpStream>> {
+    let (sender, receiver) = mpsc::sync_channel(workers.saturating_mul(POOL_QUEUE_PER_WORKER));
+    let receiver = Arc::new(Mutex::new(receiver));
+    for i in 0..workers {
+        let server = server.clone();
+        let receiver = receiver.clone();
+        std::thread::Builder::new()
+            .name(format!("torus-worker-{i}"))
+            .spawn(move || work(&server, &receiver))?;
+    }
+    Ok(sender)
+}
+
+/// Serves the connections that come in over `receiver` one after another, until the listener
+/// is gone
+fn work(server: &Arc<HttpServer>, receiver: &Mutex<mpsc::Receiver<TcpStream>>) {
+    loop {
+        let stream = receiver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .recv();
+        let Ok(stream) = stream else {
+            return;
+        };
+        // handler panics are caught further in, this keeps the worker around when a logger or
+        // an upgraded connection panics
+        _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            serve_connection(server, stream);
+        }));
+    }
+}
+
+fn serve_connection(server: &Arc<HttpServer>, stream: TcpStream) {
+    if let Err(e) = handle_connection(server, stream)
+        && let Some(logger) = &server.logger
+    {
+        logger.log_error(&e);
+    }
+}
+
+/// Answers a connection the worker queue has no room for with `503 Service Unavailable`
+///
+/// Nothing was read yet, so the response middleware are skipped
+fn reject_busy(server: &HttpServer, mut stream: TcpStream) {
+    let response = server
+        .with_default_headers(server.error_response(HttpStatus::SERVICE_UNAVAILABLE, "server busy"))
+        .insert_header("Connection", "close");
+    // the listening thread writes this, it mustn't wait on a client that doesn't read
+    _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+    _ = write_response(&mut stream, &server.with_date(response), &mut Vec::new());
+}
+
+/// Initial size of the per connection buffers, they only grow past this for large requests
+const BUFFER_SIZE: usize = 4096 * 4;
+/// Upper bound for the request line and headers of a single request
+const MAX_HEAD_SIZE: usize = 4096 * 4;
+/// What [`HttpServer::with_max_url_length`] defaults to
+const DEFAULT_MAX_URL_LENGTH: usize = 8192;
+
+/// Upper bound for the body of a single request
+const MAX_BODY_SIZE: usize = 1024 * 1024 * 16;
+/// How long a connection may sit idle waiting for the next request
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Buffers that live as long as a connection and get reused for every request on it
+struct ConnectionBuffers {
+    read: Vec<u8>,
+    write: Vec<u8>,
+}
+
+impl ConnectionBuffers {
+    fn new() -> Self {
+        Self {
+            read: Vec::with_capacity(BUFFER_SIZE),
+            write: Vec::with_capacity(BUFFER_SIZE),
+        }
+    }
+
+    /// Clear the write buffer and give back memory if a big request or response ballooned them
+    fn reset(&mut self) {
+        self.write.clear();
+        if self.write.capacity() > BUFFER_SIZE * 4 {
+            self.write.shrink_to(BUFFER_SIZE);
         }
+        if self.read.capacity() > BUFFER_SIZE * 4 {
+            self.read.shrink_to(BUFFER_SIZE);
+        }
+    }
+}
+
+fn handle_connection(server: &Arc<HttpServer>, mut stream: TcpStream) -> Result<(), ServerError> {
+    let _connection = server.metrics.as_ref().map(Metrics::connection_opened);
+    stream.set_read_timeout(Some(KEEP_ALIVE_TIMEOUT))?;
+    let mut buffers = ConnectionBuffers::new();
+    let connection = ConnectionInfo {
+        peer_addr: stream.peer_addr().ok(),
+        local_addr: stream.local_addr().ok(),
+        tls: false,
+        sni: None,
     };
-    let path = request.path.clone();
-    let method = request.method.clone();
-    if let Some(handler) = handlers.get(&(path, method)) {
-        let ret = handler.call(request);
-        stream.write_all(ret.to_response().into_bytes().as_slice())?;
-    } else {
-        stream.write_all(&"no method found".to_response().into_bytes())?;
+    let mut peer_addr = connection.peer_addr;
+    if server.proxy_protocol {
+        match read_proxy_header(&mut stream, &mut buffers.read)? {
+            Some(ProxyHeader::Proxied(addr)) => peer_addr = Some(addr),
+            Some(ProxyHeader::Unknown) => {}
+            None if server.proxy_protocol_fallback => {}
+            None => return Err(invalid_data("missing or malformed PROXY protocol header").into()),
+        }
+    }
+
+    let mut cycle_start = Instant::now();
+    loop {
+        let incoming = read_request(
+            &mut stream,
+            &mut buffers.read,
+            &mut cycle_start,
+            server.max_url_length,
+            server.cycle_timeout,
+        );
+        let start = Instant::now();
+        let len = match incoming {
+            Ok(Incoming::Request(len)) => len,
+            Ok(Incoming::Closed) => break,
+            Ok(Incoming::TargetTooLong) => {
+                let (request, response) = server.reject_long_target(&buffers.read, peer_addr);
+                return answer_early(server, stream, &request, &response, start);
+            }
+            Ok(Incoming::TimedOut) => {
+                let request = server.stand_in_request(&buffers.read, peer_addr);
+                let response = server.answer_early(
+                    &request,
+                    HttpStatus::SERVICE_UNAVAILABLE,
+                    "request timed out",
+                );
+                return answer_early(server, stream, &request, &response, start);
+            }
+            Err(err) => {
+                return reject_malformed(server, &mut stream, err, &buffers.read, peer_addr);
+            }
+        };
+        let raw = &buffers.read[..len];
+        let mut request = match parse_request(raw) {
+            Ok(request) => request,
+            Err(err) => {
+                return reject_malformed(server, &mut stream, err, &buffers.read, peer_addr);
+            }
+        };
+        request.peer_addr = peer_addr;
+        request.extensions.insert(connection.clone());
+        let keep_alive = wants_keep_alive(raw, &request);
+        let http_1_0 = is_http_1_0(raw);
+        buffers.read.drain(..len);
+
+        // the handler takes ownership of the request, so keep a copy around for the logger
+        let logged_request = server.logger.as_ref().map(|_| request.clone());
+        let cycle = match (server.cycle_timeout, &server.watchdog) {
+            (Some(limit), Some(watchdog)) => Some(Cycle::watch(
+                server,
+                watchdog,
+                &stream,
+                &request,
+                cycle_start + limit,
+            )?),
+            _ => None,
+        };
+        let response = server.handle(request);
+        // the watchdog answered in the meantime and closed the connection
+        if cycle.is_some_and(|cycle| !cycle.finish()) {
+            return Ok(());
+        }
+        let response = server.frame_response(for_version(response, http_1_0), keep_alive);
+
+        if let Some(limit) = server.cycle_timeout {
+            let remaining = limit.saturating_sub(cycle_start.elapsed());
+            stream.set_write_timeout(Some(remaining.max(Duration::from_millis(1))))?;
+        }
+        write_response(&mut stream, &response, &mut buffers.write)?;
+        if let (Some(logger), Some(request)) = (&server.logger, logged_request) {
+            logger.log_request(&request, &response, start.elapsed());
+        }
+        buffers.reset();
+
+        if let Some(upgrade) = response.upgrade.as_ref().and_then(Upgrade::take) {
+            stream.set_read_timeout(None)?;
+            upgrade(Upgraded {
+                stream,
+                buffered: std::mem::take(&mut buffers.read),
+            });
+            return Ok(());
+        }
+        if !keep_alive {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Writes and logs a response [`HttpServer::answer_early`] made for a request that wasn't read
+/// in full, then closes the connection
+fn answer_early(
+    server: &HttpServer,
+    mut stream: TcpStream,
+    request: &HttpRequest,
+    response: &HttpResponse,
+    start: Instant,
+) -> Result<(), ServerError> {
+    write_response(&mut stream, response, &mut Vec::new())?;
+    if let Some(logger) = &server.logger {
+        logger.log_request(request, response, start.elapsed());
     }
+    linger(&mut stream);
+    Ok(())
+}
+
+/// Answers a request that couldn't be read or parsed with `400 Bad Request` and closes the
+/// connection, errors of the connection itself are passed on
+fn reject_malformed(
+    server: &HttpServer,
+    stream: &mut impl Write,
+    err: ServerError,
+    raw: &[u8],
+    peer_addr: Option<SocketAddr>,
+) -> Result<(), ServerError> {
+    let Some(response) = server.reject_malformed(raw, peer_addr, &err) else {
+        return Err(err);
+    };
+    write_response(stream, &response, &mut Vec::new())?;
     Ok(())
 }
+
+/// The span [`HttpServer::handle`] runs a request in, kept in the request's extensions until
+/// the route is known
+#[cfg(feature = "tracing")]
+struct RequestSpan(tracing::Span);
+
+/// Opens the span for `request`, see [`HttpServer::with_slow_request_threshold`]
+#[cfg(feature = "tracing")]
+fn start_span(mut request: HttpRequest) -> (HttpRequest, tracing::Span, Instant) {
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method_str(),
+        path = %request.path,
+        peer = request.peer_addr.map(tracing::field::display),
+        route = tracing::field::Empty,
+        status = tracing::field::Empty,
+    );
+    request.extensions.insert(RequestSpan(span.clone()));
+    (request, span, Instant::now())
+}
+
+/// Boxes up the middleware given to [`HttpServer::route_with_middleware`] and friends
+fn route_middleware<M, R>(middleware: impl IntoIterator<Item = M>) -> Vec<RouteMiddlewareFn>
+where
+    M: Fn(HttpRequest) -> R + Send + Sync + 'static,
+    R: Into<MiddlewareResult>,
+{
+    middleware
+        .into_iter()
+        .map(|f| Arc::new(move |req| f(req).into()) as RouteMiddlewareFn)
+        .collect()
+}
+
+/// The pattern a request was routed by, for the [`metrics`] label
+#[derive(Clone, Default)]
+struct MatchedRoute(Arc<OnceLock<String>>);
+
+/// Notes the pattern `request` was routed by for the metrics and on its span
+fn record_route(request: &HttpRequest, pattern: &str) {
+    if let Some(MatchedRoute(route)) = request.extensions.get() {
+        _ = route.set(pattern.to_owned());
+    }
+    #[cfg(feature = "tracing")]
+    if let Some(RequestSpan(span)) = request.extensions.get() {
+        span.record("route", tracing::field::display(pattern));
+    }
+}
+
+/// The message a panic was started with, `panic!` payloads are either of the two string types
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// Parses a full request as read by [`read_request`], undoing chunked transfer encoding
+pub(crate) fn parse_request(raw: &[u8]) -> Result<HttpRequest, ServerError> {
+    let mut request = HttpRequest::try_from(raw)?;
+    if is_chunked(&request) && request.body.is_some() {
+        let head_len = request::head_len(raw).unwrap_or(raw.len());
+        request.body = Some(parser::dechunk(&raw[head_len..])?).filter(|b| !b.is_empty());
+    }
+    Ok(request)
+}
+
+/// A request being handled under [`HttpServer::with_cycle_timeout`], answered by whoever gets
+/// to it first: the connection with the handler's response or the watchdog with a `503`
+struct Cycle(Mutex<Option<(TcpStream, HttpRequest)>>);
+
+impl Cycle {
+    /// Hands the request to the watchdog until [`Cycle::finish`], only the head of the request
+    /// is kept for the response middleware and logger
+    fn watch(
+        server: &Arc<HttpServer>,
+        watchdog: &mpsc::Sender<Watched>,
+        stream: &TcpStream,
+        request: &HttpRequest,
+        deadline: Instant,
+    ) -> std::io::Result<Arc<Self>> {
+        let stand_in = HttpRequest {
+            method: request.method.clone(),
+            path: request.path.clone(),
+            headers: request.headers.clone(),
+            body: None,
+            query: request.query.clone(),
+            raw_query: request.raw_query.clone(),
+            peer_addr: request.peer_addr,
+            extensions: request.extensions.clone(),
+        };
+        let cycle = Arc::new(Self(Mutex::new(Some((stream.try_clone()?, stand_in)))));
+        // the watchdog only stops with the server, which we're holding on to
+        _ = watchdog.send(Watched {
+            deadline,
+            started: Instant::now(),
+            cycle: cycle.clone(),
+            server: server.clone(),
+        });
+        Ok(cycle)
+    }
+
+    /// Claims the request for the handler's response, `false` if the watchdog already answered
+    fn finish(&self) -> bool {
+        self.take().is_some()
+    }
+
+    fn take(&self) -> Option<(TcpStream, HttpRequest)> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).take()
+    }
+}
+
+/// A [`Cycle`] the watchdog answers once `deadline` passes
+struct Watched {
+    deadline: Instant,
+    /// When the handler got the request, for the logger
+    started: Instant,
+    cycle: Arc<Cycle>,
+    server: Arc<HttpServer>,
+}
+
+impl Watched {
+    /// Sends the `503` if the handler hasn't answered yet and closes the connection, the
+    /// handler keeps running and its response is dropped
+    fn expire(self) {
+        let Some((mut stream, request)) = self.cycle.take() else {
+            return;
+        };
+        let server = &self.server;
+        let response = server.answer_early(
+            &request,
+            HttpStatus::SERVICE_UNAVAILABLE,
+            "request timed out",
+        );
+        _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+        _ = write_response(&mut stream, &response, &mut Vec::new());
+        _ = stream.shutdown(std::net::Shutdown::Both);
+        if let Some(logger) = &server.logger {
+            logger.log_request(&request, &response, self.started.elapsed());
+        }
+    }
+}
+
+/// The watchdog thread of [`HttpServer::with_cycle_timeout`], one for the whole server that
+/// sleeps until the earliest deadline of the requests being handled
+fn watch(receiver: &mpsc::Receiver<Watched>) {
+    let mut watched: Vec<Watched> = Vec::new();
+    loop {
+        let next = watched.iter().map(|w| w.deadline).min();
+        let job = match next {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => receiver
+                .recv()
+                .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match job {
+            Ok(w) => watched.push(w),
+            Err(mpsc::RecvTimeoutError::Disconnected) if watched.is_empty() => return,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                std::thread::sleep(next.map_or(Duration::ZERO, |deadline| {
+                    deadline.saturating_duration_since(Instant::now())
+                }));
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+        let now = Instant::now();
+        let (expired, pending) = std::mem::take(&mut watched)
+            .into_iter()
+            .partition(|w| w.deadline <= now);
+        watched = pending;
+        for w in expired {
+            w.expire();
+        }
+        // requests answered in time don't need to be woken up for
+        watched.retain(|w| {
+            w.cycle
+                .0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .is_some()
+        });
+    }
+}
+
+/// Serializes `response` into `buf` and sends it off in one go
+///
+/// [`HttpResponse::write_to`] writes piece by piece, going through the connection's reused
+/// buffer keeps that down to a single write on the socket. The stream still gets flushed in case
+/// it is ever wrapped in a `BufWriter`
+fn write_response(
+    stream: &mut impl Write,
+    response: &HttpResponse,
+    buf: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    response.write_to(buf)?;
+    stream.write_all(buf)?;
+    stream.flush()
+}
+
+/// Interim response telling a client that sent `Expect: 100-continue` to send its body
+const CONTINUE: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n";
+
+/// What [`read_request`] found on the connection
+enum Incoming {
+    /// A full request of this many bytes is at the start of the buffer
+    Request(usize),
+    /// The client went away or idled out in between requests
+    Closed,
+    /// The request target is longer than allowed, the request wasn't read any further
+    TargetTooLong,
+    /// The cycle timeout ran out before the whole request was there
+    TimedOut,
+}
+
+/// A connection [`read_request`] can read from, the read timeout is shortened to what is left
+/// of the cycle timeout
+pub(crate) trait Transport: Read + Write {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()>;
+}
+
+/// A request already in memory, what is written back (`100 Continue`) is dropped
+struct Replay<'a>(&'a [u8]);
+
+impl Read for Replay<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Replay<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for Replay<'_> {
+    fn set_read_timeout(&mut self, _: Option<Duration>) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for TcpStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+/// Reads the next full request (head plus `Content-Length` or chunked body) into `buf` and
+/// returns its length, leaving any pipelined bytes after it in the buffer
+///
+/// The request target is checked against `max_target_len` as soon as it comes in. `started`
+/// is set to when the first byte of the request was there, the whole request has to be there
+/// within `cycle` of that
+fn read_request(
+    stream: &mut impl Transport,
+    buf: &mut Vec<u8>,
+    started: &mut Instant,
+    max_target_len: usize,
+    cycle: Option<Duration>,
+) -> Result<Incoming, ServerError> {
+    // pipelined requests are already (partly) here
+    if !buf.is_empty() {
+        *started = Instant::now();
+    } else if cycle.is_some() {
+        // the last request may have shortened it
+        stream.set_read_timeout(Some(KEEP_ALIVE_TIMEOUT))?;
+    }
+    let deadline = |buf: &[u8], started: Instant| {
+        cycle
+            .filter(|_| !buf.is_empty())
+            .map(|limit| started + limit)
+    };
+    let head_len = loop {
+        if request_target_len(buf) > max_target_len {
+            return Ok(Incoming::TargetTooLong);
+        }
+        if let Some(head_len) = request::head_len(buf) {
+            break head_len;
+        }
+        // the request line may be as long as the target allows, the head limit is on top of it
+        let line_len = buf
+            .iter()
+            .position(|&b| b == b'\n')
+            .unwrap_or(max_target_len);
+        if buf.len() > MAX_HEAD_SIZE.saturating_add(line_len) {
+            return Err(invalid_data("request head too large").into());
+        }
+        let Some(filled) = fill_until(stream, buf, deadline(buf, *started)).transpose() else {
+            return Ok(Incoming::TimedOut);
+        };
+        match filled {
+            Ok(0) if buf.is_empty() => return Ok(Incoming::Closed),
+            Ok(0) => return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into()),
+            Ok(n) if n == buf.len() => *started = Instant::now(),
+            Ok(_) => {}
+            Err(e)
+                if buf.is_empty()
+                    && matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                return Ok(Incoming::Closed);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    // the client holds the body back until we tell it to go ahead, right before we'd wait for it
+    // http/1.0 clients can't ask for it, the expectation is ignored (RFC 9110 10.1.1)
+    let mut continue_pending = head_header(&buf[..head_len], "expect")?
+        .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+        && !is_http_1_0(&buf[..head_len]);
+
+    // chunked bodies don't say how long they are up front, so keep reading until the last chunk
+    if let Some(codings) = head_header(&buf[..head_len], "transfer-encoding")? {
+        // without chunked last there is no telling where the body ends, guessing from a
+        // `Content-Length` is how requests get smuggled past proxies (RFC 9112 6.3)
+        if !parser::ends_in_chunked(codings) {
+            return Err(invalid_data("Transfer-Encoding doesn't end in chunked").into());
+        }
+        loop {
+            if let Some(body_len) = parser::chunked_len(&buf[head_len..])? {
+                return Ok(Incoming::Request(head_len + body_len));
+            }
+            if buf.len() - head_len > MAX_BODY_SIZE {
+                return Err(invalid_data("request body too large").into());
+            }
+            if std::mem::take(&mut continue_pending) {
+                stream.write_all(CONTINUE)?;
+            }
+            match fill_until(stream, buf, deadline(buf, *started))? {
+                Some(0) => return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into()),
+                Some(_) => {}
+                None => return Ok(Incoming::TimedOut),
+            }
+        }
+    }
+
+    let content_length = content_length(&buf[..head_len])?;
+    if content_length > MAX_BODY_SIZE {
+        return Err(invalid_data("request body too large").into());
+    }
+    let total = head_len + content_length;
+    while buf.len() < total {
+        if std::mem::take(&mut continue_pending) {
+            stream.write_all(CONTINUE)?;
+        }
+        match fill_until(stream, buf, deadline(buf, *started))? {
+            Some(0) => return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into()),
+            Some(_) => {}
+            None => return Ok(Incoming::TimedOut),
+        }
+    }
+    Ok(Incoming::Request(total))
+}
+
+/// Most bytes [`linger`] reads before giving up on the client
+const LINGER_LIMIT: usize = 64 * 1024;
+
+/// Reads and drops what the client is still sending before closing a connection that was
+/// answered early, closing with unread bytes around would reset the connection and could take
+/// the response with it
+fn linger(stream: &mut TcpStream) {
+    _ = stream.shutdown(std::net::Shutdown::Write);
+    _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+    _ = std::io::copy(
+        &mut (&*stream).take(LINGER_LIMIT as u64),
+        &mut std::io::sink(),
+    );
+}
+
+/// Longest possible PROXY protocol v1 line, including the trailing `\r\n`
+const MAX_PROXY_HEADER_SIZE: usize = 107;
+
+enum ProxyHeader {
+    /// The client address the proxy saw
+    Proxied(SocketAddr),
+    /// `PROXY UNKNOWN`, the proxy doesn't know the client so the socket's peer is used
+    Unknown,
+}
+
+/// Reads and consumes a PROXY protocol v1 line from the start of the connection
+///
+/// Returns `None` without consuming anything if the connection doesn't start with a valid one
+fn read_proxy_header(
+    stream: &mut impl Read,
+    buf: &mut Vec<u8>,
+) -> Result<Option<ProxyHeader>, ServerError> {
+    let line_len = loop {
+        if !buf.starts_with(&b"PROXY "[..buf.len().min(6)]) {
+            return Ok(None);
+        }
+        if let Some(pos) = buf.windows(2).position(|w| w == b"\r\n") {
+            break pos + 2;
+        }
+        if buf.len() >= MAX_PROXY_HEADER_SIZE || fill(stream, buf)? == 0 {
+            return Ok(None);
+        }
+    };
+    let header = from_utf8(&buf[..line_len])
+        .ok()
+        .and_then(parse_proxy_header);
+    if header.is_some() {
+        buf.drain(..line_len);
+    }
+    Ok(header)
+}
+
+/// Parses `PROXY TCP4 <src> <dst> <src port> <dst port>\r\n` (or `TCP6`/`UNKNOWN`)
+fn parse_proxy_header(line: &str) -> Option<ProxyHeader> {
+    let mut parts = line.strip_suffix("\r\n")?.split(' ');
+    if parts.next()? != "PROXY" {
+        return None;
+    }
+    let is_v4 = match parts.next()? {
+        "TCP4" => true,
+        "TCP6" => false,
+        "UNKNOWN" => return Some(ProxyHeader::Unknown),
+        _ => return None,
+    };
+    let src: IpAddr = parts.next()?.parse().ok()?;
+    let _dst: IpAddr = parts.next()?.parse().ok()?;
+    let src_port: u16 = parts.next()?.parse().ok()?;
+    let _dst_port: u16 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || src.is_ipv4() != is_v4 {
+        return None;
+    }
+    Some(ProxyHeader::Proxied(SocketAddr::new(src, src_port)))
+}
+
+/// Read whatever is available from the stream and append it to `buf`
+fn fill(stream: &mut impl Read, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+    let mut chunk = [0; 4096];
+    let n = stream.read(&mut chunk)?;
+    buf.extend_from_slice(&chunk[..n]);
+    Ok(n)
+}
+
+/// [`fill`] that gives up once `deadline` passes, `None` if it did
+fn fill_until(
+    stream: &mut impl Transport,
+    buf: &mut Vec<u8>,
+    deadline: Option<Instant>,
+) -> std::io::Result<Option<usize>> {
+    let Some(deadline) = deadline else {
+        return fill(stream, buf).map(Some);
+    };
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Ok(None);
+    }
+    stream.set_read_timeout(Some(remaining.min(KEEP_ALIVE_TIMEOUT)))?;
+    match fill(stream, buf) {
+        Err(e)
+            if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+                && Instant::now() >= deadline =>
+        {
+            Ok(None)
+        }
+        filled => filled.map(Some),
+    }
+}
+
+/// Looks up a header in a raw request head before it has been parsed
+fn head_header<'a>(head: &'a [u8], name: &str) -> Result<Option<&'a str>, ServerError> {
+    Ok(from_utf8(head)?.lines().skip(1).find_map(|line| {
+        line.split_once(':')
+            .filter(|(k, _)| k.trim().eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.trim())
+    }))
+}
+
+/// Looks for the `Content-Length` header in the raw request head, defaulting to no body
+fn content_length(head: &[u8]) -> Result<usize, ServerError> {
+    let Some(value) = head_header(head, "content-length")? else {
+        return Ok(0);
+    };
+    value
+        .parse()
+        .map_err(|_| invalid_data("invalid Content-Length header").into())
+}
+
+/// Length of the path and query in the request line, without parsing or allocating anything
+fn request_target_len(raw: &[u8]) -> usize {
+    let line_end = raw.iter().position(|&b| b == b'\n').unwrap_or(raw.len());
+    raw[..line_end]
+        .split(|&b| b == b' ')
+        .nth(1)
+        .map_or(0, <[u8]>::len)
+}
+
+/// Whether the body was sent with `Transfer-Encoding: chunked`
+fn is_chunked(request: &HttpRequest) -> bool {
+    request
+        .header("Transfer-Encoding")
+        .is_some_and(parser::ends_in_chunked)
+}
+
+/// Http/1.1 connections stay open unless asked otherwise, http/1.0 ones have to opt in
+pub(crate) fn wants_keep_alive(raw: &[u8], request: &HttpRequest) -> bool {
+    let connection = request
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("connection"))
+        .map(|(_, v)| v.to_ascii_lowercase());
+    match connection.as_deref() {
+        Some("close") => false,
+        Some("keep-alive") => true,
+        _ => !is_http_1_0(raw),
+    }
+}
+
+/// Whether the request line of `raw` ends in `HTTP/1.0`
+fn is_http_1_0(raw: &[u8]) -> bool {
+    raw.split(|&b| b == b'\n')
+        .next()
+        .is_some_and(|line| line.trim_ascii_end().ends_with(b"HTTP/1.0"))
+}
+
+/// Drops the interim responses for http/1.0 clients, which don't know `1xx` and could take one
+/// for the final response (RFC 9110 15.2)
+fn for_version(mut response: HttpResponse, http_1_0: bool) -> HttpResponse {
+    if http_1_0 {
+        response.interim.clear();
+    }
+    response
+}
+
+fn invalid_data(msg: &str) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, msg)
+}
+
 #[derive(Debug)]
 pub enum ServerError {
     Utf8Conversion(Utf8Error),
     IoError(std::io::Error),
+    RequestParse(RequestParseError),
+    ChunkParse(ChunkParseError),
+    /// The server wasn't started, see [`HttpServer::validate`]
+    InvalidRoutes(Vec<RouteValidationError>),
+}
+
+/// A problem with a registered route, see [`HttpServer::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteValidationError {
+    EmptyPath,
+    /// The path, which can never match since request paths start with `/`
+    MissingLeadingSlash(String),
+    /// The path and the method registered more than once for it
+    DuplicateRoute(String, HttpMethod),
+    /// The path and what is wrong with it
+    InvalidPattern(String, String),
+    /// A state middleware wants state of this type, but none or a different one was set
+    MissingState(&'static str),
+}
+
+impl std::fmt::Display for RouteValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouteValidationError::EmptyPath => f.write_str("route with an empty path"),
+            RouteValidationError::MissingLeadingSlash(path) => {
+                write!(f, "route path {path:?} doesn't start with `/`")
+            }
+            RouteValidationError::DuplicateRoute(path, method) => {
+                write!(f, "{} {path} is registered more than once", method.as_str())
+            }
+            RouteValidationError::InvalidPattern(path, reason) => {
+                write!(f, "invalid route pattern {path:?}: {reason}")
+            }
+            RouteValidationError::MissingState(name) => {
+                write!(f, "state middleware needs state of type {name}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RouteValidationError {}
+
+impl From<ChunkParseError> for ServerError {
+    fn from(value: ChunkParseError) -> Self {
+        Self::ChunkParse(value)
+    }
+}
+
+impl From<RequestParseError> for ServerError {
+    fn from(value: RequestParseError) -> Self {
+        Self::RequestParse(value)
+    }
 }
 
 impl From<Utf8Error> for ServerError {
@@ -17,19 +17,148 @@ pub enum HttpStatus {
     ClientError(ClientErrorResponse),
     /// Status range 5xx - See `ServerErrorResponse` for more info
     ServerError(ServerErrorResponse),
+    /// Any other code, with its own reason phrase - See `CustomStatus` for more info
+    Custom(CustomStatus),
 }
 
 #[allow(unused)]
 impl HttpStatus {
     pub const INTERNAL_SERVER_ERROR: Self =
         Self::ServerError(ServerErrorResponse::InternalServerError);
+    pub const NOT_IMPLEMENTED: Self = Self::ServerError(ServerErrorResponse::NotImplemented);
+    pub const SERVICE_UNAVAILABLE: Self =
+        Self::ServerError(ServerErrorResponse::ServiceUnavailable);
+    pub const GATEWAY_TIMEOUT: Self = Self::ServerError(ServerErrorResponse::GatewayTimeout);
     pub const UNAUTHORIZED: Self = Self::ClientError(ClientErrorResponse::Unauthorized);
     pub const NOT_FOUND: Self = Self::ClientError(ClientErrorResponse::NotFound);
+    pub const METHOD_NOT_ALLOWED: Self = Self::ClientError(ClientErrorResponse::MethodNotAllowed);
     pub const FORBIDDEN: Self = Self::ClientError(ClientErrorResponse::Forbidden);
     pub const BAD_REQUEST: Self = Self::ClientError(ClientErrorResponse::BadRequest);
     pub const TOO_MANY_REQUESTS: Self = Self::ClientError(ClientErrorResponse::TooManyRequests);
+    pub const EARLY_HINTS: Self = Self::Informational(InformationalResponse::EarlyHints);
     pub const OK: Self = Self::Success(SuccessResponse::OK);
+    pub const CREATED: Self = Self::Success(SuccessResponse::Created);
+    pub const NO_CONTENT: Self = Self::Success(SuccessResponse::NoContent);
     pub const MOVED_PERMANENTLY: Self = Self::Redirection(RedirectionResponse::MovedPermanently);
+    pub const NOT_MODIFIED: Self = Self::Redirection(RedirectionResponse::NotModified);
+
+    /// The numeric status code
+    ///
+    /// ```rust
+    /// use torus_http::status::HttpStatus;
+    /// assert_eq!(HttpStatus::NOT_FOUND.code(), 404);
+    /// assert_eq!(HttpStatus::OK.code(), 200);
+    /// ```
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        match self {
+            HttpStatus::Informational(status) => status.clone() as u16,
+            HttpStatus::Success(status) => status.clone() as u16,
+            HttpStatus::Redirection(status) => status.clone() as u16,
+            HttpStatus::ClientError(status) => status.clone() as u16,
+            HttpStatus::ServerError(status) => status.clone() as u16,
+            HttpStatus::Custom(status) => status.code,
+        }
+    }
+
+    /// The reason phrase, without the code
+    ///
+    /// ```rust
+    /// use torus_http::status::{ClientErrorResponse, CustomStatus, HttpStatus};
+    /// assert_eq!(HttpStatus::OK.reason_phrase(), "OK");
+    /// assert_eq!(HttpStatus::NOT_FOUND.reason_phrase(), "Not Found");
+    /// assert_eq!(HttpStatus::from(ClientErrorResponse::ImATeapot).reason_phrase(), "I'm a teapot");
+    /// assert_eq!(HttpStatus::from(CustomStatus::new(299, "Fine").unwrap()).reason_phrase(), "Fine");
+    /// ```
+    #[must_use]
+    pub fn reason_phrase(&self) -> &str {
+        match self {
+            HttpStatus::Informational(status) => status.reason_phrase(),
+            HttpStatus::Success(status) => status.reason_phrase(),
+            HttpStatus::Redirection(status) => status.reason_phrase(),
+            HttpStatus::ClientError(status) => status.reason_phrase(),
+            HttpStatus::ServerError(status) => status.reason_phrase(),
+            HttpStatus::Custom(status) => &status.reason,
+        }
+    }
+
+    /// The status for a numeric code, `None` for codes torus doesn't know, which need a
+    /// [`CustomStatus`]
+    ///
+    /// ```rust
+    /// use torus_http::status::HttpStatus;
+    /// assert_eq!(HttpStatus::from_code(404), Some(HttpStatus::NOT_FOUND));
+    /// assert_eq!(HttpStatus::from_code(299), None);
+    /// ```
+    #[must_use]
+    pub const fn from_code(code: u16) -> Option<Self> {
+        Some(match code {
+            100 => Self::Informational(InformationalResponse::Continue),
+            101 => Self::Informational(InformationalResponse::SwitchingProtocols),
+            102 => Self::Informational(InformationalResponse::Processing),
+            103 => Self::Informational(InformationalResponse::EarlyHints),
+            200 => Self::Success(SuccessResponse::OK),
+            201 => Self::Success(SuccessResponse::Created),
+            202 => Self::Success(SuccessResponse::Accepted),
+            203 => Self::Success(SuccessResponse::NonAuthoritativeInformation),
+            204 => Self::Success(SuccessResponse::NoContent),
+            205 => Self::Success(SuccessResponse::ResetContent),
+            206 => Self::Success(SuccessResponse::PartialContent),
+            207 => Self::Success(SuccessResponse::MultiStatus),
+            208 => Self::Success(SuccessResponse::AlreadyReported),
+            226 => Self::Success(SuccessResponse::IMUsed),
+            300 => Self::Redirection(RedirectionResponse::MultipleChoices),
+            301 => Self::Redirection(RedirectionResponse::MovedPermanently),
+            302 => Self::Redirection(RedirectionResponse::Found),
+            303 => Self::Redirection(RedirectionResponse::SeeOther),
+            304 => Self::Redirection(RedirectionResponse::NotModified),
+            305 => Self::Redirection(RedirectionResponse::UseProxy),
+            306 => Self::Redirection(RedirectionResponse::SwitchProxy),
+            307 => Self::Redirection(RedirectionResponse::TemporaryRedirect),
+            308 => Self::Redirection(RedirectionResponse::PermanentRedirect),
+            400 => Self::ClientError(ClientErrorResponse::BadRequest),
+            401 => Self::ClientError(ClientErrorResponse::Unauthorized),
+            402 => Self::ClientError(ClientErrorResponse::PaymentRequired),
+            403 => Self::ClientError(ClientErrorResponse::Forbidden),
+            404 => Self::ClientError(ClientErrorResponse::NotFound),
+            405 => Self::ClientError(ClientErrorResponse::MethodNotAllowed),
+            406 => Self::ClientError(ClientErrorResponse::NotAcceptable),
+            407 => Self::ClientError(ClientErrorResponse::ProxyAuthenticationRequired),
+            408 => Self::ClientError(ClientErrorResponse::RequestTimeout),
+            409 => Self::ClientError(ClientErrorResponse::Conflict),
+            410 => Self::ClientError(ClientErrorResponse::Gone),
+            411 => Self::ClientError(ClientErrorResponse::LengthRequired),
+            412 => Self::ClientError(ClientErrorResponse::PreconditionFailed),
+            413 => Self::ClientError(ClientErrorResponse::PayloadTooLarge),
+            414 => Self::ClientError(ClientErrorResponse::URITooLong),
+            415 => Self::ClientError(ClientErrorResponse::UnsupportedMediaType),
+            416 => Self::ClientError(ClientErrorResponse::RangeNotSatisfiable),
+            417 => Self::ClientError(ClientErrorResponse::ExpectationFailed),
+            418 => Self::ClientError(ClientErrorResponse::ImATeapot),
+            421 => Self::ClientError(ClientErrorResponse::MisdirectedRequest),
+            422 => Self::ClientError(ClientErrorResponse::UnprocessableContent),
+            423 => Self::ClientError(ClientErrorResponse::Locked),
+            424 => Self::ClientError(ClientErrorResponse::FailedDependency),
+            425 => Self::ClientError(ClientErrorResponse::TooEarly),
+            426 => Self::ClientError(ClientErrorResponse::UpgradeRequired),
+            428 => Self::ClientError(ClientErrorResponse::PreconditionRequired),
+            429 => Self::ClientError(ClientErrorResponse::TooManyRequests),
+            431 => Self::ClientError(ClientErrorResponse::RequestHeaderFieldsTooLarge),
+            451 => Self::ClientError(ClientErrorResponse::UnavailableForLegalReasons),
+            500 => Self::ServerError(ServerErrorResponse::InternalServerError),
+            501 => Self::ServerError(ServerErrorResponse::NotImplemented),
+            502 => Self::ServerError(ServerErrorResponse::BadGateway),
+            503 => Self::ServerError(ServerErrorResponse::ServiceUnavailable),
+            504 => Self::ServerError(ServerErrorResponse::GatewayTimeout),
+            505 => Self::ServerError(ServerErrorResponse::HTTPVersionNotSupported),
+            506 => Self::ServerError(ServerErrorResponse::VariantAlsoNegotiates),
+            507 => Self::ServerError(ServerErrorResponse::InsufficientStorage),
+            508 => Self::ServerError(ServerErrorResponse::LoopDetected),
+            510 => Self::ServerError(ServerErrorResponse::NotExtended),
+            511 => Self::ServerError(ServerErrorResponse::NetworkAuthenticationRequired),
+            _ => return None,
+        })
+    }
 }
 
 impl Default for HttpStatus {
@@ -44,6 +173,8 @@ impl Default for HttpStatus {
 ///
 /// an `InformationalResponse` (`1xx`) indicates that the request was received and understood and is being processed. It alerts the client to wait for a final response. The message does not contain a body. As the `HTTP/1.0` standard did not define any `1xx` status codes, servers must not send a `1xx` response to an `HTTP/1.0` compliant client except under experimental conditions.
 pub enum InformationalResponse {
+    /// Status 100, sent by the server on its own to clients waiting with `Expect: 100-continue`
+    Continue = 100,
     /// Status 101
     SwitchingProtocols = 101,
     Processing = 102,
@@ -138,237 +269,294 @@ pub enum ServerErrorResponse {
     NetworkAuthenticationRequired = 511,
 }
 
+/// A status code torus has no variant for, or a known one with a reason phrase of its own
+///
+/// Responses parsed with [`HttpResponse::from_bytes`](crate::response::HttpResponse::from_bytes)
+/// keep unknown codes this way
+///
+/// ```rust
+/// use torus_http::{prelude::*, status::CustomStatus};
+///
+/// let status: HttpStatus = CustomStatus::new(299, "Fine").unwrap().into();
+/// assert_eq!(status.code(), 299);
+/// assert_eq!(status.to_string(), "299 Fine");
+///
+/// let res = HttpResponse::from_bytes(b"HTTP/1.1 299 Fine\r\n\r\n").unwrap();
+/// assert_eq!(res.status, status);
+///
+/// assert!(CustomStatus::new(99, "Too Low").is_none());
+/// assert!(CustomStatus::new(1000, "Too High").is_none());
+/// assert!(CustomStatus::new(299, "Fine\r\nSet-Cookie: a=b").is_none());
+/// ```
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Debug)]
+pub struct CustomStatus {
+    code: u16,
+    reason: String,
+}
+
+impl CustomStatus {
+    /// `None` unless `code` has three digits and `reason` fits a status line, no control
+    /// characters other than tabs
+    #[must_use]
+    pub fn new(code: u16, reason: impl Into<String>) -> Option<Self> {
+        let reason = reason.into();
+        let fits =
+            (100..=999).contains(&code) && reason.chars().all(|c| c == '\t' || !c.is_control());
+        fits.then_some(Self { code, reason })
+    }
+
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    #[must_use]
+    pub fn reason_phrase(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl Display for CustomStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.code, self.reason)
+    }
+}
+
+/// `code reason`, the way it appears in the status line
+///
+/// ```rust
+/// use torus_http::status::HttpStatus;
+/// assert_eq!(HttpStatus::NOT_FOUND.to_string(), "404 Not Found");
+/// assert_eq!(HttpStatus::OK.to_string(), "200 OK");
+/// ```
 impl Display for HttpStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.code(), self.reason_phrase())
+    }
+}
+
+impl ServerErrorResponse {
+    /// The standard reason phrase, like `Service Unavailable` for `503`
+    #[must_use]
+    pub fn reason_phrase(&self) -> &'static str {
         match self {
-            HttpStatus::Informational(informational_response) => {
-                write!(f, "{informational_response}")
-            }
-            HttpStatus::Success(success_response) => write!(f, "{success_response}"),
-            HttpStatus::Redirection(redirection_response) => write!(f, "{redirection_response}"),
-            HttpStatus::ClientError(client_error_response) => write!(f, "{client_error_response}"),
-            HttpStatus::ServerError(server_error_response) => write!(f, "{server_error_response}"),
+            ServerErrorResponse::InternalServerError => "Internal Server Error",
+            ServerErrorResponse::NotImplemented => "Not Implemented",
+            ServerErrorResponse::BadGateway => "Bad Gateway",
+            ServerErrorResponse::ServiceUnavailable => "Service Unavailable",
+            ServerErrorResponse::GatewayTimeout => "Gateway Timeout",
+            ServerErrorResponse::HTTPVersionNotSupported => "HTTP Version Not Supported",
+            ServerErrorResponse::VariantAlsoNegotiates => "Variant Also Negotiates",
+            ServerErrorResponse::InsufficientStorage => "Insufficient Storage",
+            ServerErrorResponse::LoopDetected => "Loop Detected",
+            ServerErrorResponse::NotExtended => "Not Extended",
+            ServerErrorResponse::NetworkAuthenticationRequired => "Network Authentication Required",
         }
     }
 }
 
 impl Display for ServerErrorResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (name, num) = match self {
-            ServerErrorResponse::InternalServerError => (
-                "InternalServerError",
-                ServerErrorResponse::InternalServerError as u16,
-            ),
-            ServerErrorResponse::NotImplemented => {
-                ("NotImplemented", ServerErrorResponse::NotImplemented as u16)
-            }
-            ServerErrorResponse::BadGateway => {
-                ("BadGateway", ServerErrorResponse::BadGateway as u16)
-            }
-            ServerErrorResponse::ServiceUnavailable => (
-                "ServiceUnavailable",
-                ServerErrorResponse::ServiceUnavailable as u16,
-            ),
-            ServerErrorResponse::GatewayTimeout => {
-                ("GatewayTimeout", ServerErrorResponse::GatewayTimeout as u16)
-            }
-            ServerErrorResponse::HTTPVersionNotSupported => (
-                "HTTPVersionNotSupported",
-                ServerErrorResponse::HTTPVersionNotSupported as u16,
-            ),
-            ServerErrorResponse::VariantAlsoNegotiates => (
-                "VariantAlsoNegotiates",
-                ServerErrorResponse::VariantAlsoNegotiates as u16,
-            ),
-            ServerErrorResponse::InsufficientStorage => (
-                "InsufficientStorage",
-                ServerErrorResponse::InsufficientStorage as u16,
-            ),
-            ServerErrorResponse::LoopDetected => {
-                ("LoopDetected", ServerErrorResponse::LoopDetected as u16)
-            }
-            ServerErrorResponse::NotExtended => {
-                ("NotExtended", ServerErrorResponse::NotExtended as u16)
-            }
-            ServerErrorResponse::NetworkAuthenticationRequired => (
-                "NetworkAuthenticationRequired",
-                ServerErrorResponse::NetworkAuthenticationRequired as u16,
-            ),
-        };
-        write!(f, "{num} {name}")
+        write!(f, "{} {}", self.clone() as u16, self.reason_phrase())
+    }
+}
+
+impl ClientErrorResponse {
+    /// The standard reason phrase, like `Not Found` for `404`
+    #[must_use]
+    pub fn reason_phrase(&self) -> &'static str {
+        match self {
+            ClientErrorResponse::BadRequest => "Bad Request",
+            ClientErrorResponse::Unauthorized => "Unauthorized",
+            ClientErrorResponse::PaymentRequired => "Payment Required",
+            ClientErrorResponse::Forbidden => "Forbidden",
+            ClientErrorResponse::NotFound => "Not Found",
+            ClientErrorResponse::MethodNotAllowed => "Method Not Allowed",
+            ClientErrorResponse::NotAcceptable => "Not Acceptable",
+            ClientErrorResponse::ProxyAuthenticationRequired => "Proxy Authentication Required",
+            ClientErrorResponse::RequestTimeout => "Request Timeout",
+            ClientErrorResponse::Conflict => "Conflict",
+            ClientErrorResponse::Gone => "Gone",
+            ClientErrorResponse::LengthRequired => "Length Required",
+            ClientErrorResponse::PreconditionFailed => "Precondition Failed",
+            ClientErrorResponse::PayloadTooLarge => "Payload Too Large",
+            ClientErrorResponse::URITooLong => "URI Too Long",
+            ClientErrorResponse::UnsupportedMediaType => "Unsupported Media Type",
+            ClientErrorResponse::RangeNotSatisfiable => "Range Not Satisfiable",
+            ClientErrorResponse::ExpectationFailed => "Expectation Failed",
+            ClientErrorResponse::ImATeapot => "I'm a teapot",
+            ClientErrorResponse::MisdirectedRequest => "Misdirected Request",
+            ClientErrorResponse::UnprocessableContent => "Unprocessable Content",
+            ClientErrorResponse::Locked => "Locked",
+            ClientErrorResponse::FailedDependency => "Failed Dependency",
+            ClientErrorResponse::TooEarly => "Too Early",
+            ClientErrorResponse::UpgradeRequired => "Upgrade Required",
+            ClientErrorResponse::PreconditionRequired => "Precondition Required",
+            ClientErrorResponse::TooManyRequests => "Too Many Requests",
+            ClientErrorResponse::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
+            ClientErrorResponse::UnavailableForLegalReasons => "Unavailable For Legal Reasons",
+        }
     }
 }
 
 impl Display for ClientErrorResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (name, num) = match self {
-            ClientErrorResponse::BadRequest => {
-                ("BadRequest", ClientErrorResponse::BadRequest as u16)
-            }
-            ClientErrorResponse::Unauthorized => {
-                ("Unauthorized", ClientErrorResponse::Unauthorized as u16)
-            }
-            ClientErrorResponse::PaymentRequired => (
-                "PaymentRequired",
-                ClientErrorResponse::PaymentRequired as u16,
-            ),
-            ClientErrorResponse::Forbidden => ("Forbidden", ClientErrorResponse::Forbidden as u16),
-            ClientErrorResponse::NotFound => ("NotFound", ClientErrorResponse::NotFound as u16),
-            ClientErrorResponse::MethodNotAllowed => (
-                "MethodNotAllowed",
-                ClientErrorResponse::MethodNotAllowed as u16,
-            ),
-            ClientErrorResponse::NotAcceptable => {
-                ("NotAcceptable", ClientErrorResponse::NotAcceptable as u16)
-            }
-            ClientErrorResponse::ProxyAuthenticationRequired => (
-                "ProxyAuthenticationRequired",
-                ClientErrorResponse::ProxyAuthenticationRequired as u16,
-            ),
-            ClientErrorResponse::RequestTimeout => {
-                ("RequestTimeout", ClientErrorResponse::RequestTimeout as u16)
-            }
-            ClientErrorResponse::Conflict => ("Conflict", ClientErrorResponse::Conflict as u16),
-            ClientErrorResponse::Gone => ("Gone", ClientErrorResponse::Gone as u16),
-            ClientErrorResponse::LengthRequired => {
-                ("LengthRequired", ClientErrorResponse::LengthRequired as u16)
-            }
-            ClientErrorResponse::PreconditionFailed => (
-                "PreconditionFailed",
-                ClientErrorResponse::PreconditionFailed as u16,
-            ),
-            ClientErrorResponse::PayloadTooLarge => (
-                "PayloadTooLarge",
-                ClientErrorResponse::PayloadTooLarge as u16,
-            ),
-            ClientErrorResponse::URITooLong => {
-                ("URITooLong", ClientErrorResponse::URITooLong as u16)
-            }
-            ClientErrorResponse::UnsupportedMediaType => (
-                "UnsupportedMediaType",
-                ClientErrorResponse::UnsupportedMediaType as u16,
-            ),
-            ClientErrorResponse::RangeNotSatisfiable => (
-                "RangeNotSatisfiable",
-                ClientErrorResponse::RangeNotSatisfiable as u16,
-            ),
-            ClientErrorResponse::ExpectationFailed => (
-                "ExpectationFailed",
-                ClientErrorResponse::ExpectationFailed as u16,
-            ),
-            ClientErrorResponse::ImATeapot => ("ImATeapot", ClientErrorResponse::ImATeapot as u16),
-            ClientErrorResponse::MisdirectedRequest => (
-                "MisdirectedRequest",
-                ClientErrorResponse::MisdirectedRequest as u16,
-            ),
-            ClientErrorResponse::UnprocessableContent => (
-                "UnprocessableContent",
-                ClientErrorResponse::UnprocessableContent as u16,
-            ),
-            ClientErrorResponse::Locked => ("Locked", ClientErrorResponse::Locked as u16),
-            ClientErrorResponse::FailedDependency => (
-                "FailedDependency",
-                ClientErrorResponse::FailedDependency as u16,
-            ),
-            ClientErrorResponse::TooEarly => ("TooEarly", ClientErrorResponse::TooEarly as u16),
-            ClientErrorResponse::UpgradeRequired => (
-                "UpgradeRequired",
-                ClientErrorResponse::UpgradeRequired as u16,
-            ),
-            ClientErrorResponse::PreconditionRequired => (
-                "PreconditionRequired",
-                ClientErrorResponse::PreconditionRequired as u16,
-            ),
-            ClientErrorResponse::TooManyRequests => (
-                "TooManyRequests",
-                ClientErrorResponse::TooManyRequests as u16,
-            ),
-            ClientErrorResponse::RequestHeaderFieldsTooLarge => (
-                "RequestHeaderFieldsTooLarge",
-                ClientErrorResponse::RequestHeaderFieldsTooLarge as u16,
-            ),
-            ClientErrorResponse::UnavailableForLegalReasons => (
-                "UnavailableForLegalReasons",
-                ClientErrorResponse::UnavailableForLegalReasons as u16,
-            ),
-        };
-        write!(f, "{num} {name}")
+        write!(f, "{} {}", self.clone() as u16, self.reason_phrase())
+    }
+}
+
+impl RedirectionResponse {
+    /// The standard reason phrase, like `Moved Permanently` for `301`
+    #[must_use]
+    pub fn reason_phrase(&self) -> &'static str {
+        match self {
+            RedirectionResponse::MultipleChoices => "Multiple Choices",
+            RedirectionResponse::MovedPermanently => "Moved Permanently",
+            RedirectionResponse::Found => "Found",
+            RedirectionResponse::SeeOther => "See Other",
+            RedirectionResponse::NotModified => "Not Modified",
+            RedirectionResponse::UseProxy => "Use Proxy",
+            RedirectionResponse::SwitchProxy => "Switch Proxy",
+            RedirectionResponse::TemporaryRedirect => "Temporary Redirect",
+            RedirectionResponse::PermanentRedirect => "Permanent Redirect",
+        }
     }
 }
 
 impl Display for RedirectionResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (name, num) = match self {
-            RedirectionResponse::MultipleChoices => (
-                "MultipleChoices",
-                RedirectionResponse::MultipleChoices as u16,
-            ),
-            RedirectionResponse::MovedPermanently => (
-                "MovedPermanently",
-                RedirectionResponse::MovedPermanently as u16,
-            ),
-            RedirectionResponse::Found => ("Found", RedirectionResponse::Found as u16),
-            RedirectionResponse::SeeOther => ("SeeOther", RedirectionResponse::SeeOther as u16),
-            RedirectionResponse::NotModified => {
-                ("NotModified", RedirectionResponse::NotModified as u16)
-            }
-            RedirectionResponse::UseProxy => ("UseProxy", RedirectionResponse::UseProxy as u16),
-            RedirectionResponse::SwitchProxy => {
-                ("SwitchProxy", RedirectionResponse::SwitchProxy as u16)
-            }
-            RedirectionResponse::TemporaryRedirect => (
-                "TemporaryRedirect",
-                RedirectionResponse::TemporaryRedirect as u16,
-            ),
-            RedirectionResponse::PermanentRedirect => (
-                "PermanentRedirect",
-                RedirectionResponse::PermanentRedirect as u16,
-            ),
-        };
-        write!(f, "{num} {name}")
+        write!(f, "{} {}", self.clone() as u16, self.reason_phrase())
+    }
+}
+
+impl SuccessResponse {
+    /// The standard reason phrase, like `No Content` for `204`
+    #[must_use]
+    pub fn reason_phrase(&self) -> &'static str {
+        match self {
+            SuccessResponse::OK => "OK",
+            SuccessResponse::Created => "Created",
+            SuccessResponse::Accepted => "Accepted",
+            SuccessResponse::NonAuthoritativeInformation => "Non-Authoritative Information",
+            SuccessResponse::NoContent => "No Content",
+            SuccessResponse::ResetContent => "Reset Content",
+            SuccessResponse::PartialContent => "Partial Content",
+            SuccessResponse::MultiStatus => "Multi-Status",
+            SuccessResponse::AlreadyReported => "Already Reported",
+            SuccessResponse::IMUsed => "IM Used",
+        }
     }
 }
 
 impl Display for SuccessResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (name, num) = match self {
-            SuccessResponse::OK => ("OK", SuccessResponse::OK as u16),
-            SuccessResponse::Created => ("Created", SuccessResponse::Created as u16),
-            SuccessResponse::Accepted => ("Accepted", SuccessResponse::Accepted as u16),
-            SuccessResponse::NonAuthoritativeInformation => (
-                "NonAuthoritativeInformation",
-                SuccessResponse::NonAuthoritativeInformation as u16,
-            ),
-            SuccessResponse::NoContent => ("NoContent", SuccessResponse::NoContent as u16),
-            SuccessResponse::ResetContent => ("ResetContent", SuccessResponse::ResetContent as u16),
-            SuccessResponse::PartialContent => {
-                ("PartialContent", SuccessResponse::PartialContent as u16)
-            }
-            SuccessResponse::MultiStatus => ("MultiStatus", SuccessResponse::MultiStatus as u16),
-            SuccessResponse::AlreadyReported => {
-                ("AlreadyReported", SuccessResponse::AlreadyReported as u16)
-            }
-            SuccessResponse::IMUsed => ("IMUsed", SuccessResponse::IMUsed as u16),
-        };
-        write!(f, "{num} {name}")
+        write!(f, "{} {}", self.clone() as u16, self.reason_phrase())
+    }
+}
+
+impl InformationalResponse {
+    /// The standard reason phrase, like `Early Hints` for `103`
+    #[must_use]
+    pub fn reason_phrase(&self) -> &'static str {
+        match self {
+            InformationalResponse::Continue => "Continue",
+            InformationalResponse::SwitchingProtocols => "Switching Protocols",
+            InformationalResponse::Processing => "Processing",
+            InformationalResponse::EarlyHints => "Early Hints",
+        }
     }
 }
 
 impl Display for InformationalResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.clone() as u16, self.reason_phrase())
+    }
+}
+
+impl TryFrom<u16> for HttpStatus {
+    type Error = u16;
+
+    /// See [`HttpStatus::from_code`], unknown codes are handed back as the error
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        Self::from_code(code).ok_or(code)
+    }
+}
+
+/// Serialized as the numeric code, a [`CustomStatus`] as `"code reason"` to keep its reason
+/// phrase
+///
+/// Codes [`HttpStatus::from_code`] doesn't know deserialize into a [`CustomStatus`] with an
+/// empty reason phrase
+///
+/// ```rust
+/// use torus_http::status::{ClientErrorResponse, CustomStatus, HttpStatus};
+///
+/// for status in [
+///     HttpStatus::OK,
+///     HttpStatus::NOT_FOUND,
+///     ClientErrorResponse::ImATeapot.into(),
+///     CustomStatus::new(299, "Fine").unwrap().into(),
+///     CustomStatus::new(404, "Nothing Here").unwrap().into(),
+///     CustomStatus::new(299, "").unwrap().into(),
+/// ] {
+///     let json = serde_json::to_string(&status).unwrap();
+///     assert_eq!(serde_json::from_str::<HttpStatus>(&json).unwrap(), status);
+/// }
+/// assert_eq!(serde_json::to_string(&HttpStatus::NOT_FOUND).unwrap(), "404");
+/// assert_eq!(
+///     serde_json::to_string(&HttpStatus::from(CustomStatus::new(299, "Fine").unwrap())).unwrap(),
+///     r#""299 Fine""#
+/// );
+/// assert_eq!(
+///     serde_json::from_str::<HttpStatus>("299").unwrap(),
+///     CustomStatus::new(299, "").unwrap().into()
+/// );
+/// assert!(serde_json::from_str::<HttpStatus>("1000").is_err());
+/// assert!(serde_json::from_str::<HttpStatus>(r#""Fine""#).is_err());
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for HttpStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         match self {
-            InformationalResponse::SwitchingProtocols => write!(
-                f,
-                "{} SwitchingProtocols",
-                InformationalResponse::SwitchingProtocols
-            ),
-            InformationalResponse::Processing => {
-                write!(f, "{} Processing", InformationalResponse::Processing)
+            HttpStatus::Custom(status) => serializer.collect_str(status),
+            _ => serializer.serialize_u16(self.code()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HttpStatus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StatusVisitor;
+
+        impl serde::de::Visitor<'_> for StatusVisitor {
+            type Value = HttpStatus;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a status code, or a code followed by a reason phrase")
             }
-            InformationalResponse::EarlyHints => {
-                write!(f, "{} EarlyHints", InformationalResponse::EarlyHints)
+
+            fn visit_u64<E: serde::de::Error>(self, code: u64) -> Result<HttpStatus, E> {
+                u16::try_from(code)
+                    .ok()
+                    .and_then(|code| {
+                        HttpStatus::from_code(code)
+                            .or_else(|| CustomStatus::new(code, "").map(HttpStatus::Custom))
+                    })
+                    .ok_or_else(|| E::custom(format_args!("invalid http status code {code}")))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, status: &str) -> Result<HttpStatus, E> {
+                let (code, reason) = status.split_once(' ').unwrap_or((status, ""));
+                code.parse()
+                    .ok()
+                    .and_then(|code| CustomStatus::new(code, reason))
+                    .map(HttpStatus::Custom)
+                    .ok_or_else(|| E::custom(format_args!("invalid http status {status:?}")))
             }
         }
+
+        deserializer.deserialize_any(StatusVisitor)
     }
 }
 
@@ -397,3 +585,8 @@ impl From<SuccessResponse> for HttpStatus {
         HttpStatus::Success(val)
     }
 }
+impl From<CustomStatus> for HttpStatus {
+    fn from(val: CustomStatus) -> Self {
+        HttpStatus::Custom(val)
+    }
+}
@@ -0,0 +1,187 @@
+//! Testing routes in process, without opening sockets
+//!
+//! [`TestClient`] turns the requests it builds into raw http and sends them through the same
+//! parsing, middleware, routing and response framing a connection would, so what comes back is
+//! what a client would have been sent. Only the socket is missing, so there is no
+//! [`ConnectionInfo`](crate::request::ConnectionInfo) and no peer address.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use torus_http::{prelude::*, testing::TestClient};
+//!
+//! let client = TestClient::new(
+//!     HttpServer::new()
+//!         .add_response_middleware(|_req, res| res.insert_header("X-Served-By", "torus"))
+//!         .get("/users/1", |req: HttpRequest| {
+//!             if req.accepts("application/json") {
+//!                 HttpResponse::new()
+//!                     .set_body(r#"{"id": 1}"#)
+//!                     .insert_header("Content-Type", "application/json")
+//!             } else {
+//!                 HttpResponse::new().set_body("user 1")
+//!             }
+//!         })
+//!         .post("/echo", |req: HttpRequest| req.body_str().unwrap_or_default().to_owned()),
+//! );
+//!
+//! let res = client.get("/users/1").header("Accept", "application/json").send();
+//! assert_eq!(res.status, HttpStatus::OK);
+//! assert_eq!(res.header("content-type"), Some("application/json"));
+//! assert_eq!(res.header("x-served-by"), Some("torus"));
+//! assert_eq!(res.body_str(), Some(r#"{"id": 1}"#));
+//! assert_eq!(res.header("Content-Length"), Some("9"));
+//!
+//! let res = client.post("/echo").body("hello").send();
+//! assert_eq!(res.body_str(), Some("hello"));
+//!
+//! let res = client.get("/missing").send();
+//! assert_eq!(res.status, HttpStatus::NOT_FOUND);
+//! ```
+use crate::{method::HttpMethod, request, response::HttpResponse, server::HttpServer};
+
+/// Sends requests straight to an [`HttpServer`], see the [module docs](self)
+pub struct TestClient {
+    server: HttpServer,
+}
+
+impl TestClient {
+    /// # Panics
+    ///
+    /// If the routes don't pass [`HttpServer::validate`], unless validation was skipped
+    #[must_use]
+    pub fn new(server: HttpServer) -> Self {
+        if let Err(errors) = server.validate()
+            && !server.skips_validation()
+        {
+            let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            panic!("invalid routes: {}", errors.join(", "));
+        }
+        Self { server }
+    }
+
+    /// The server requests go to
+    #[must_use]
+    pub fn server(&self) -> &HttpServer {
+        &self.server
+    }
+
+    /// Start a request with any method, `path` may include a query string
+    pub fn request(&self, method: HttpMethod, path: impl Into<String>) -> TestRequest<'_> {
+        TestRequest {
+            server: &self.server,
+            method,
+            path: path.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(&self, path: impl Into<String>) -> TestRequest<'_> {
+        self.request(HttpMethod::Get, path)
+    }
+
+    pub fn post(&self, path: impl Into<String>) -> TestRequest<'_> {
+        self.request(HttpMethod::Post, path)
+    }
+
+    pub fn put(&self, path: impl Into<String>) -> TestRequest<'_> {
+        self.request(HttpMethod::Put, path)
+    }
+
+    pub fn patch(&self, path: impl Into<String>) -> TestRequest<'_> {
+        self.request(HttpMethod::Patch, path)
+    }
+
+    pub fn delete(&self, path: impl Into<String>) -> TestRequest<'_> {
+        self.request(HttpMethod::Delete, path)
+    }
+
+    pub fn head(&self, path: impl Into<String>) -> TestRequest<'_> {
+        self.request(HttpMethod::Head, path)
+    }
+
+    pub fn options(&self, path: impl Into<String>) -> TestRequest<'_> {
+        self.request(HttpMethod::Options, path)
+    }
+}
+
+/// A request being built by a [`TestClient`]
+#[must_use = "requests do nothing until they are sent"]
+pub struct TestRequest<'a> {
+    server: &'a HttpServer,
+    method: HttpMethod,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+}
+
+impl TestRequest<'_> {
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set the body, `Content-Length` is filled in unless a header for it or
+    /// `Transfer-Encoding` was given
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// The request as it would go over the wire
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        request::encode_request(
+            &self.method,
+            &self.path,
+            &self.headers,
+            self.body.as_deref(),
+        )
+    }
+
+    /// Run the request through the server and return the response as it would be written to a
+    /// connection
+    ///
+    /// The request is read the way a connection reads it, so a request the server would refuse
+    /// gets the same `400 Bad Request` or `414 URI Too Long`. A body shorter than its
+    /// `Content-Length` or a chunked body without its last chunk, which a connection would keep
+    /// waiting on, is a `400` too
+    ///
+    /// ```rust
+    /// use torus_http::{prelude::*, testing::TestClient};
+    ///
+    /// let client = TestClient::new(HttpServer::new().post("/", |_| "ok"));
+    ///
+    /// let res = client.post("/").header("Transfer-Encoding", "chunked").body("zz\r\n").send();
+    /// assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+    /// assert_eq!(res.header("Connection"), Some("close"));
+    ///
+    /// let res = client.post("/").header("Content-Length", "10").body("short").send();
+    /// assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+    ///
+    /// let res = client.post("/").header("Transfer-Encoding", "gzip").body("x").send();
+    /// assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+    ///
+    /// let res = client.post("/").header("Transfer-Encoding", "chunked").body("2\r\nok\r\n0\r\n\r\n").send();
+    /// assert_eq!(res.status, HttpStatus::OK);
+    ///
+    /// // heads that don't parse are a 400 as well, where a socket would have been sent one
+    /// let res = client.get("/").header("X-Name", "caf\u{e9}").send();
+    /// assert_eq!(res.status, HttpStatus::BAD_REQUEST);
+    ///
+    /// // a `Content-Length` in any spelling is left alone
+    /// let client = TestClient::new(HttpServer::new().get("/", |_| {
+    ///     let mut res = HttpResponse::new().set_body("hi");
+    ///     res.headers.remove("Content-Length");
+    ///     res.insert_header("content-length", "2")
+    /// }));
+    /// let res = client.get("/").send();
+    /// let lengths = res.headers.keys().filter(|k| k.eq_ignore_ascii_case("Content-Length"));
+    /// assert_eq!(lengths.count(), 1);
+    /// ```
+    #[must_use]
+    pub fn send(self) -> HttpResponse {
+        self.server.respond_to_raw(&self.to_bytes())
+    }
+}
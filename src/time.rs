@@ -0,0 +1,319 @@
+//! Http dates, the `Sun, 06 Nov 1994 08:49:37 GMT` format used by `Date`, `Last-Modified` and
+//! friends
+//!
+//! Dates are always sent in that format, but parsing also accepts the two obsolete ones old
+//! clients may still send, see [`parse_http_date`]
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::time::{Duration, UNIX_EPOCH};
+//! use torus_http::time::{format_http_date, parse_http_date};
+//!
+//! let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+//! assert_eq!(format_http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+//! assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Ok(time));
+//! ```
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+/// The weekdays as spelled out by RFC 850 dates
+const LONG_WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Formats `time` as an http date in utc, times before 1970 are clamped to the epoch
+///
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use torus_http::time::format_http_date;
+///
+/// assert_eq!(format_http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+/// let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400);
+/// assert_eq!(format_http_date(leap_day), "Tue, 29 Feb 2000 00:00:00 GMT");
+/// let time = UNIX_EPOCH + Duration::from_secs(1_700_035_951);
+/// assert_eq!(format_http_date(time), "Wed, 15 Nov 2023 08:12:31 GMT");
+/// ```
+#[must_use]
+pub fn format_http_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let days = secs / 86_400;
+    let secs_of_day = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    // the epoch was a thursday
+    let weekday = WEEKDAYS[usize::try_from((days + 3) % 7).unwrap_or_default()];
+    format!(
+        "{weekday}, {day:02} {} {year} {:02}:{:02}:{:02} GMT",
+        MONTHS[usize::try_from(month - 1).unwrap_or_default()],
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
+
+/// `(year, month, day)` of the day `days` after 1970-01-01, months and days counting from 1
+///
+/// Howard Hinnant's `civil_from_days`, shifted so the year starts in march and leap days come
+/// last
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Parses an http date in any of the three formats http has used over time
+///
+/// - IMF-fixdate, the one [`format_http_date`] writes: `Sun, 06 Nov 1994 08:49:37 GMT`
+/// - RFC 850: `Sunday, 06-Nov-94 08:49:37 GMT`, a two digit year more than 50 years in the
+///   future means the century before, see [`parse_http_date_at`]
+/// - asctime: `Sun Nov  6 08:49:37 1994`
+///
+/// Whether the weekday fits the date isn't checked, the date itself has to exist though
+///
+/// # Errors
+///
+/// - `date` is in none of the formats, or names a day or time that doesn't exist
+///
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use torus_http::time::{InvalidHttpDate, format_http_date, parse_http_date};
+///
+/// let at = |secs| Ok(UNIX_EPOCH + Duration::from_secs(secs));
+/// let time = at(784_111_777);
+/// assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), time);
+/// assert_eq!(parse_http_date("Sunday, 06-Nov-1994 08:49:37 GMT"), Err(InvalidHttpDate));
+/// assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), time);
+/// assert_eq!(parse_http_date("Sun Nov 06 08:49:37 1994"), time);
+///
+/// assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), at(0));
+/// assert_eq!(parse_http_date("Thu Jan  1 00:00:00 1970"), at(0));
+/// assert_eq!(parse_http_date("Wed, 31 Dec 1969 23:59:59 GMT"), Ok(UNIX_EPOCH - Duration::from_secs(1)));
+///
+/// // leap years
+/// assert_eq!(parse_http_date("Tue, 29 Feb 2000 00:00:00 GMT"), at(951_782_400));
+/// assert_eq!(parse_http_date("Thu, 29 Feb 2024 12:00:00 GMT"), at(1_709_208_000));
+/// assert_eq!(parse_http_date("Thu Feb 29 12:00:00 2024"), at(1_709_208_000));
+/// assert_eq!(parse_http_date("Thu, 01 Mar 2100 00:00:00 GMT"), at(4_107_542_400));
+/// assert_eq!(parse_http_date("Mon, 29 Feb 2100 00:00:00 GMT"), Err(InvalidHttpDate));
+/// assert_eq!(parse_http_date("Thu, 29 Feb 2001 00:00:00 GMT"), Err(InvalidHttpDate));
+///
+/// for broken in [
+///     "",
+///     "yesterday",
+///     "Sun, 06 Nov 1994 08:49:37 UTC",
+///     "Sun, 6 Nov 1994 08:49:37 GMT",
+///     "Sun, 06 nov 1994 08:49:37 GMT",
+///     "Sun, 06 Nov 94 08:49:37 GMT",
+///     "Sun, 31 Nov 1994 08:49:37 GMT",
+///     "Sun, 06 Nov 1994 24:00:00 GMT",
+///     "Sun, 06 Nov 1994 08:60:00 GMT",
+///     "Sun, 06 Nov 1994 8:49:37 GMT",
+///     "Sun, 06 Nov 1994 08:49:37 GMT extra",
+///     "Sun, 06-Nov-94 08:49:37 GMT",
+///     "Sun Nov  6 08:49:37 94",
+///     "Sun Nov 32 08:49:37 1994",
+///     "Fun, 06 Nov 1994 08:49:37 GMT",
+/// ] {
+///     assert_eq!(parse_http_date(broken), Err(InvalidHttpDate), "{broken}");
+/// }
+///
+/// // whatever gets formatted parses back, down to the second
+/// let mut secs: u64 = 1;
+/// for _ in 0..10_000 {
+///     secs = secs.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1) % 253_402_300_800;
+///     let time = UNIX_EPOCH + Duration::from_secs(secs);
+///     assert_eq!(parse_http_date(&format_http_date(time)), Ok(time), "{secs}");
+/// }
+/// ```
+pub fn parse_http_date(date: &str) -> Result<SystemTime, InvalidHttpDate> {
+    parse_http_date_at(date, SystemTime::now())
+}
+
+/// [`parse_http_date`] as if it was `now`, which only matters for two digit RFC 850 years
+///
+/// They stand for the year with those last two digits that is at most 50 years after `now`
+///
+/// # Errors
+///
+/// - the same as [`parse_http_date`]
+///
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use torus_http::time::parse_http_date_at;
+///
+/// let at = |secs| Ok(UNIX_EPOCH + Duration::from_secs(secs));
+/// // Sun, 06 Nov 1994 08:49:37 GMT
+/// let now = UNIX_EPOCH + Duration::from_secs(784_111_777);
+/// assert_eq!(parse_http_date_at("Sunday, 06-Nov-94 08:49:37 GMT", now), at(784_111_777));
+///
+/// assert_eq!(parse_http_date_at("Tuesday, 01-Jan-85 00:00:00 GMT", now), at(473_385_600));
+/// assert_eq!(parse_http_date_at("Saturday, 01-Jan-00 00:00:00 GMT", now), at(946_684_800));
+/// assert_eq!(parse_http_date_at("Friday, 01-Jan-44 00:00:00 GMT", now), at(2_335_219_200));
+/// assert_eq!(
+///     parse_http_date_at("Monday, 01-Jan-45 00:00:00 GMT", now),
+///     Ok(UNIX_EPOCH - Duration::from_secs(788_918_400))
+/// );
+///
+/// // a century later the same dates mean something else
+/// let now = UNIX_EPOCH + Duration::from_secs(3_913_142_400);
+/// assert_eq!(parse_http_date_at("Friday, 01-Jan-00 00:00:00 GMT", now), at(4_102_444_800));
+/// assert_eq!(parse_http_date_at("Wednesday, 01-Jan-44 00:00:00 GMT", now), at(5_490_892_800));
+/// ```
+pub fn parse_http_date_at(date: &str, now: SystemTime) -> Result<SystemTime, InvalidHttpDate> {
+    let date = date.trim();
+    let (year, month, day, secs_of_day) = parse_imf_fixdate(date)
+        .or_else(|| parse_rfc850(date, now))
+        .or_else(|| parse_asctime(date))
+        .ok_or(InvalidHttpDate)?;
+    if day == 0 || day > days_in_month(year, month) {
+        return Err(InvalidHttpDate);
+    }
+    let secs = days_from_civil(year, month, day) * 86_400 + secs_of_day;
+    let since_epoch = Duration::from_secs(secs.unsigned_abs());
+    if secs >= 0 {
+        UNIX_EPOCH.checked_add(since_epoch)
+    } else {
+        UNIX_EPOCH.checked_sub(since_epoch)
+    }
+    .ok_or(InvalidHttpDate)
+}
+
+/// `(year, month, day, seconds into the day)`, months and days counting from 1
+type DateParts = (i64, i64, i64, i64);
+
+/// `Sun, 06 Nov 1994 08:49:37 GMT`
+fn parse_imf_fixdate(date: &str) -> Option<DateParts> {
+    let (weekday, rest) = date.split_once(", ")?;
+    let mut parts = rest.split(' ');
+    let day = number(parts.next()?, 2)?;
+    let month = month(parts.next()?)?;
+    let year = number(parts.next()?, 4)?;
+    let secs_of_day = time_of_day(parts.next()?)?;
+    let valid = WEEKDAYS.contains(&weekday) && parts.next()? == "GMT" && parts.next().is_none();
+    valid.then_some((year, month, day, secs_of_day))
+}
+
+/// `Sunday, 06-Nov-94 08:49:37 GMT`
+fn parse_rfc850(date: &str, now: SystemTime) -> Option<DateParts> {
+    let (weekday, rest) = date.split_once(", ")?;
+    let mut parts = rest.split(' ');
+    let mut day_month_year = parts.next()?.split('-');
+    let day = number(day_month_year.next()?, 2)?;
+    let month = month(day_month_year.next()?)?;
+    let year = full_year(number(day_month_year.next()?, 2)?, now);
+    let secs_of_day = time_of_day(parts.next()?)?;
+    let valid = LONG_WEEKDAYS.contains(&weekday)
+        && day_month_year.next().is_none()
+        && parts.next()? == "GMT"
+        && parts.next().is_none();
+    valid.then_some((year, month, day, secs_of_day))
+}
+
+/// `Sun Nov  6 08:49:37 1994`, the day is padded with a space but zeros are fine as well
+fn parse_asctime(date: &str) -> Option<DateParts> {
+    let mut parts = date.split_whitespace();
+    let weekday = parts.next()?;
+    let month = month(parts.next()?)?;
+    let day = parts.next()?;
+    let day = number(day, 1).or_else(|| number(day, 2))?;
+    let secs_of_day = time_of_day(parts.next()?)?;
+    let year = number(parts.next()?, 4)?;
+    let valid = WEEKDAYS.contains(&weekday) && parts.next().is_none();
+    valid.then_some((year, month, day, secs_of_day))
+}
+
+/// Exactly `len` ascii digits
+fn number(digits: &str, len: usize) -> Option<i64> {
+    if digits.len() != len || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn month(name: &str) -> Option<i64> {
+    let index = MONTHS.iter().position(|month| *month == name)?;
+    i64::try_from(index).ok().map(|index| index + 1)
+}
+
+/// `08:49:37` as seconds into the day, a leap second is let through
+fn time_of_day(time: &str) -> Option<i64> {
+    let mut parts = time.split(':');
+    let hours = number(parts.next()?, 2).filter(|hours| *hours < 24)?;
+    let minutes = number(parts.next()?, 2).filter(|minutes| *minutes < 60)?;
+    let seconds = number(parts.next()?, 2).filter(|seconds| *seconds <= 60)?;
+    parts
+        .next()
+        .is_none()
+        .then_some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// The year a two digit RFC 850 year stands for, the latest one at most 50 years after `now`
+fn full_year(two_digits: i64, now: SystemTime) -> i64 {
+    let days = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400);
+    let this_year = i64::try_from(civil_from_days(days).0).unwrap_or(1970);
+    let year = this_year - this_year % 100 + two_digits;
+    if year > this_year + 50 {
+        year - 100
+    } else if year + 100 <= this_year + 50 {
+        year + 100
+    } else {
+        year
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to the given day, the counterpart of [`civil_from_days`]
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = (month + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// A date [`parse_http_date`] couldn't make sense of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidHttpDate;
+
+impl std::fmt::Display for InvalidHttpDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid http date")
+    }
+}
+
+impl std::error::Error for InvalidHttpDate {}
@@ -0,0 +1,133 @@
+//! `Server-Timing` headers, which show up in the network tab of the browser's dev tools
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::time::Instant;
+//! use torus_http::{
+//!     middleware::server_timing::TotalTiming, prelude::*, testing::TestClient,
+//!     timing::ServerTiming,
+//! };
+//!
+//! let client = TestClient::new(
+//!     HttpServer::new()
+//!         .add_around_middleware(TotalTiming)
+//!         .get("/report", |_| {
+//!             let started = Instant::now();
+//!             // ... querying the database
+//!             let timing = ServerTiming::from_elapsed("db", started).metric("render", 3.1);
+//!             HttpResponse::new().set_body("report").with_server_timing(timing)
+//!         }),
+//! );
+//!
+//! let res = client.get("/report").send();
+//! let header = res.header("Server-Timing").unwrap();
+//! assert!(header.starts_with("db;dur="), "{header}");
+//! assert!(header.contains(", render;dur=3.1, total;dur="), "{header}");
+//! ```
+use std::{
+    fmt::{Display, Write},
+    time::Instant,
+};
+
+/// One named duration, in milliseconds
+#[derive(Debug, Clone, PartialEq)]
+struct Metric {
+    name: String,
+    duration: f64,
+    description: Option<String>,
+}
+
+/// The value of a `Server-Timing` header
+///
+/// ```rust
+/// use torus_http::timing::ServerTiming;
+///
+/// let timing = ServerTiming::new().metric("db", 12.5).metric("render", 3.0);
+/// assert_eq!(timing.to_string(), "db;dur=12.5, render;dur=3");
+///
+/// let timing = ServerTiming::new().metric_with_description("cache", 0.25, "redis \"hit\"");
+/// assert_eq!(timing.to_string(), r#"cache;dur=0.25;desc="redis \"hit\"""#);
+///
+/// // a quoted-string only escapes quotes and backslashes
+/// let timing = ServerTiming::new().metric_with_description("disk", 1.0, "C:\\tmp, café\t");
+/// assert_eq!(timing.to_string(), "disk;dur=1;desc=\"C:\\\\tmp, café\t\"");
+/// assert_eq!(ServerTiming::new().to_string(), "");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerTiming {
+    metrics: Vec<Metric>,
+}
+
+impl ServerTiming {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts with a metric for the time since `start`, rounded to microseconds
+    #[must_use]
+    pub fn from_elapsed(name: impl Into<String>, start: Instant) -> Self {
+        Self::new().elapsed(name, start)
+    }
+
+    /// Adds `name` having taken `duration` milliseconds
+    #[must_use]
+    pub fn metric(self, name: impl Into<String>, duration: f64) -> Self {
+        self.push(name.into(), duration, None)
+    }
+
+    /// [`ServerTiming::metric`] with a description shown instead of the name
+    #[must_use]
+    pub fn metric_with_description(
+        self,
+        name: impl Into<String>,
+        duration: f64,
+        description: impl Into<String>,
+    ) -> Self {
+        self.push(name.into(), duration, Some(description.into()))
+    }
+
+    /// Adds `name` having taken the time since `start`, rounded to microseconds
+    #[must_use]
+    pub fn elapsed(self, name: impl Into<String>, start: Instant) -> Self {
+        let micros = start.elapsed().as_secs_f64() * 1_000_000.0;
+        self.metric(name, micros.round() / 1000.0)
+    }
+
+    fn push(mut self, name: String, duration: f64, description: Option<String>) -> Self {
+        self.metrics.push(Metric {
+            name,
+            duration,
+            description,
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+}
+
+impl Display for ServerTiming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, metric) in self.metrics.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{};dur={}", metric.name, metric.duration)?;
+            if let Some(description) = &metric.description {
+                f.write_str(";desc=\"")?;
+                for c in description.chars() {
+                    if matches!(c, '"' | '\\') {
+                        f.write_char('\\')?;
+                    }
+                    f.write_char(c)?;
+                }
+                f.write_char('"')?;
+            }
+        }
+        Ok(())
+    }
+}
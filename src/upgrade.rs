@@ -0,0 +1,119 @@
+//! Escape hatch for switching protocols (websockets and friends)
+//!
+//! A handler attaches a callback to its (usually `101 Switching Protocols`) response with
+//! [`HttpResponse::on_upgrade`]. The server writes that response as usual, then stops treating
+//! the connection as http and hands the raw stream over to the callback.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use std::{io::{Read, Write}, net::TcpStream, thread};
+//! use torus_http::{prelude::*, status::InformationalResponse};
+//!
+//! let server = HttpServer::new()
+//!     .get("/echo", |_| {
+//!         HttpResponse::new()
+//!             .set_status(InformationalResponse::SwitchingProtocols.into())
+//!             .insert_header("Upgrade", "echo")
+//!             .insert_header("Connection", "Upgrade")
+//!             .on_upgrade(|mut conn| {
+//!                 let mut buf = [0; 64];
+//!                 while let Ok(n @ 1..) = conn.read(&mut buf) {
+//!                     _ = conn.write_all(&buf[..n]);
+//!                 }
+//!             })
+//!     })
+//!     .bind(("127.0.0.1", 0))
+//!     .unwrap();
+//! let port = server.port();
+//! thread::spawn(move || server.listen());
+//! let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+//!
+//! // bytes sent right behind the request must not get lost
+//! stream.write_all(b"GET /echo HTTP/1.1\r\nUpgrade: echo\r\n\r\nping").unwrap();
+//! let mut buf = [0; 256];
+//! let mut received = Vec::new();
+//! while !received.ends_with(b"ping") {
+//!     let n = stream.read(&mut buf).unwrap();
+//!     received.extend_from_slice(&buf[..n]);
+//! }
+//! assert!(received.starts_with(b"HTTP/1.1 101"));
+//!
+//! stream.write_all(b"pong").unwrap();
+//! let n = stream.read(&mut buf).unwrap();
+//! assert_eq!(&buf[..n], b"pong");
+//! ```
+//!
+//! [`HttpResponse::on_upgrade`]: crate::response::HttpResponse::on_upgrade
+use std::{
+    fmt::Debug,
+    io::{Read, Write},
+    net::TcpStream,
+    sync::{Arc, Mutex},
+};
+
+/// A connection that has been taken over from the http server
+///
+/// Reading first yields whatever the client already sent after the upgrade request, then
+/// continues with the underlying stream
+pub struct Upgraded {
+    pub stream: TcpStream,
+    /// Bytes that were read from the client after the upgrade request
+    pub buffered: Vec<u8>,
+}
+
+impl Read for Upgraded {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.buffered.is_empty() {
+            return self.stream.read(buf);
+        }
+        let n = self.buffered.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.buffered[..n]);
+        self.buffered.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for Upgraded {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+type UpgradeFn = Box<dyn FnOnce(Upgraded) + Send>;
+
+/// The callback attached to a response that takes over the connection
+///
+/// Responses get cloned on their way out of a handler, so the callback sits behind a shared
+/// slot and whoever takes it first gets to run it
+#[derive(Clone)]
+pub struct Upgrade(Arc<Mutex<Option<UpgradeFn>>>);
+
+impl Upgrade {
+    pub(crate) fn new(f: impl FnOnce(Upgraded) + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Some(Box::new(f)))))
+    }
+
+    /// Take the callback out, `None` if it already ran
+    pub(crate) fn take(&self) -> Option<UpgradeFn> {
+        self.0.lock().ok()?.take()
+    }
+}
+
+impl PartialEq for Upgrade {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Upgrade {}
+
+impl Debug for Upgrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Upgrade(..)")
+    }
+}
@@ -0,0 +1,20 @@
+//! Panicking handlers are reported through `tracing` together with the request they panicked on
+#![cfg(feature = "tracing")]
+
+use torus_http::{prelude::*, testing::TestClient};
+use tracing_test::traced_test;
+
+#[test]
+#[traced_test]
+fn handler_panics_are_logged_with_the_request() {
+    let client = TestClient::new(HttpServer::new().get("/users/:id", |req: HttpRequest| {
+        req.header("X-Api-Key").unwrap().to_owned()
+    }));
+
+    let res = client.get("/users/42").send();
+
+    assert_eq!(res.status, HttpStatus::INTERNAL_SERVER_ERROR);
+    assert!(logs_contain("handler panicked"));
+    assert!(logs_contain("request{method=GET path=/users/42"));
+    assert!(logs_contain("called `Option::unwrap()` on a `None` value"));
+}
@@ -0,0 +1,172 @@
+//! Behaviour of the server over real connections, things an in-process
+//! [`TestClient`](torus_http::testing::TestClient) can't see
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use torus_http::{prelude::*, server::POOL_QUEUE_PER_WORKER};
+
+/// Serves `server` on a free port in the background, returning the port
+fn serve(server: HttpServer) -> u16 {
+    let server = server.bind(("127.0.0.1", 0)).unwrap();
+    let port = server.port();
+    thread::spawn(move || server.listen());
+    port
+}
+
+/// Sends `request` on a connection of its own and reads until the server closes it
+fn send(port: u16, request: &[u8]) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.write_all(request).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+/// The most requests that were ever handled at the same time with `workers` threads
+fn overlap(workers: usize) -> usize {
+    let active = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let (active_in, most_in) = (active.clone(), most.clone());
+    let port = serve(HttpServer::new().workers(workers).get("/", move |_| {
+        let now = active_in.fetch_add(1, Ordering::SeqCst) + 1;
+        most_in.fetch_max(now, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(200));
+        active_in.fetch_sub(1, Ordering::SeqCst);
+        thread::current().name().unwrap_or_default().to_owned()
+    }));
+
+    let clients: Vec<_> = (0..2)
+        .map(|_| {
+            thread::spawn(move || {
+                let response = send(port, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n");
+                assert!(
+                    response.ends_with("\r\n\r\ntorus-worker-0")
+                        || response.ends_with("\r\n\r\ntorus-worker-1")
+                );
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+    most.load(Ordering::SeqCst)
+}
+
+#[test]
+fn workers_bound_concurrent_requests() {
+    assert_eq!(overlap(1), 1);
+    assert_eq!(overlap(2), 2);
+}
+
+#[test]
+fn workers_turn_away_a_full_queue() {
+    let (entered, wait_entered) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+    let (entered, released) = (Mutex::new(entered), Mutex::new(released));
+    let port = serve(HttpServer::new().workers(1).get("/", move |_| {
+        entered.lock().unwrap().send(()).unwrap();
+        _ = released.lock().unwrap().recv();
+        "done"
+    }));
+
+    let mut busy = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    busy.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    wait_entered.recv().unwrap();
+    let queued: Vec<_> = (0..POOL_QUEUE_PER_WORKER)
+        .map(|_| TcpStream::connect(("127.0.0.1", port)).unwrap())
+        .collect();
+    let mut turned_away = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut response = String::new();
+    turned_away.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 503"));
+
+    drop(queued);
+    release.send(()).unwrap();
+    let mut response = String::new();
+    busy.read_to_string(&mut response).unwrap();
+    assert!(response.ends_with("done"));
+}
+
+fn cycle_timeout_server() -> u16 {
+    serve(
+        HttpServer::new()
+            .with_cycle_timeout(Duration::from_millis(100))
+            .add_response_middleware(|_, res| res.insert_header("X-Seen", "yes"))
+            .get("/slow", |_| {
+                thread::sleep(Duration::from_secs(2));
+                "finally"
+            })
+            .get("/fast", |_| "quick")
+            .post("/upload", |_| "stored"),
+    )
+}
+
+#[test]
+fn cycle_timeout_answers_for_slow_handlers() {
+    let port = cycle_timeout_server();
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.write_all(b"GET /fast HTTP/1.1\r\n\r\n").unwrap();
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).unwrap();
+    assert!(buf[..n].starts_with(b"HTTP/1.1 200"));
+
+    let start = Instant::now();
+    stream.write_all(b"GET /slow HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 503"));
+    assert!(response.lines().any(|l| l == "Connection: close"));
+    assert!(response.lines().any(|l| l == "X-Seen: yes"));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn cycle_timeout_cuts_off_trickling_bodies() {
+    let port = cycle_timeout_server();
+
+    let start = Instant::now();
+    let response = send(
+        port,
+        b"POST /upload HTTP/1.1\r\nContent-Length: 100\r\n\r\nsome",
+    );
+    assert!(response.starts_with("HTTP/1.1 503"));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn max_url_length_past_the_head_limit() {
+    let port = serve(
+        HttpServer::new()
+            .with_max_url_length(32 * 1024)
+            .add_response_middleware(|req, res| {
+                let seen = format!("{} {}", req.method, req.path.len());
+                res.insert_header("X-Seen", seen)
+            })
+            .get("/**", |req: HttpRequest| req.path.len().to_string()),
+    );
+    let get = |target_len: usize| {
+        let request = format!(
+            "GET /{} HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "a".repeat(target_len - 1)
+        );
+        send(port, request.as_bytes())
+    };
+
+    assert!(get(20_000).ends_with("\r\n\r\n20000"));
+    let response = get(40_000);
+    assert!(response.starts_with("HTTP/1.1 414"));
+    assert!(response.lines().any(|line| line == "X-Seen: GET 32768"));
+    assert!(response.lines().any(|line| line == "Connection: close"));
+}